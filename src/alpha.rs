@@ -5,7 +5,7 @@
 // `freshen_rec` gets a value and its pattern ready for destructuring.
 
 use crate::{
-    ast::Ast::{self, *},
+    ast::{Ast, AstContents::*},
     name::*,
     util::{assoc::Assoc, mbe::EnvMBE},
 };
@@ -26,6 +26,16 @@ impl Ren {
             self.env_phaseless.find(&n)
         }
     }
+    /// What the reference `var` (to `n`) becomes. (Renaming it doesn't lose its location.)
+    fn replace(&self, n: Name, var: &Ast) -> Ast {
+        match self.find(n) {
+            Some(new) => match *new.c() {
+                VariableReference(new_n) => var.with_c(VariableReference(new_n)),
+                _ => new.clone(),
+            },
+            None => var.clone(),
+        }
+    }
     pub fn unset(self, n: Name) -> Ren {
         if self.q_lev == 0 {
             Ren {
@@ -71,25 +81,28 @@ impl From<Assoc<Name, Ast>> for Ren {
 }
 
 fn substitute_rec(node: &Ast, cur_node_contents: &EnvMBE<Ast>, env: &Ren) -> Ast {
-    match *node {
+    match *node.c() {
         Node(ref f, ref new_parts, ref export) => {
             // let new_cnc = parts.clone();
-            Node(
+            node.with_c(Node(
                 f.clone(),
                 new_parts.marched_map(&mut |_, marched_parts: &EnvMBE<Ast>, part: &Ast| {
                     substitute_rec(part, marched_parts, env)
                 }),
                 export.clone(),
-            )
+            ))
         }
-        VariableReference(n) => env.find(n).unwrap_or(&node.clone()).clone(),
+        VariableReference(n) => env.replace(n, node),
         ExtendEnv(ref body, ref beta) => {
             let mut new_env = env.clone();
             for bound_name in crate::beta::bound_from_beta(beta, cur_node_contents, 0) {
                 new_env = new_env.unset(bound_name);
             }
 
-            ExtendEnv(Box::new(substitute_rec(body, cur_node_contents, &new_env)), beta.clone())
+            node.with_c(ExtendEnv(
+                Box::new(substitute_rec(body, cur_node_contents, &new_env)),
+                beta.clone(),
+            ))
         }
         ExtendEnvPhaseless(ref body, ref beta) => {
             let mut new_env = env.clone();
@@ -97,16 +110,22 @@ fn substitute_rec(node: &Ast, cur_node_contents: &EnvMBE<Ast>, env: &Ren) -> Ast
                 new_env = new_env.unset(bound_name);
             }
 
-            ExtendEnvPhaseless(
+            node.with_c(ExtendEnvPhaseless(
                 Box::new(substitute_rec(body, cur_node_contents, &new_env)),
                 beta.clone(),
-            )
+            ))
         }
         QuoteMore(ref body, pos) => {
-            QuoteMore(Box::new(substitute_rec(body, cur_node_contents, &env.q_more(1))), pos)
+            node.with_c(QuoteMore(
+                Box::new(substitute_rec(body, cur_node_contents, &env.q_more(1))),
+                pos,
+            ))
         }
         QuoteLess(ref body, depth) => {
-            QuoteLess(Box::new(substitute_rec(body, cur_node_contents, &env.q_less(depth))), depth)
+            node.with_c(QuoteLess(
+                Box::new(substitute_rec(body, cur_node_contents, &env.q_less(depth))),
+                depth,
+            ))
         }
         _ => node.clone(),
     }
@@ -123,15 +142,21 @@ pub fn substitute(node: &Ast, env: &Assoc<Name, Ast>) -> Ast {
     substitute_rec(node, &EnvMBE::new(), &Ren::from(env.clone()))
 }
 
+/// Like `substitute`, but only affects the syntax that `node` quotes:
+///  `VariableReference`s under exactly one more level of quotation than `node` itself.
+pub fn substitute_quoted(node: &Ast, env: &Assoc<Name, Ast>) -> Ast {
+    substitute_rec(node, &EnvMBE::new(), &Ren { q_lev: -1, ..Ren::from(env.clone()) })
+}
+
 /// Like `beta::names_mentioned`, but for all the imports in `parts`
 fn mentioned_in_import(parts: &EnvMBE<Ast>) -> Vec<Name> {
     fn process_ast(a: &Ast, v: &mut Vec<Name>) {
-        match *a {
+        match *a.c() {
             Node(_, _, _) => {} // new scope
             ExtendEnv(ref body, ref beta) | ExtendEnvPhaseless(ref body, ref beta) => {
                 let mut beta_mentions = beta.names_mentioned_and_bound();
                 v.append(&mut beta_mentions);
-                process_ast(&*body, v);
+                process_ast(body, v);
             }
             // TODO: does it make sense to mention a name underneath a quotation?
             QuoteMore(ref body, _) | QuoteLess(ref body, _) => process_ast(body, v),
@@ -147,13 +172,13 @@ fn mentioned_in_import(parts: &EnvMBE<Ast>) -> Vec<Name> {
 
 fn freshen_rec(node: &Ast, renamings: &EnvMBE<(Ast, Ren)>, env: Ren) -> Ast {
     //  `env` is used to update the references to those atoms to match
-    match *node {
+    match *node.c() {
         Node(_, _, _) => substitute_rec(node, &EnvMBE::new(), &env),
-        VariableReference(n) => env.find(n).unwrap_or(&node.clone()).clone(),
+        VariableReference(n) => env.replace(n, node),
         ExtendEnv(ref body, ref beta) => {
             let new_env = env.set_assoc(&beta.extract_from_mbe(renamings, &|x: &(_, Ren)| &x.1));
 
-            ExtendEnv(Box::new(freshen_rec(body, renamings, new_env)), beta.clone())
+            node.with_c(ExtendEnv(Box::new(freshen_rec(body, renamings, new_env)), beta.clone()))
         }
         ExtendEnvPhaseless(ref body, ref beta) => {
             // Everything bound this way becomes phaseless.
@@ -161,43 +186,46 @@ fn freshen_rec(node: &Ast, renamings: &EnvMBE<(Ast, Ren)>, env: Ren) -> Ast {
                 &beta.extract_from_mbe(renamings, &|x: &(_, Ren)| &x.1).become_phaseless(),
             );
 
-            ExtendEnvPhaseless(Box::new(freshen_rec(body, renamings, new__env)), beta.clone())
+            node.with_c(ExtendEnvPhaseless(
+                Box::new(freshen_rec(body, renamings, new__env)),
+                beta.clone(),
+            ))
         }
         QuoteMore(ref body, pos) => {
-            QuoteMore(Box::new(freshen_rec(body, renamings, env.q_more(1))), pos)
+            node.with_c(QuoteMore(Box::new(freshen_rec(body, renamings, env.q_more(1))), pos))
         }
         QuoteLess(ref body, depth) => {
-            QuoteLess(Box::new(freshen_rec(body, renamings, env.q_less(depth))), depth)
+            node.with_c(QuoteLess(Box::new(freshen_rec(body, renamings, env.q_less(depth))), depth))
         }
         Atom(_) | Trivial | IncompleteNode(_) | Shape(_) => node.clone(),
     }
 }
 
 thread_local! {
-    pub static freshening_enabled: std::cell::RefCell<bool> = std::cell::RefCell::new(true);
+    pub static freshening_enabled: std::cell::RefCell<bool> = const { std::cell::RefCell::new(true) };
 }
 
 pub fn freshen(a: &Ast) -> Ast {
     // TODO: I think this shouldn't take a reference for performance
     if freshening_enabled.with(|f| *f.borrow()) {
-        match a {
-            &Node(ref f, ref p, ref export) => {
+        match *a.c() {
+            Node(ref f, ref p, ref export) => {
                 // Every part that gets mentioned inside this node...
                 let mentioned = mentioned_in_import(p);
                 // ...needs to have its binders freshend:
                 let fresh_ast_and_rens = freshen_binders_inside_node(p, &mentioned);
 
-                Node(
+                a.with_c(Node(
                     f.clone(),
                     fresh_ast_and_rens.marched_map(
-                        &mut |_, marched: &EnvMBE<(Ast, Ren)>, &(ref part, _)| {
+                        &mut |_, marched: &EnvMBE<(Ast, Ren)>, (part, _)| {
                             freshen_rec(part, marched, Ren::new())
                         },
                     ),
                     export.clone(),
-                )
+                ))
             }
-            non_node => non_node.clone(),
+            _ => a.clone(),
         }
     } else {
         a.clone()
@@ -206,8 +234,8 @@ pub fn freshen(a: &Ast) -> Ast {
 // TODO: verify that this handles internal `ExtendEnv`s right
 pub fn freshen_with(lhs: &Ast, rhs: &Ast) -> (Ast, Ast) {
     if freshening_enabled.with(|f| *f.borrow()) {
-        match (lhs, rhs) {
-            (&Node(ref f, ref p_lhs, ref export), &Node(ref f_rhs, ref p_rhs, ref export_rhs)) => {
+        match (lhs.c(), rhs.c()) {
+            (Node(f, p_lhs, export), Node(f_rhs, p_rhs, export_rhs)) => {
                 if f != f_rhs || export != export_rhs {
                     return (lhs.clone(), rhs.clone());
                 }
@@ -220,7 +248,7 @@ pub fn freshen_with(lhs: &Ast, rhs: &Ast) -> (Ast, Ast) {
                         let new_p_lhs = fresh_ast_and_rens.marched_map(
                             &mut |_,
                                   marched: &EnvMBE<(Ast, Ren, Ast, Ren)>,
-                                  &(ref parts, _, _, _)| {
+                                  (parts, _, _, _)| {
                                 freshen_rec(
                                     parts,
                                     &marched.map(&mut |q| (q.0.clone(), q.1.clone())),
@@ -231,7 +259,7 @@ pub fn freshen_with(lhs: &Ast, rhs: &Ast) -> (Ast, Ast) {
                         let new_p_rhs = fresh_ast_and_rens.marched_map(
                             &mut |_,
                                   marched: &EnvMBE<(Ast, Ren, Ast, Ren)>,
-                                  &(_, _, ref parts, _)| {
+                                  (_, _, parts, _)| {
                                 freshen_rec(
                                     parts,
                                     &marched.map(&mut |q| (q.2.clone(), q.3.clone())),
@@ -240,8 +268,8 @@ pub fn freshen_with(lhs: &Ast, rhs: &Ast) -> (Ast, Ast) {
                             },
                         );
                         (
-                            Node(f.clone(), new_p_lhs, export.clone()),
-                            Node(f.clone(), new_p_rhs, export.clone()),
+                            lhs.with_c(Node(f.clone(), new_p_lhs, export.clone())),
+                            rhs.with_c(Node(f.clone(), new_p_rhs, export.clone())),
                         )
                     }
                     None => (lhs.clone(), rhs.clone()), // No destructuring will be performed!
@@ -288,11 +316,11 @@ pub fn freshen_binders_inside_node_with(
 /// Returns an `Ast` like `a`, but with fresh `Atom`s
 ///  and a map to change references in the same manner
 pub fn freshen_binders(a: &Ast) -> (Ast, Ren) {
-    match *a {
+    match *a.c() {
         Trivial | VariableReference(_) => (a.clone(), Ren::new()),
         Atom(old_name) => {
             let new_name = old_name.freshen();
-            (Atom(new_name), Ren::single(old_name, VariableReference(new_name)))
+            (a.with_c(Atom(new_name)), Ren::single(old_name, raw_ast!(VariableReference(new_name))))
         }
         Node(ref f, ref parts, ref export) => {
             if export == &crate::beta::ExportBeta::Nothing {
@@ -301,52 +329,159 @@ pub fn freshen_binders(a: &Ast) -> (Ast, Ren) {
             let exported = export.names_mentioned(); // Unmentioned atoms shouldn't be touched
 
             let fresh_pairs = freshen_binders_inside_node(parts, &exported);
-            let fresh_ast = fresh_pairs.map(&mut |&(ref a, _): &(Ast, _)| a.clone());
-            let renaming = export.extract_from_mbe(&fresh_pairs, &|&(_, ref r): &(_, Ren)| &r);
+            let fresh_ast = fresh_pairs.map(&mut |(a, _): &(Ast, _)| a.clone());
+            let renaming = export.extract_from_mbe(&fresh_pairs, &|(_, r): &(_, Ren)| r);
 
-            (Node(f.clone(), fresh_ast, export.clone()), renaming)
+            (a.with_c(Node(f.clone(), fresh_ast, export.clone())), renaming)
         }
         IncompleteNode(_) | Shape(_) => icp!("didn't think this was needed"),
         QuoteMore(ref body, pos) => {
-            let (a, r) = freshen_binders(body);
-            (QuoteMore(Box::new(a), pos), r.q_less(1))
+            let (new_body, r) = freshen_binders(body);
+            (a.with_c(QuoteMore(Box::new(new_body), pos)), r.q_less(1))
         }
         QuoteLess(ref body, depth) => {
-            let (a, r) = freshen_binders(body);
-            (QuoteLess(Box::new(a), depth), r.q_more(depth))
+            let (new_body, r) = freshen_binders(body);
+            (a.with_c(QuoteLess(Box::new(new_body), depth)), r.q_more(depth))
         }
         ExtendEnv(ref sub, ref beta) => {
             // We're only looking at `Atom`s, so this is transparent
-            let (new_sub, subst) = freshen_binders(&*sub);
-            (ExtendEnv(Box::new(new_sub), beta.clone()), subst)
+            let (new_sub, subst) = freshen_binders(sub);
+            (a.with_c(ExtendEnv(Box::new(new_sub), beta.clone())), subst)
         }
         ExtendEnvPhaseless(ref sub, ref beta) => {
             // We're only looking at `Atom`s, so this is transparent
-            let (new_sub, subst) = freshen_binders(&*sub);
-            (ExtendEnvPhaseless(Box::new(new_sub), beta.clone()), subst.become_phaseless())
+            let (new_sub, subst) = freshen_binders(sub);
+            (
+                a.with_c(ExtendEnvPhaseless(Box::new(new_sub), beta.clone())),
+                subst.become_phaseless(),
+            )
+        }
+    }
+}
+
+/// Are `lhs` and `rhs` the same, up to the names of their binders?
+pub fn alpha_equiv(lhs: &Ast, rhs: &Ast) -> bool {
+    match (lhs.c(), rhs.c()) {
+        (Node(f, _, export), Node(f_rhs, _, export_rhs)) => {
+            if f != f_rhs || export != export_rhs {
+                return false;
+            }
+            // Give corresponding binders the same fresh names:
+            let (lhs, rhs) = freshen_with(lhs, rhs);
+            match (lhs.c(), rhs.c()) {
+                (Node(_, parts_lhs, _), Node(_, parts_rhs, _)) => {
+                    parts_lhs.can_map_with(parts_rhs)
+                        && parts_lhs.map_reduce_with(
+                            parts_rhs,
+                            &alpha_equiv,
+                            &|a, b| *a && *b,
+                            true,
+                        )
+                }
+                _ => false,
+            }
+        }
+        (&ExtendEnv(ref body, ref beta), &ExtendEnv(ref body_rhs, ref beta_rhs))
+        | (
+            &ExtendEnvPhaseless(ref body, ref beta),
+            &ExtendEnvPhaseless(ref body_rhs, ref beta_rhs),
+        ) => beta == beta_rhs && alpha_equiv(body, body_rhs),
+        (&QuoteMore(ref body, pos), &QuoteMore(ref body_rhs, pos_rhs)) => {
+            pos == pos_rhs && alpha_equiv(body, body_rhs)
+        }
+        (&QuoteLess(ref body, depth), &QuoteLess(ref body_rhs, depth_rhs)) => {
+            depth == depth_rhs && alpha_equiv(body, body_rhs)
+        }
+        _ => lhs == rhs,
+    }
+}
+
+/// Rebuild `a`, applying `f` to every name in an `Atom` or `VariableReference`.
+pub(crate) fn map_names(a: &Ast, f: &mut dyn FnMut(Name) -> Name) -> Ast {
+    match *a.c() {
+        Atom(name) => a.with_c(Atom(f(name))),
+        VariableReference(name) => a.with_c(VariableReference(f(name))),
+        Node(ref form, ref parts, ref export) => {
+            a.with_c(Node(form.clone(), parts.map(&mut |p| map_names(p, f)), export.clone()))
+        }
+        Shape(ref v) => a.with_c(Shape(v.iter().map(|p| map_names(p, f)).collect())),
+        IncompleteNode(ref parts) => a.with_c(IncompleteNode(parts.map(&mut |p| map_names(p, f)))),
+        ExtendEnv(ref body, ref beta) => {
+            a.with_c(ExtendEnv(Box::new(map_names(body, f)), beta.clone()))
+        }
+        ExtendEnvPhaseless(ref body, ref beta) => {
+            a.with_c(ExtendEnvPhaseless(Box::new(map_names(body, f)), beta.clone()))
         }
+        QuoteMore(ref body, pos) => a.with_c(QuoteMore(Box::new(map_names(body, f)), pos)),
+        QuoteLess(ref body, depth) => a.with_c(QuoteLess(Box::new(map_names(body, f)), depth)),
+        Trivial => a.clone(),
     }
 }
 
+/// `freshen`, all the way down: afterwards, no two binders have the same name.
+fn freshen_deeply(a: &Ast) -> Ast {
+    match *a.c() {
+        Node(_, _, _) => {
+            let fresh = freshen(a);
+            match *fresh.c() {
+                Node(ref form, ref parts, ref export) => fresh.with_c(Node(
+                    form.clone(),
+                    parts.map(&mut freshen_deeply),
+                    export.clone(),
+                )),
+                _ => icp!("freshening changed the shape of {}", a),
+            }
+        }
+        ExtendEnv(ref body, ref beta) => {
+            a.with_c(ExtendEnv(Box::new(freshen_deeply(body)), beta.clone()))
+        }
+        ExtendEnvPhaseless(ref body, ref beta) => {
+            a.with_c(ExtendEnvPhaseless(Box::new(freshen_deeply(body)), beta.clone()))
+        }
+        QuoteMore(ref body, pos) => a.with_c(QuoteMore(Box::new(freshen_deeply(body)), pos)),
+        QuoteLess(ref body, depth) => a.with_c(QuoteLess(Box::new(freshen_deeply(body)), depth)),
+        _ => a.clone(),
+    }
+}
+
+/// `a`, with its binders renamed by order of appearance (like de Bruijn indices),
+///  so that two `Ast`s are alpha-equivalent exactly when their canonical forms are equal.
+pub fn canonical_binders(a: &Ast) -> Ast {
+    let mut free = std::collections::HashSet::new();
+    map_names(a, &mut |name| {
+        free.insert(name);
+        name
+    });
+    // Freshening renames only binders, so any name we didn't start with is one:
+    let mut canonical = std::collections::HashMap::new();
+    map_names(&freshen_deeply(a), &mut |name| {
+        if free.contains(&name) {
+            return name;
+        }
+        let next = canonical.len();
+        *canonical.entry(name).or_insert_with(|| n(&format!("⟦{}⟧", next)))
+    })
+}
+
 /// Like `freshen_binders`, but to unite two `Ast`s with identical structure (else returns `None`).
 pub fn freshen_binders_with(lhs: &Ast, rhs: &Ast) -> Option<(Ast, Ren, Ast, Ren)> {
-    match (lhs, rhs) {
+    match (lhs.c(), rhs.c()) {
         (&Trivial, &Trivial) | (&VariableReference(_), &VariableReference(_)) => {
             Some((lhs.clone(), Ren::new(), rhs.clone(), Ren::new()))
         }
         (&Atom(old_name_lhs), &Atom(old_name_rhs)) => {
             let new_name = old_name_lhs.freshen();
             Some((
-                Atom(new_name),
-                Ren::single(old_name_lhs, VariableReference(new_name)),
-                Atom(new_name),
-                Ren::single(old_name_rhs, VariableReference(new_name)),
+                lhs.with_c(Atom(new_name)),
+                Ren::single(old_name_lhs, raw_ast!(VariableReference(new_name))),
+                rhs.with_c(Atom(new_name)),
+                Ren::single(old_name_rhs, raw_ast!(VariableReference(new_name))),
             ))
         }
         // TODO: Handle matching `'[let (a,b) = ⋯]'` against the pattern `'[let ,[p], = ⋯]'` !!
         (
-            &Node(ref f, ref parts_lhs, ref export),
-            &Node(ref f_rhs, ref parts_rhs, ref export_rhs),
+            Node(f, parts_lhs, export),
+            Node(f_rhs, parts_rhs, export_rhs),
         ) => {
             if f != f_rhs || export != export_rhs {
                 return None;
@@ -360,14 +495,14 @@ pub fn freshen_binders_with(lhs: &Ast, rhs: &Ast) -> Option<(Ast, Ren, Ast, Ren)
 
             match freshen_binders_inside_node_with(parts_lhs, parts_rhs, &exported) {
                 Some(fresh_pairs) => {
-                    let fresh_ast_lhs = fresh_pairs.map(&mut |&(ref a, _, _, _)| a.clone());
-                    let fresh_ast_rhs = fresh_pairs.map(&mut |&(_, _, ref a, _)| a.clone());
+                    let fresh_ast_lhs = fresh_pairs.map(&mut |(a, _, _, _)| a.clone());
+                    let fresh_ast_rhs = fresh_pairs.map(&mut |(_, _, a, _)| a.clone());
                     let ren_lhs = export.extract_from_mbe(&fresh_pairs, &|t: &(_, Ren, _, _)| &t.1);
                     let ren_rhs = export.extract_from_mbe(&fresh_pairs, &|t: &(_, _, _, Ren)| &t.3);
                     Some((
-                        Node(f.clone(), fresh_ast_lhs, export.clone()),
+                        lhs.with_c(Node(f.clone(), fresh_ast_lhs, export.clone())),
                         ren_lhs,
-                        Node(f.clone(), fresh_ast_rhs, export.clone()),
+                        rhs.with_c(Node(f.clone(), fresh_ast_rhs, export.clone())),
                         ren_rhs,
                     ))
                 }
@@ -375,28 +510,22 @@ pub fn freshen_binders_with(lhs: &Ast, rhs: &Ast) -> Option<(Ast, Ren, Ast, Ren)
             }
         }
         (&QuoteMore(ref body_lhs, pos), &QuoteMore(ref body_rhs, pos_rhs)) if pos == pos_rhs => {
-            match freshen_binders_with(&*body_lhs, &*body_rhs) {
-                Some((n_lhs, ren_lhs, n_rhs, ren_rhs)) => Some((
-                    QuoteMore(Box::new(n_lhs), pos),
+            freshen_binders_with(body_lhs, body_rhs).map(|(n_lhs, ren_lhs, n_rhs, ren_rhs)| (
+                    lhs.with_c(QuoteMore(Box::new(n_lhs), pos)),
                     ren_lhs.q_less(1),
-                    QuoteMore(Box::new(n_rhs), pos),
+                    rhs.with_c(QuoteMore(Box::new(n_rhs), pos)),
                     ren_rhs.q_less(1),
-                )),
-                None => None,
-            }
+                ))
         }
         (&QuoteLess(ref body_lhs, depth), &QuoteLess(ref body_rhs, depth_rhs))
             if depth == depth_rhs =>
         {
-            match freshen_binders_with(&*body_lhs, &*body_rhs) {
-                Some((n_lhs, ren_lhs, n_rhs, ren_rhs)) => Some((
-                    QuoteLess(Box::new(n_lhs), depth),
+            freshen_binders_with(body_lhs, body_rhs).map(|(n_lhs, ren_lhs, n_rhs, ren_rhs)| (
+                    lhs.with_c(QuoteLess(Box::new(n_lhs), depth)),
                     ren_lhs.q_more(depth),
-                    QuoteLess(Box::new(n_rhs), depth),
+                    rhs.with_c(QuoteLess(Box::new(n_rhs), depth)),
                     ren_rhs.q_more(depth),
-                )),
-                None => None,
-            }
+                ))
         }
         (&IncompleteNode(_), _) | (&Shape(_), _) => icp!("didn't think this was needed"),
         (&ExtendEnv(ref sub_lhs, ref beta), &ExtendEnv(ref sub_rhs, ref beta_rhs))
@@ -407,20 +536,15 @@ pub fn freshen_binders_with(lhs: &Ast, rhs: &Ast) -> Option<(Ast, Ren, Ast, Ren)
             if beta != beta_rhs {
                 return None;
             }
-            let ee = |a: Ast| -> Ast {
-                if let ExtendEnv(_, _) = lhs {
-                    ExtendEnv(Box::new(a), beta.clone())
+            let ee = |orig: &Ast, a: Ast| -> Ast {
+                if let ExtendEnv(_, _) = lhs.c() {
+                    orig.with_c(ExtendEnv(Box::new(a), beta.clone()))
                 } else {
-                    ExtendEnvPhaseless(Box::new(a), beta.clone())
+                    orig.with_c(ExtendEnvPhaseless(Box::new(a), beta.clone()))
                 }
             };
             // We're only looking at `Atom`s, so this is transparent
-            match freshen_binders_with(&*sub_lhs, &*sub_rhs) {
-                Some((n_lhs, ren_lhs, n_rhs, ren_rhs)) => {
-                    Some((ee(n_lhs), ren_lhs, ee(n_rhs), ren_rhs))
-                }
-                None => None,
-            }
+            freshen_binders_with(sub_lhs, sub_rhs).map(|(n_lhs, ren_lhs, n_rhs, ren_rhs)| (ee(lhs, n_lhs), ren_lhs, ee(rhs, n_rhs), ren_rhs))
         }
         _ => None, // Match failure
     }
@@ -700,3 +824,25 @@ fn alpha_quote_more_or_less() {
                                                  "rand" => [(vr "a🍅"), (vr "b🍅")]})))})
     );
 }
+
+#[test]
+fn alpha_equivalence() {
+    let lam = |params: &[&str], refs: &[&str]| {
+        ast!({"Expr" "lambda" :
+            "param" => (,seq params.iter().map(|p| ast!(*p)).collect::<Vec<_>>()),
+            "body" => (import [* ["param" : "[ignored]"]]
+                {"Expr" "apply" : "rator" => (vr "f"),
+                 "rand" => (,seq refs.iter().map(|r| ast!((vr *r))).collect::<Vec<_>>())})})
+    };
+    assert!(alpha_equiv(&lam(&["a", "b"], &["a", "b"]), &lam(&["x", "y"], &["x", "y"])));
+    assert!(alpha_equiv(&lam(&["a", "b"], &["b", "c"]), &lam(&["x", "y"], &["y", "c"])));
+    assert!(!alpha_equiv(&lam(&["a", "b"], &["a", "b"]), &lam(&["x", "y"], &["y", "x"])));
+    // Free variables have to match:
+    assert!(!alpha_equiv(&lam(&["a"], &["a", "c"]), &lam(&["x"], &["x", "d"])));
+    assert!(!alpha_equiv(&lam(&["a"], &["a"]), &lam(&["a", "b"], &["a"])));
+
+    let canon = |a: &Ast| format!("{:?}", canonical_binders(a));
+    assert_eq!(canon(&lam(&["a", "b"], &["b", "c"])), canon(&lam(&["x", "y"], &["y", "c"])));
+    assert_ne!(canon(&lam(&["a", "b"], &["a", "b"])), canon(&lam(&["x", "y"], &["y", "x"])));
+    assert_ne!(canon(&lam(&["a"], &["a", "c"])), canon(&lam(&["x"], &["x", "d"])));
+}
@@ -8,13 +8,12 @@ use crate::{
     beta::{Beta, ExportBeta},
     form::Form,
     name::*,
-    util::mbe::EnvMBE,
+    util::{mbe::EnvMBE, source::Span},
 };
 use std::{fmt, iter};
 
-// TODO: This really ought to be an `Rc` around an `enum`
 #[derive(Clone, PartialEq)]
-pub enum Ast {
+pub enum AstContents {
     Trivial,
     /// Typically, a binder
     Atom(Name),
@@ -41,6 +40,24 @@ pub enum Ast {
     ExtendEnvPhaseless(Box<Ast>, Beta),
 }
 
+/// An `AstContents`, plus where it came from (if it came from source code).
+/// Locations are ignored by `==`: two `Ast`s are equal if they have the same contents.
+#[derive(Clone)]
+pub struct Ast(pub std::rc::Rc<LocatedAst>);
+
+#[derive(Clone)]
+pub struct LocatedAst {
+    pub c: AstContents,
+    pub loc: Option<Span>,
+    /// Tells apart occurrences that have the same contents and location
+    ///  (macro expansion makes plenty of those). See `Ast::identified`.
+    pub id: Option<usize>,
+}
+
+impl PartialEq for Ast {
+    fn eq(&self, other: &Ast) -> bool { self.c() == other.c() }
+}
+
 // Reification macros would totally work for this,
 //  but it's worth having a special case in `Value` in order to make this faster.
 impl crate::runtime::reify::Reifiable for Ast {
@@ -51,13 +68,24 @@ impl crate::runtime::reify::Reifiable for Ast {
     }
 
     fn reflect(v: &crate::runtime::eval::Value) -> Ast {
-        extract!((v) crate::runtime::eval::Value::AbstractSyntax = (ref ast) => (ast.clone()))
+        extract!((v) crate::runtime::eval::Value::AbstractSyntax = (ref ast) => ast.clone())
+    }
+
+    fn try_reflect(v: &crate::runtime::eval::Value) -> Option<Ast> {
+        match *v {
+            crate::runtime::eval::Value::AbstractSyntax(ref ast) => Some(ast.clone()),
+            _ => None,
+        }
     }
 }
 
-pub use self::Ast::*;
+pub use self::AstContents::*;
 
 impl fmt::Debug for Ast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { self.c().fmt(f) }
+}
+
+impl fmt::Debug for AstContents {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Trivial => write!(f, "⨉"),
@@ -101,7 +129,7 @@ impl fmt::Debug for Ast {
 // Warning: this assumes the core language! To properly display an `Ast`, you need the `SynEnv`.
 impl fmt::Display for Ast {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
+        match *self.c() {
             Atom(ref n) => write!(f, "{}", n.print()),
             VariableReference(ref v) => write!(f, "{}", v.print()),
             Node(ref form, ref body, _) => {
@@ -132,9 +160,66 @@ impl fmt::Display for Ast {
 }
 
 impl Ast {
+    pub fn new(c: AstContents) -> Ast {
+        Ast(std::rc::Rc::new(LocatedAst { c, loc: None, id: None }))
+    }
+
+    pub fn c(&self) -> &AstContents { &self.0.c }
+
+    /// Where in the source this came from, if anywhere.
+    pub fn loc(&self) -> Option<Span> { self.0.loc.clone() }
+
+    pub fn with_loc(&self, loc: Option<Span>) -> Ast {
+        Ast(std::rc::Rc::new(LocatedAst { c: self.c().clone(), loc, id: self.0.id }))
+    }
+
+    /// Replace the contents, but keep the location and id (for rewriting an `Ast` in place).
+    pub fn with_c(&self, c: AstContents) -> Ast {
+        Ast(std::rc::Rc::new(LocatedAst { c, loc: self.loc(), id: self.0.id }))
+    }
+
+    /// The id given to this occurrence by `identified`, if any.
+    pub fn id(&self) -> Option<usize> { self.0.id }
+
+    /// A copy of this `Ast` with an id unlike any other's.
+    /// Rewriting it with `with_c` (as freshening does) keeps the id.
+    pub fn identified(&self) -> Ast {
+        static next_id: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let id = next_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Ast(std::rc::Rc::new(LocatedAst { c: self.c().clone(), loc: self.loc(), id: Some(id) }))
+    }
+
+    /// The location of this `Ast`, or else of the first located thing inside it.
+    pub fn some_loc(&self) -> Option<Span> {
+        if let Some(loc) = self.loc() {
+            return Some(loc);
+        }
+        match *self.c() {
+            Trivial | Atom(_) | VariableReference(_) => None,
+            Shape(ref v) => v.iter().filter_map(Ast::some_loc).next(),
+            Node(_, ref body, _) | IncompleteNode(ref body) => body.map_reduce(
+                &|a: &Ast| a.some_loc(),
+                &|l: &Option<Span>, r| l.clone().or_else(|| r.clone()),
+                None,
+            ),
+            QuoteMore(ref body, _)
+            | QuoteLess(ref body, _)
+            | ExtendEnv(ref body, _)
+            | ExtendEnvPhaseless(ref body, _) => body.some_loc(),
+        }
+    }
+
+    /// `file:line:col`, if we know it; otherwise, the pretty-printed `Ast` itself.
+    pub fn describe_location(&self) -> String {
+        match self.some_loc() {
+            Some(span) => format!("{}", span),
+            None => format!("{}", self),
+        }
+    }
+
     // TODO: this ought to at least warn if we're losing anything other than `Shape`
     pub fn flatten(&self) -> EnvMBE<Ast> {
-        match *self {
+        match *self.c() {
             Trivial | Atom(_) => EnvMBE::new(),
             VariableReference(_) => EnvMBE::new(),
             Shape(ref v) => {
@@ -162,7 +247,7 @@ impl Ast {
         expd_form: std::rc::Rc<Form>,
     ) -> Option<crate::util::mbe::EnvMBE<Ast>>
     {
-        if let Node(ref f, ref parts, _) = self {
+        if let Node(ref f, ref parts, _) = self.c() {
             if f == &expd_form {
                 return Some(parts.clone());
             }
@@ -172,20 +257,20 @@ impl Ast {
 
     // TODO: I think we have a lot of places where we ought to use this function:
     pub fn node_parts(&self) -> &EnvMBE<Ast> {
-        match *self {
+        match *self.c() {
             Node(_, ref body, _) => body,
             _ => icp!(),
         }
     }
     pub fn node_form(&self) -> &Form {
-        match *self {
+        match *self.c() {
             Node(ref form, _, _) => form,
             _ => icp!(),
         }
     }
 
     pub fn free_vrs(&self) -> Vec<Name> {
-        match *self {
+        match *self.c() {
             Trivial | Atom(_) => vec![],
             VariableReference(v) => vec![v],
             Shape(_) | IncompleteNode(_) => unimplemented!("TODO"),
@@ -210,7 +295,9 @@ impl Ast {
 // This is used by combine::many, which is used by the Star parser
 impl iter::FromIterator<Ast> for Ast {
     fn from_iter<I: IntoIterator<Item = Ast>>(i: I) -> Self {
-        IncompleteNode(EnvMBE::new_from_anon_repeat(i.into_iter().map(|a| a.flatten()).collect()))
+        raw_ast!(IncompleteNode(EnvMBE::new_from_anon_repeat(
+            i.into_iter().map(|a| a.flatten()).collect()
+        )))
     }
 }
 
@@ -236,7 +323,7 @@ fn combine_from_kleene_star() {
              "b" => [@"triple" "8.0", "8.1", "8.2"]);
     expected_mbe.anonimize_repeat(n("triple"));
 
-    assert_eq!(parsed, IncompleteNode(expected_mbe));
+    assert_eq!(parsed, raw_ast!(IncompleteNode(expected_mbe)));
 }
 
 #[test]
@@ -264,3 +351,14 @@ fn mbe_r_and_r_roundtrip() {
     let mbe1 = mbe!( "a" => [@"duo" "1", "2"], "b" => [@"duo" "11", "22"]);
     assert_eq!(mbe1, EnvMBE::<Ast>::reflect(&mbe1.reify()));
 }
+
+#[test]
+fn locations_do_not_affect_equality() {
+    let file = crate::util::source::SourceFile::new("eq.≉", "x y");
+    let here = Some(Span { file: file.clone(), begin: 0, end: 1 });
+    let there = Some(Span { file, begin: 2, end: 3 });
+
+    assert_eq!(ast!((vr "x")).with_loc(here.clone()), ast!((vr "x")).with_loc(there));
+    assert_eq!(ast!((vr "x")).with_loc(here.clone()).with_c(Atom(n("y"))).loc(), here);
+    assert_eq!(ast!({ - "a" => (vr "x") }).with_loc(here.clone()).some_loc(), here);
+}
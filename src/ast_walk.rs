@@ -59,7 +59,7 @@
 //  because all syntax should be constructable and matchable.
 
 use crate::{
-    ast::Ast::{self, *},
+    ast::{Ast, AstContents::*},
     beta::*,
     name::*,
     runtime::{eval, reify},
@@ -81,8 +81,8 @@ impl<Elt: WalkElt> Clo<Elt> {
         // we cut out the bits of the environments that are the same.
         let o_different_env = other.env.cut_common(&self.env);
 
-        let o_renaming =
-            o_different_env.keyed_map_borrow_f(&mut |name, _| VariableReference(name.freshen()));
+        let o_renaming = o_different_env
+            .keyed_map_borrow_f(&mut |name, _| raw_ast!(VariableReference(name.freshen())));
 
         let mut fresh_o_env = Assoc::new();
         for (o_name, o_val) in o_different_env.iter_pairs() {
@@ -102,8 +102,8 @@ impl<Elt: WalkElt> Clo<Elt> {
 
 thread_local! {
     // Tuple elements are (layers deep, number of steps taken).
-    pub static ast_walk_layer: RefCell<(u32, u32)> = RefCell::new((0, 0));
-    pub static ld_enabled: bool = std::env::var(&"UNSEEMLY_TRACE").map(|t| t == "full") == Ok(true);
+    pub static ast_walk_layer: RefCell<(u32, u32)> = const { RefCell::new((0, 0)) };
+    pub static ld_enabled: bool = std::env::var("UNSEEMLY_TRACE").map(|t| t == "full") == Ok(true);
 }
 
 /// Make a `<Mode::D as Dir>::Out` by walking `node` in the environment from `walk_ctxt`.
@@ -118,7 +118,7 @@ pub fn walk<Mode: WalkMode>(
         // TODO: can we get rid of the & in front of our arguments and save the cloning?
         // TODO: this has a lot of direction-specific runtime hackery.
         //  Maybe we want separate positive and negative versions?
-        let (a, walk_ctxt) = match *a {
+        let (a, walk_ctxt) = match *a.c() {
           // HACK: We want to process EE before pre_match before everything else.
           // This probably means we should find a way to get rid of pre_match.
           // But we can't just swap `a` and the ctxt when `a` is LiteralLike and the ctxt isn't.
@@ -134,9 +134,9 @@ pub fn walk<Mode: WalkMode>(
         // lc!(ast_walk_layer, ld_enabled, " in: {}", walk_ctxt.env/*.map_borrow_f(&mut |_| "…")*/);
 
         let literally : Option<bool> = // If we're under a wrapper, `this_ast` might not be a Node
-            match a {
+            match a.c() {
                 QuoteMore(_,_) | QuoteLess(_,_) | ExtendEnv(_,_) | ExtendEnvPhaseless(_,_) => {
-                    match walk_ctxt.this_ast {
+                    match walk_ctxt.this_ast.c() {
                         // `this_ast` might be `NotWalked` (and non-literal) if under `switch_mode`.
                         // It's weird, but seems to be the right thing
                         Node(ref f, _, _) => Some(Mode::get_walk_rule(f).is_literally()),
@@ -146,7 +146,7 @@ pub fn walk<Mode: WalkMode>(
                 _ => None
             };
 
-        match a {
+        match *a.c() {
             Node(ref f, ref parts, _) => {
                 let new_walk_ctxt = walk_ctxt.switch_ast(parts, a.clone());
                 // certain walks only work on certain kinds of AST nodes
@@ -159,7 +159,9 @@ pub fn walk<Mode: WalkMode>(
             }
             IncompleteNode(ref parts) => { icp!("{:#?} isn't a complete node", parts)}
 
-            VariableReference(n) => { Mode::walk_var(n, &walk_ctxt) }
+            VariableReference(n) => {
+                Mode::walk_var(n, &LazyWalkReses { this_ast: a.clone(), ..walk_ctxt.clone() })
+            }
             Atom(n) => { Mode::walk_atom(n, &walk_ctxt) }
 
             // TODO: we need to preserve these in LiteralLike contexts!!
@@ -230,13 +232,13 @@ pub fn walk<Mode: WalkMode>(
             }
 
             ExtendEnv(ref body, ref beta) | ExtendEnvPhaseless(ref body, ref beta) => {
-                let phaseless = match a { ExtendEnvPhaseless(_,_) => true, _ => false };
+                let phaseless = matches!(a.c(), ExtendEnvPhaseless(_,_));
 
                 fn extract__ee_body<Mode: WalkMode>(e: <Mode as WalkMode>::Elt)
                         -> <Mode as WalkMode>::Elt {
-                    match e.to_ast() {
+                    match *e.to_ast().c() {
                         ExtendEnv(ref body, _) | ExtendEnvPhaseless(ref body, _) => {
-                            <Mode as WalkMode>::Elt::from_ast(&*body)
+                            <Mode as WalkMode>::Elt::from_ast(body)
                         }
                         _ => { e } // Match will fail
                     }
@@ -265,7 +267,7 @@ pub fn walk<Mode: WalkMode>(
                 // The context element is sometimes leftover from a previous negative walk.
                     new__walk_ctxt.with_context(extract__ee_body::<Mode>(
                         walk_ctxt.env.find(&negative_ret_val()).unwrap_or(
-                            &<Mode as WalkMode>::Elt::from_ast(&Trivial)).clone()));
+                            &<Mode as WalkMode>::Elt::from_ast(&raw_ast!(Trivial))).clone()));
 
                 maybe_literally__walk(&a, body, new__walk_ctxt,
                     walk_ctxt.maybe__context_elt().map(extract__ee_body::<Mode>), literally)
@@ -291,7 +293,7 @@ fn maybe_literally__walk<Mode: WalkMode>(
     if literally.expect("ICP: unable to determine literalness") {
         Mode::walk_quasi_literally(a.clone(), &walk_ctxt)
     } else {
-        walk(&*body, &walk_ctxt)
+        walk(body, &walk_ctxt)
     }
 }
 
@@ -302,7 +304,8 @@ pub enum WalkRule<Mode: WalkMode> {
     ///  to the type/value of this form.
     /// The environment is accessible via the `LazyWalkReses`.
     /// Any of the other `WalkRule`s can be implemented as a simple `Custom`.
-    Custom(Rc<Box<(dyn Fn(LazyWalkReses<Mode>) -> Result<<Mode::D as Dir>::Out, Mode::Err>)>>),
+    #[allow(clippy::type_complexity)] // (the complexity is all `Mode`'s associated types)
+    Custom(Rc<Box<dyn Fn(LazyWalkReses<Mode>) -> Result<<Mode::D as Dir>::Out, Mode::Err>>>),
     /// "this form has the same type/value as one of its subforms".
     /// (useful for forms that only exist as wrapper s around other AST nodes)
     Body(Name),
@@ -314,12 +317,7 @@ pub enum WalkRule<Mode: WalkMode> {
 }
 
 impl<Mode: WalkMode> WalkRule<Mode> {
-    fn is_literally(&self) -> bool {
-        match self {
-            LiteralLike => true,
-            _ => false,
-        }
-    }
+    fn is_literally(&self) -> bool { matches!(self, LiteralLike) }
 }
 
 // trait bounds on parameters and functions are not yet supported by `Reifiable!`
@@ -346,7 +344,12 @@ impl<Mode: WalkMode + Copy + 'static> reify::Reifiable for WalkRule<Mode> {
         } else if choice.is("Body") {
             WalkRule::Body(Name::reflect(&parts[0]))
         } else if choice.is("Custom") {
-            WalkRule::Custom(reify::reflect_1ary_function(parts[0].clone()))
+            // Walk rules are always Rust functions (reified, and now reflected back);
+            //  those can't fail outside of `Mode::Err`.
+            let f = reify::reflect_1ary_function::<_, Result<_, Mode::Err>>(parts[0].clone());
+            WalkRule::Custom(Rc::new(Box::new(move |lwr| {
+                f(lwr).unwrap_or_else(|e| icp!("walk rule failed: {}", e))
+            })))
         } else if choice.is("LiteralLike") {
             WalkRule::LiteralLike
         } else {
@@ -375,6 +378,7 @@ pub type ResEnv<Elt> = Assoc<Name, Elt>;
 #[derive(Debug)]
 pub struct LazilyWalkedTerm<Mode: WalkMode> {
     pub term: Ast,
+    #[allow(clippy::type_complexity)] // (the complexity is all `Mode`'s associated types)
     pub res: RefCell<Option<Result<<Mode::D as Dir>::Out, Mode::Err>>>,
 }
 
@@ -514,13 +518,13 @@ impl<Mode: WalkMode> LazyWalkReses<Mode> {
     ) -> LazyWalkReses<Mode>
     {
         LazyWalkReses {
-            env: env,
-            prelude_env: prelude_env,
+            env,
+            prelude_env,
             more_quoted_env: vec![],
             less_quoted_env: vec![],
             less_quoted_out_env: vec![],
             parts: parts_unwalked.map(&mut LazilyWalkedTerm::new),
-            this_ast: this_ast,
+            this_ast,
             extra_info: std::default::Default::default(),
         }
     }
@@ -546,7 +550,7 @@ impl<Mode: WalkMode> LazyWalkReses<Mode> {
     ) -> LazyWalkReses<Mode>
     {
         LazyWalkReses {
-            env: env,
+            env,
             prelude_env: Assoc::new(),
             more_quoted_env: mqe,
             less_quoted_env: vec![],
@@ -560,11 +564,11 @@ impl<Mode: WalkMode> LazyWalkReses<Mode> {
     pub fn new_empty() -> LazyWalkReses<Mode> { Self::new_wrapper(Assoc::new()) }
 
     pub fn switch_ast(self, parts: &EnvMBE<Ast>, this_ast: Ast) -> LazyWalkReses<Mode> {
-        LazyWalkReses { parts: parts.map(&mut LazilyWalkedTerm::new), this_ast: this_ast, ..self }
+        LazyWalkReses { parts: parts.map(&mut LazilyWalkedTerm::new), this_ast, ..self }
     }
 
     pub fn this_form(&self) -> Rc<crate::form::Form> {
-        match self.this_ast {
+        match self.this_ast.c() {
             Node(ref f, _, _) => f.clone(),
             _ => icp!(),
         }
@@ -596,7 +600,7 @@ impl<Mode: WalkMode> LazyWalkReses<Mode> {
             part_name,
             depth,
             &|lwt: &Rc<LazilyWalkedTerm<Mode>>| -> Result<<Mode::D as Dir>::Out, Mode::Err> {
-                return lwt.get_res(self).map(map);
+                lwt.get_res(self).map(map)
             },
             &|v: Vec<Result<<Mode::D as Dir>::Out, Mode::Err>>| {
                 let mut accum = vec![];
@@ -646,7 +650,7 @@ impl<Mode: WalkMode> LazyWalkReses<Mode> {
             part_name,
             depth,
             &|lwt: &Rc<LazilyWalkedTerm<Mode>>| -> S {
-                return m(&lwt.term);
+                m(&lwt.term)
             },
             f,
         )
@@ -683,7 +687,7 @@ impl<Mode: WalkMode> LazyWalkReses<Mode> {
 
     pub fn maybe__context_elt(&self) -> Option<Mode::Elt> {
         // Kind of a HACK; users might set the context_elt in a positive mode before a mode switch.
-        self.env.find(&negative_ret_val()).map(Clone::clone)
+        self.env.find(&negative_ret_val()).cloned()
     }
 
     /// Change the context (by editing the environment). Only sensible for negative walks.
@@ -694,12 +698,12 @@ impl<Mode: WalkMode> LazyWalkReses<Mode> {
 
     /// Change the whole environment
     pub fn with_environment(&self, env: ResEnv<Mode::Elt>) -> LazyWalkReses<Mode> {
-        LazyWalkReses { env: env, ..(*self).clone() }
+        LazyWalkReses { env, ..(*self).clone() }
     }
 
     /// Change the prelude environment
     pub fn with_prelude_environment(&self, prelude_env: ResEnv<Mode::Elt>) -> LazyWalkReses<Mode> {
-        LazyWalkReses { prelude_env: prelude_env, ..(*self).clone() }
+        LazyWalkReses { prelude_env, ..(*self).clone() }
     }
 
     /// Clear the memo table; important if you're re-evaluating the same term,
@@ -800,7 +804,7 @@ impl<Mode: WalkMode> LazyWalkReses<Mode> {
             return None;
         }
         let mut res = vec![];
-        for (marched_parts, ctx) in marched.into_iter().zip(new_contexts.into_iter()) {
+        for (marched_parts, ctx) in marched.into_iter().zip(new_contexts) {
             res.push(LazyWalkReses {
                 env: self.env.set(negative_ret_val(), ctx),
                 parts: marched_parts,
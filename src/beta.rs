@@ -2,7 +2,7 @@
 
 use crate::{
     alpha::Ren,
-    ast::{Ast, Atom, ExtendEnv, VariableReference},
+    ast::{Ast, AstContents::*},
     ast_walk::{LazilyWalkedTerm, LazyWalkReses},
     name::*,
     util::{assoc::Assoc, mbe::EnvMBE},
@@ -90,12 +90,12 @@ impl Beta {
         match *self {
             Nothing => vec![],
             Shadow(ref lhs, ref rhs) => {
-                lhs.names_mentioned().into_iter().chain(rhs.names_mentioned().into_iter()).collect()
+                lhs.names_mentioned().into_iter().chain(rhs.names_mentioned()).collect()
             }
             ShadowAll(_, ref drivers) => drivers.clone(),
             Basic(n, v) => vec![n, v],
             SameAs(n, ref v_source) => {
-                vec![n].into_iter().chain(v_source.free_vrs().into_iter()).collect()
+                vec![n].into_iter().chain(v_source.free_vrs()).collect()
             }
             BoundButNotUsable(n) => vec![n],
             Underspecified(n) => vec![n],
@@ -163,25 +163,26 @@ pub fn env_from_beta<Mode: crate::walk_mode::WalkMode>(
     match *b {
         Nothing => Ok(Assoc::new()),
         Shadow(ref lhs, ref rhs) => {
-            Ok(env_from_beta::<Mode>(&*lhs, parts)?
-                .set_assoc(&env_from_beta::<Mode>(&*rhs, parts)?))
+            Ok(env_from_beta::<Mode>(lhs, parts)?
+                .set_assoc(&env_from_beta::<Mode>(rhs, parts)?))
         }
         ShadowAll(ref sub_beta, ref drivers) => {
             let mut res = Assoc::new();
             for parts in parts.march_all(drivers) {
-                res = res.set_assoc(&env_from_beta::<Mode>(&*sub_beta, &parts)?);
+                res = res.set_assoc(&env_from_beta::<Mode>(sub_beta, &parts)?);
             }
             Ok(res)
         }
         Basic(name_source, rhs_source) => {
-            if let LazilyWalkedTerm { term: Atom(ref name), .. } =
-                **parts.parts.get_leaf_or_panic(&name_source)
-            {
+            let name_term = &parts.parts.get_leaf_or_panic(&name_source).term;
+            if let Atom(ref name) = *name_term.c() {
                 // let LazilyWalkedTerm {term: ref rhs_stx, ..}
                 //    = **parts.parts.get_leaf_or_panic(rhs_source);
                 let rhs = parts.switch_to_positive().get_res(rhs_source)?;
 
                 Ok(Assoc::new().set(*name, rhs.clone()))
+            } else if is_interpolated_atom(name_term) {
+                Ok(Assoc::new())
             } else {
                 panic!(
                     "User error: {:#?} is supposed to supply names, but is not an Atom.",
@@ -200,12 +201,24 @@ pub fn env_from_beta<Mode: crate::walk_mode::WalkMode>(
             // Probably ought to just use `susbsitute` anyways.
             let mut env_for_parts = parts.env.clone();
             for n in res_source.free_vrs() {
-                env_for_parts = env_for_parts.set(n, parts.switch_to_positive().get_res(n)?);
+                let res = if parts.has(n) {
+                    parts.switch_to_positive().get_res(n)?
+                } else {
+                    // A repeated part is a tuple of its results (for `:::[ ]:::` to expand):
+                    let components: Vec<Ast> = parts
+                        .switch_to_positive()
+                        .get_rep_res(n)?
+                        .iter()
+                        .map(|res| res.to_ast())
+                        .collect();
+                    Mode::Elt::from_ast(&ast!({"Type" "tuple" : "component" => (,seq components)}))
+                };
+                env_for_parts = env_for_parts.set(n, res);
             }
 
             let rhs_parts = parts.switch_to_positive().with_environment(env_for_parts);
             let ctxt: Mode::Elt =
-                crate::ast_walk::walk::<<Mode as WalkMode>::AsPositive>(&res_source, &rhs_parts)?;
+                crate::ast_walk::walk::<<Mode as WalkMode>::AsPositive>(res_source, &rhs_parts)?;
 
             // Do the actual work:
             let res = parts.switch_to_negative().with_context(ctxt).get_res(name_source)?;
@@ -246,17 +259,18 @@ pub fn env_from_beta<Mode: crate::walk_mode::WalkMode>(
 
             let mut res = Assoc::new();
             for name in expected_res_keys {
-                res = res.set(name, <Mode::Elt as WalkElt>::from_ast(&crate::ast::Trivial));
+                res = res.set(name, <Mode::Elt as WalkElt>::from_ast(&raw_ast!(Trivial)));
             }
 
             Ok(res)
         }
 
         Underspecified(ref name_source) => {
-            if let LazilyWalkedTerm { term: Atom(ref name), .. } =
-                **parts.parts.get_leaf_or_panic(name_source)
-            {
+            let name_term = &parts.parts.get_leaf_or_panic(name_source).term;
+            if let Atom(ref name) = *name_term.c() {
                 Ok(Assoc::new().set(*name, Mode::underspecified(*name)))
+            } else if is_interpolated_atom(name_term) {
+                Ok(Assoc::new())
             } else {
                 panic!(
                     "{:#?} is supposed to supply names, but is not an Atom.",
@@ -267,15 +281,15 @@ pub fn env_from_beta<Mode: crate::walk_mode::WalkMode>(
 
         Protected(ref name_source) => {
             // Since protection isn't binding, it gets variable references instead
-            if let LazilyWalkedTerm { term: ExtendEnv(ref boxed_vr, _), .. } =
-                **parts.parts.get_leaf_or_panic(name_source)
+            if let ExtendEnv(ref boxed_vr, _) =
+                *parts.parts.get_leaf_or_panic(name_source).term.c()
             {
                 // HACK: rely on the fact that `walk_var`
                 //  won't recursively substitute until it "hits bottom"
                 // Drop the variable reference right into the environment.
                 Ok(Assoc::new().set(
-                    crate::core_forms::vr_to_name(&*boxed_vr),
-                    Mode::Elt::from_ast(&*boxed_vr),
+                    crate::core_forms::vr_to_name(boxed_vr),
+                    Mode::Elt::from_ast(boxed_vr),
                 ))
             } else {
                 panic!(
@@ -357,13 +371,24 @@ impl ExportBeta {
     }
 }
 
+/// Under quasiquotation, a binder name can be interpolated (`.[ ,[name], : Int . ⋯ ].`).
+/// The name isn't known until the quotation is evaluated, so the binder binds nothing (yet).
+fn is_interpolated_atom(a: &Ast) -> bool {
+    match *a.c() {
+        Node(ref f, _, _) => f.name == n("unquote") || f.name == n("dotdotdot"),
+        _ => false,
+    }
+}
+
 // Helper for `bound_from_[export_]beta`:
 fn names_exported_by(ast: &Ast, quote_depth: i16) -> Vec<Name> {
     use tap::TapOps;
 
-    match *ast {
-        Ast::Atom(n) => vec![n],
-        Ast::Node(_, ref sub_parts, ref export) => {
+    match *ast.c() {
+        // Quoted atoms (e.g. literals, or binders in quoted syntax) are matched literally;
+        //  they don't bind anything:
+        Atom(n) if quote_depth <= 0 => vec![n],
+        Node(_, ref sub_parts, ref export) => {
             if quote_depth <= 0 {
                 bound_from_export_beta(export, sub_parts, quote_depth)
             } else {
@@ -374,10 +399,10 @@ fn names_exported_by(ast: &Ast, quote_depth: i16) -> Vec<Name> {
                 )
             }
         }
-        Ast::ExtendEnv(ref body, _) => names_exported_by(body, quote_depth),
-        Ast::QuoteMore(ref body, _) => names_exported_by(body, quote_depth + 1),
-        Ast::QuoteLess(ref body, _) => names_exported_by(body, quote_depth - 1),
-        ref ast if quote_depth <= 0 => icp!("beta SameAs refers to an invalid AST node: {}", ast),
+        ExtendEnv(ref body, _) => names_exported_by(body, quote_depth),
+        QuoteMore(ref body, _) => names_exported_by(body, quote_depth + 1),
+        QuoteLess(ref body, _) => names_exported_by(body, quote_depth - 1),
+        _ if quote_depth <= 0 => icp!("beta SameAs refers to an invalid AST node: {}", ast),
         _ => vec![],
     }
 }
@@ -388,15 +413,15 @@ pub fn bound_from_beta(b: &Beta, parts: &EnvMBE<crate::ast::Ast>, quote_depth: i
     match *b {
         Nothing => vec![],
         Shadow(ref lhs, ref rhs) => {
-            let mut res = bound_from_beta(&*lhs, parts, quote_depth);
-            let mut res_r = bound_from_beta(&*rhs, parts, quote_depth);
+            let mut res = bound_from_beta(lhs, parts, quote_depth);
+            let mut res_r = bound_from_beta(rhs, parts, quote_depth);
             res.append(&mut res_r);
             res
         }
         ShadowAll(ref sub_beta, ref drivers) => {
             let mut res = vec![];
             for sub_parts in &parts.march_all(drivers) {
-                res.append(&mut bound_from_beta(&*sub_beta, sub_parts, quote_depth));
+                res.append(&mut bound_from_beta(sub_beta, sub_parts, quote_depth));
             }
             res
         }
@@ -406,7 +431,13 @@ pub fn bound_from_beta(b: &Beta, parts: &EnvMBE<crate::ast::Ast>, quote_depth: i
         }
         Protected(ref _n_s) => vec![], // Non-binding
         Basic(ref n_s, _) | Underspecified(ref n_s) => {
-            vec![crate::core_forms::ast_to_name(parts.get_leaf_or_panic(n_s))]
+            let name_source = parts.get_leaf_or_panic(n_s);
+            if is_interpolated_atom(name_source) {
+                // Can be an unquotation, which exports whatever its pattern binds (if anything)
+                names_exported_by(name_source, quote_depth)
+            } else {
+                vec![crate::core_forms::ast_to_name(name_source)]
+            }
         }
     }
 }
@@ -421,15 +452,15 @@ pub fn bound_from_export_beta(
     match *b {
         ExportBeta::Nothing => vec![],
         ExportBeta::Shadow(ref lhs, ref rhs) => {
-            let mut res = bound_from_export_beta(&*lhs, parts, quote_depth);
-            let mut res_r = bound_from_export_beta(&*rhs, parts, quote_depth);
+            let mut res = bound_from_export_beta(lhs, parts, quote_depth);
+            let mut res_r = bound_from_export_beta(rhs, parts, quote_depth);
             res.append(&mut res_r);
             res
         }
         ExportBeta::ShadowAll(ref sub_beta, ref drivers) => {
             let mut res = vec![];
             for sub_parts in &parts.march_all(drivers) {
-                res.append(&mut bound_from_export_beta(&*sub_beta, sub_parts, quote_depth));
+                res.append(&mut bound_from_export_beta(sub_beta, sub_parts, quote_depth));
             }
             res
         }
@@ -454,12 +485,12 @@ pub fn freshening_from_beta(
 {
     match *b {
         Nothing => Assoc::new(),
-        Shadow(ref lhs, ref rhs) => freshening_from_beta(&*lhs, parts, memo)
-            .set_assoc(&freshening_from_beta(&*rhs, parts, memo)),
+        Shadow(ref lhs, ref rhs) => freshening_from_beta(lhs, parts, memo)
+            .set_assoc(&freshening_from_beta(rhs, parts, memo)),
         ShadowAll(ref sub_beta, ref drivers) => {
             let mut res = Assoc::new();
             for parts in parts.march_all(drivers) {
-                res = res.set_assoc(&freshening_from_beta(&*sub_beta, &parts, memo));
+                res = res.set_assoc(&freshening_from_beta(sub_beta, &parts, memo));
             }
             res
         }
@@ -470,9 +501,9 @@ pub fn freshening_from_beta(
 
             Assoc::new().set(
                 this_name,
-                crate::ast::VariableReference(
+                raw_ast!(VariableReference(
                     *memo.entry((n_s, this_name)).or_insert_with(|| this_name.freshen()),
-                ),
+                )),
             )
         }
     }
@@ -528,6 +559,18 @@ fn env_from_beta_basics() {
     assert_eq!(env_from_beta(&beta!(["a" : "S"]), &lwr), Ok(assoc_n!("aa" => uty!({Nat :}))));
 }
 
+#[test]
+fn quoted_atoms_bind_nothing() {
+    // In a quoted pattern like `'[Expr | (plus 0x1F ,[e],)]'`, the literal is matched, not bound:
+    let literal = raw_ast!(Atom(n("0x1F")));
+    let unquoted = raw_ast!(QuoteLess(Box::new(raw_ast!(Atom(n("e")))), 1));
+    let quoted = |a: Ast| raw_ast!(QuoteMore(Box::new(a), false));
+
+    assert_eq!(names_exported_by(&literal, 0), vec![n("0x1F")]);
+    assert_eq!(names_exported_by(&quoted(literal), 0), vec![]);
+    assert_eq!(names_exported_by(&quoted(unquoted), 0), vec![n("e")]);
+}
+
 // fn fold_beta<T>(b: Beta, over: Assoc<Name, T>,
 //                    leaf: Fn(&Ast ) -> S
 
@@ -14,7 +14,7 @@ use crate::{
     ty::*,
     util::assoc::Assoc,
 };
-use num::bigint::ToBigInt;
+use num::bigint::{BigInt, ToBigInt};
 use std::rc::Rc; // type forms are kinda bulky
 
 // Core forms!
@@ -26,25 +26,107 @@ use std::rc::Rc; // type forms are kinda bulky
 // TODO: these `&Ast`-consuming functions to `ast.rs`; possibly into an `impl` of `Ast`.
 
 pub fn ast_to_name(ast: &Ast) -> Name {
-    match *ast {
+    match *ast.c() {
         Atom(n) => n,
         _ => icp!("{:#?} is not an atom", ast),
     }
 }
 pub fn vr_to_name(ast: &Ast) -> Name {
-    match *ast {
+    match *ast.c() {
         VariableReference(n) => n,
         _ => icp!("{:#?} is not a vr", ast),
     }
 }
 
+/// Decimal, hexadecimal (`0x`), or binary (`0b`) digits, possibly separated by `_`s.
+/// (There must be at least one digit, so `0x_` isn't a literal.)
+/// The `\b` keeps `0x1F` from also parsing as `0` followed by the variable `x1F`.
+pub const INT_LITERAL: &str =
+    r"(0x_*[0-9a-fA-F][0-9a-fA-F_]*|0b_*[01][01_]*|[0-9][0-9_]*)\b";
+
+/// The value of an integer literal that matched `INT_LITERAL`.
+pub fn parse_int_literal(lit: &str) -> BigInt {
+    let digits = lit.replace('_', "");
+    let (digits, radix) = if let Some(hex) = digits.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(bin) = digits.strip_prefix("0b") {
+        (bin, 2)
+    } else {
+        (&digits[..], 10)
+    };
+    BigInt::parse_bytes(digits.as_bytes(), radix)
+        .unwrap_or_else(|| icp!("malformed integer literal {}", lit))
+}
+
+/// Like `INT_LITERAL`, but with a suffix naming a fixed-width integer type, like `255u8`.
+pub const FIXED_INT_LITERAL: &str =
+    r"((?:0x_*[0-9a-fA-F][0-9a-fA-F_]*|0b_*[01][01_]*|[0-9][0-9_]*)[iu](?:8|16|32|64))\b";
+
+/// The value and width of a literal that matched `FIXED_INT_LITERAL`. (It may be out of range.)
+pub fn parse_fixed_int_literal(lit: &str) -> (BigInt, crate::runtime::numbers::IntWidth) {
+    let suffix_start = lit.rfind(['i', 'u']).unwrap();
+    match crate::runtime::numbers::IntWidth::from_suffix(&lit[suffix_start..]) {
+        Some(w) => (parse_int_literal(&lit[..suffix_start]), w),
+        None => icp!("malformed integer literal {}", lit),
+    }
+}
+
+/// Decimal digits with a fractional part, an exponent, or both, like `1.5`, `1e-3`, or `2.5E10`.
+pub const FLOAT_LITERAL: &str =
+    r"([0-9][0-9_]*\.[0-9][0-9_]*(?:[eE][+-]?[0-9]+)?|[0-9][0-9_]*[eE][+-]?[0-9]+)\b";
+
+/// The value of a literal that matched `FLOAT_LITERAL`. (It's infinite if it's too big.)
+pub fn parse_float_literal(lit: &str) -> f64 {
+    lit.replace('_', "").parse().unwrap_or_else(|_| icp!("malformed float literal {}", lit))
+}
+
+/// A double-quoted string, with `\` escapes.
+/// A `\u` must be followed by `{`, hex digits naming a Unicode scalar value
+///  (so, at most `10FFFF`, and not a surrogate in `D800`-`DFFF`), and `}`.
+pub const STRING_LITERAL: &str = concat!(
+    r#"("(?:[^"\\]|\\[^u]|\\u\{0*(?:"#,
+    r"[0-9a-fA-F]{1,3}|[1-9a-cA-CeEfF][0-9a-fA-F]{3}|[dD][0-7][0-9a-fA-F]{2}",
+    r"|[1-9a-fA-F][0-9a-fA-F]{4}|10[0-9a-fA-F]{4}",
+    r#")\})*")"#
+);
+
+/// Turn the source text of a string literal (quotes included) into the string it denotes.
+/// Supports `\n`, `\t`, `\r`, `\0`, and `\u{...}`; any other escaped character stands for itself.
+/// `lit` must match `STRING_LITERAL`.
+pub fn unescape_string_literal(lit: &str) -> String {
+    let mut res = String::new();
+    let mut chars = lit[1..lit.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => res.push('\n'),
+            Some('t') => res.push('\t'),
+            Some('r') => res.push('\r'),
+            Some('0') => res.push('\0'),
+            Some('u') => {
+                let code: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                match u32::from_str_radix(&code, 16).ok().and_then(std::char::from_u32) {
+                    Some(c) => res.push(c),
+                    None => icp!("malformed string literal {}", lit),
+                }
+            }
+            Some(other) => res.push(other),
+            None => res.push('\\'),
+        }
+    }
+    res
+}
+
 /// Remove an `ExtendEnv` without respecting its binding behavior.
 /// This is safe if directly inside a `Node` that was just freshened.
 /// (TODO: think about what "just" means here. It's super-subtle!)
 pub fn strip_ee(a: &Ast) -> &Ast {
-    match *a {
-        ExtendEnv(ref body, _) => (&**body),
-        ExtendEnvPhaseless(ref body, _) => (&**body),
+    match *a.c() {
+        ExtendEnv(ref body, _) => body,
+        ExtendEnvPhaseless(ref body, _) => body,
         _ => icp!("malformed thing"),
     }
 }
@@ -66,24 +148,55 @@ pub fn make_core_syn_env() -> SynEnv {
     let ctf_6 = ctf.clone();
     let ctf_7 = ctf.clone();
     let ctf_8 = ctf.clone();
+    let ctf_9 = ctf.clone();
+    let ctf_10 = ctf.clone();
+    let ctf_11 = ctf.clone();
+    let ctf_12 = ctf.clone();
+    let ctf_13 = ctf.clone();
 
     // Unseemly expressions
     let main_expr_forms = forms_to_form_pat![
         typed_form!("lambda",
         /* syntax */ /* TODO: add comma separators to the syntax! */
         (delim ".[", "[", [
-                           (star [(named "param", atom), (lit ":"),
-                                  (named "p_t", (call "Type"))]), (lit "."),
+                           (star [(named "param", atom),
+                                  (alt [(lit ":"), (named "p_t", (call "Type"))],
+                                       (named "p_t", (scope inferred_type.with(Rc::clone))))]),
+                           (lit "."),
             (named "body",
                 (import [* ["param" : "p_t"]], (call "Expr")))]),
         /* type */
         cust_rc_box!( move | part_types | {
+            // Synthesize each parameter type just once, so that omitted annotations
+            //  (which become fresh underdetermined types) are the same in the body and the result.
+            // HACK: like `Body(n("body"))`, but importing the parameters ourselves.
+            let param_tys = part_types.get_rep_res(n("p_t"))?;
+            let mut body_env = part_types.env.clone();
+            for (param, p_ty) in part_types.get_rep_term(n("param")).iter().zip(&param_tys) {
+                // (Under quasiquotation, `param` might be an unquotation, which binds nothing here)
+                if let Atom(param) = *param.c() {
+                    body_env = body_env.set(param, p_ty.clone());
+                }
+            }
+            let body = match *part_types.get_term_ref(n("body")).c() {
+                ExtendEnv(ref body, _) => (**body).clone(),
+                _ => part_types.get_term(n("body")), // Hand-built, without the `import`
+            };
+            let body_ty =
+                crate::ast_walk::walk::<SynthTy>(&body, &part_types.with_environment(body_env))?;
+
             let lambda_type : Ty =
                 ty!({ find_type(&ctf_0, "fn") ;
-                     "param" => [* part_types =>("param") part_types :
-                                   (, part_types.get_res(n("p_t"))?.concrete() )],
-                     "ret" => (, part_types.get_res(n("body"))?.concrete() )});
-            Ok(lambda_type)}),
+                     "param" => (,seq param_tys.iter().map(Ty::concrete).collect::<Vec<_>>()),
+                     "ret" => (, body_ty.concrete() )});
+            let inferred = inferred_type.with(Rc::clone);
+            let p_ts = part_types.get_rep_term(n("p_t"));
+            if p_ts.iter().all(|p_t| p_t.destructure(inferred.clone()).is_none()) {
+                return Ok(lambda_type);
+            }
+            // Checking the body has (hopefully) solved the omitted parameter types:
+            crate::ty_compare::canonicalize_partially(&lambda_type, part_types.env.clone())
+                .map_err(|e| crate::util::err::sp(e, part_types.this_ast.clone()))}),
         /* evaluation */
         cust_rc_box!( move | part_values | {
             Ok(Function(Rc::new(Closure {
@@ -100,15 +213,28 @@ pub fn make_core_syn_env() -> SynEnv {
             use crate::walk_mode::WalkMode;
             let return_type = crate::ty_compare::Subtype::underspecified(n("<return_type>"));
 
+            let rand_types = part_types.get_rep_res(n("rand"))?;
             // The `rator` must be a function that takes the `rand`s as arguments:
             let _ = crate::ty_compare::is_subtype(
                 &ty!({ "Type" "fn" :
-                    "param" => (,seq part_types.get_rep_res(n("rand"))?
-                         .iter().map(|t| t.concrete()).collect::<Vec<_>>() ),
+                    "param" => (,seq rand_types.iter().map(|t| t.concrete()).collect::<Vec<_>>() ),
                     "ret" => (, return_type.concrete() )}),
                 &part_types.get_res(n("rator"))?,
                 &part_types)
-                    .map_err(|e| crate::util::err::sp(e, part_types.this_ast.clone()))?;
+                    .map_err(|e| {
+                        // Point at the offending argument, if we can tell which one it is:
+                        let culprit = match e {
+                            TyErr::Mismatch(ref got, _) => part_types.get_rep_term(n("rand"))
+                                .into_iter()
+                                .zip(rand_types.iter())
+                                .find(|&(_, rand_type)| rand_type == got)
+                                .map(|(rand, _)| rand),
+                            _ => None
+                        };
+                        crate::util::err::sp(
+                            e.abbreviated(&part_types.env),
+                            culprit.unwrap_or_else(|| part_types.this_ast.clone()))
+                    })?;
 
 
             // TODO: write a test that exercises this (it's used in the prelude)
@@ -119,29 +245,35 @@ pub fn make_core_syn_env() -> SynEnv {
                     &unif.borrow());
 
                 // Canonicalize the type in its environment:
-                let res = crate::ty_compare::canonicalize(&res.it, res.env);
+                let res = crate::ty_compare::canonicalize_partially(&res.it, res.env);
                 res.map_err(|e| crate::util::err::sp(e, part_types.this_ast.clone()))
             })
         }),
         cust_rc_box!( move | part_values | {
             match part_values.get_res(n("rator"))? {
                 Function(clos) => {
+                    let rands = part_values.get_rep_res(n("rand"))?;
+                    if rands.len() != clos.params.len() {
+                        return Err(RuntimeError::at(
+                            RuntimeErr::ArgCountMismatch(clos.params.len(), rands),
+                            &part_values.this_ast));
+                    }
                     let mut new_env = clos.env.clone();
-                    for (p, v) in clos.params.iter().zip(
-                            part_values.get_rep_res(n("rand"))?) {
+                    for (p, v) in clos.params.iter().zip(rands) {
                         new_env = new_env.set(*p, v);
                     }
 
                     // TODO: this seems wrong; it discards other phase information.
                     // But would it be correct to have closures capture at all phases?
                     crate::runtime::eval::eval(&clos.body, new_env)
+                        .map_err(|e| e.called_from(&part_values.this_ast))
                 },
                 BuiltInFunction(crate::runtime::eval::BIF(f)) => {
-                    Ok(f(part_values.get_rep_res(n("rand"))?))
-                }
-                other => {
-                    icp!("[type error] invoked {:#?} as if it were a function", other)
+                    f(part_values.get_rep_res(n("rand"))?)
+                        .map_err(|e| e.called_from(&part_values.this_ast))
                 }
+                other => Err(RuntimeError::at(RuntimeErr::NotAFunction(other),
+                                              &part_values.this_ast))
             }
         })),
         typed_form!("match",
@@ -167,24 +299,30 @@ pub fn make_core_syn_env() -> SynEnv {
                         }
                     }
                 }
-                match res {
-                    None => { // TODO #2: this isn't anywhere near exhaustive
-                        ty_err!(NonExhaustiveMatch(part_types.get_res(n("scrutinee")).unwrap())
-                            at Trivial /* TODO */)
-                    },
-                    Some(ty_res) => Ok(ty_res)
-                }
+                crate::exhaustiveness::check_match(
+                    &part_types.get_res(n("scrutinee"))?,
+                    &part_types.get_rep_term(n("p")),
+                    &part_types.env,
+                    &part_types.this_ast)?;
+
+                Ok(res.expect("`match` has at least one arm"))
             }),
             /* Evaluation: */
             cust_rc_box!( move | part_values | {
+                let scrutinee = part_values.get_res(n("scrutinee"))?;
                 for arm_values in part_values.march_all(&[n("arm")]) {
                     // TODO: don't we need to set a context?
                     match arm_values.get_res(n("arm")) {
                         Ok(res) => { return Ok(res); }
-                        Err(()) => { /* try the next one */ }
+                        Err(RuntimeError { body: RuntimeErr::PatternMismatch(_), .. }) => {
+                            /* try the next one */
+                        }
+                        Err(e) => { return Err(e); }
                     }
                 }
-                panic!("No arms matched! TODO #2");
+                Err(RuntimeError::at(
+                    RuntimeErr::MatchFailure(scrutinee),
+                    &part_values.this_ast))
             })
         ),
         // Note that we inconveniently require the user to specify the type.
@@ -264,6 +402,57 @@ pub fn make_core_syn_env() -> SynEnv {
                     part_values.get_rep_res(n("component"))?.into_iter().map(Rc::new).collect()))
             })
         ),
+        // Like strings, the atom keeps the literal's source text (e.g. `0xFF`).
+        typed_form!("int_literal",
+            [(call "DefaultSeparator"), (named "body", (scan INT_LITERAL))],
+            cust_rc_box!( move |_| { Ok(ty!({ find_type(&ctf_10, "Int") ; })) }),
+            cust_rc_box!( move |part_values| {
+                Ok(Int(parse_int_literal(
+                    &ast_to_name(&part_values.get_term(n("body"))).orig_sp())))
+            })),
+        typed_form!("fixed_int_literal",
+            [(call "DefaultSeparator"), (named "body", (scan FIXED_INT_LITERAL))],
+            cust_rc_box!( move |part_types| {
+                let lit = ast_to_name(&part_types.get_term(n("body"))).orig_sp();
+                let (val, w) = parse_fixed_int_literal(&lit);
+                let res = ty!({ find_type(&ctf_12, w.type_name()) ; });
+                if !w.fits(&val) {
+                    ty_err!(LiteralOutOfRange(lit, res) at part_types.this_ast)
+                }
+                Ok(res)
+            }),
+            cust_rc_box!( move |part_values| {
+                let (val, w) = parse_fixed_int_literal(
+                    &ast_to_name(&part_values.get_term(n("body"))).orig_sp());
+                match w.value(&val) {
+                    Some(v) => Ok(v),
+                    None => Err(RuntimeError::at( // (only possible without typechecking)
+                        RuntimeErr::Overflow(Int(val), n(w.type_name())),
+                        &part_values.this_ast))
+                }
+            })),
+        typed_form!("float_literal",
+            [(call "DefaultSeparator"), (named "body", (scan FLOAT_LITERAL))],
+            cust_rc_box!( move |part_types| {
+                let lit = ast_to_name(&part_types.get_term(n("body"))).orig_sp();
+                let res = ty!({ find_type(&ctf_13, "Float") ; });
+                if parse_float_literal(&lit).is_infinite() {
+                    ty_err!(LiteralOutOfRange(lit, res) at part_types.this_ast)
+                }
+                Ok(res)
+            }),
+            cust_rc_box!( move |part_values| {
+                Ok(Float(parse_float_literal(
+                    &ast_to_name(&part_values.get_term(n("body"))).orig_sp())))
+            })),
+        // The atom keeps the quotes and escapes, so that the literal unparses faithfully.
+        typed_form!("string_literal",
+            [(call "DefaultSeparator"), (named "body", (scan STRING_LITERAL))],
+            cust_rc_box!( move |_| { Ok(ty!({ find_type(&ctf_9, "String") ; })) }),
+            cust_rc_box!( move |part_values| {
+                Ok(Text(unescape_string_literal(
+                    &ast_to_name(&part_values.get_term(n("body"))).orig_sp())))
+            })),
         // e.g.
         // let_type
         //   pair = mu lhs rhs. {l: lhs, r: rhs}
@@ -303,7 +492,8 @@ pub fn make_core_syn_env() -> SynEnv {
                     mu_parts;
                     {
                         // This acts like the `mu` was never there (and hiding the binding)
-                        if let ExtendEnv(ref body, _) = *mu_parts.get_leaf_or_panic(&n("body")) {
+                        let body = mu_parts.get_leaf_or_panic(&n("body"));
+                        if let ExtendEnv(ref body, _) = *body.c() {
                             synth_type(body, unfold_parts.env.clone())
                         } else { icp!("no protection to remove!"); }
                     })
@@ -322,7 +512,8 @@ pub fn make_core_syn_env() -> SynEnv {
                     mu_parts;
                     {
                         // This acts like the `mu` was never there (and hiding the binding)
-                        if let ExtendEnv(ref body, _) = *mu_parts.get_leaf_or_panic(&n("body")) {
+                        let body = mu_parts.get_leaf_or_panic(&n("body"));
+                        if let ExtendEnv(ref body, _) = *body.c() {
                             synth_type(body, fold_parts.env.clone())?
                         } else { icp!("no protection to remove!"); }
                     });
@@ -378,7 +569,8 @@ pub fn make_core_syn_env() -> SynEnv {
                             return Ok(res);
                         }
                         ty_err!(NonexistentEnumArm(ast_to_name(arm_name),
-                            Ty::new(Trivial)) /* TODO `LazyWalkReses` needs more information */
+                            /* TODO `LazyWalkReses` needs more information */
+                            Ty::new(raw_ast!(Trivial)))
                             at arm_name.clone())
                 }
             )),
@@ -388,7 +580,9 @@ pub fn make_core_syn_env() -> SynEnv {
                     Enum(ref name, ref elts) => {
                         // "Try another branch"
                         if name != &ast_to_name(&part_values.get_term(n("name"))) {
-                            return Err(());
+                            return Err(RuntimeError::at(
+                                RuntimeErr::PatternMismatch(part_values.context_elt().clone()),
+                                &part_values.this_ast));
                         }
 
                         let mut res = Assoc::new();
@@ -399,7 +593,9 @@ pub fn make_core_syn_env() -> SynEnv {
 
                         Ok(res)
                     }
-                    _ => icp!("[type error] non-enum")
+                    _ => Err(RuntimeError::at( // (only possible without typechecking)
+                        RuntimeErr::PatternMismatch(part_values.context_elt().clone()),
+                        &part_values.this_ast))
                 }
             })) => [* ["component"]],
         negative_typed_form!("struct_pat",
@@ -456,7 +652,9 @@ pub fn make_core_syn_env() -> SynEnv {
 
                         Ok(res)
                     }
-                    _ => icp!("[type error] non-struct")
+                    _ => Err(RuntimeError::at( // (only possible without typechecking)
+                        RuntimeErr::PatternMismatch(part_values.context_elt().clone()),
+                        &part_values.this_ast))
                 }
             }))  => [* ["component"]],
         negative_typed_form!("tuple_pat",
@@ -476,7 +674,7 @@ pub fn make_core_syn_env() -> SynEnv {
                             res = res.set_assoc(sub_res);
                         }
 
-                        return Ok(res);
+                        Ok(res)
 
                     }
             )),
@@ -492,11 +690,33 @@ pub fn make_core_syn_env() -> SynEnv {
 
                         Ok(res)
                     }
-                    _ =>  icp!("[type error] non-tuple")
+                    _ => Err(RuntimeError::at( // (only possible without typechecking)
+                        RuntimeErr::PatternMismatch(part_values.context_elt().clone()),
+                        &part_values.this_ast))
                 }
             })
 
         ) => [* ["component"]],
+        negative_typed_form!("int_pat",
+            [(call "DefaultSeparator"), (named "body", (scan INT_LITERAL))],
+            cust_rc_box!( move |part_types| {
+                ty_exp!(part_types.context_elt(), &ty!({ find_type(&ctf_11, "Int") ; }),
+                        part_types.this_ast);
+                Ok(Assoc::new())
+            }),
+            cust_rc_box!( move |part_values| {
+                let expected =
+                    parse_int_literal(&ast_to_name(&part_values.get_term(n("body"))).orig_sp());
+                match *part_values.context_elt() {
+                    Int(ref actual) if *actual == expected => Ok(Assoc::new()),
+                    Int(_) => Err(RuntimeError::at(
+                        RuntimeErr::PatternMismatch(part_values.context_elt().clone()),
+                        &part_values.this_ast)),
+                    _ => Err(RuntimeError::at( // (only possible without typechecking)
+                        RuntimeErr::PatternMismatch(part_values.context_elt().clone()),
+                        &part_values.this_ast))
+                }
+            })) => [],
             // TODO #16: We need a pattern for destructuring tuples.
             crate::core_qq_forms::quote(/*positive=*/false) => ["body"]];
 
@@ -506,6 +726,7 @@ pub fn make_core_syn_env() -> SynEnv {
         n("Int"),
         n("Ident"),
         n("Float"),
+        n("String"),
         n("match"),
         n("enum"),
         n("struct"),
@@ -521,6 +742,8 @@ pub fn make_core_syn_env() -> SynEnv {
         "Ident" => Rc::new(Call(n("DefaultAtom"))),
         "DefaultReference" => Rc::new(VarRef(Rc::new(Call(n("DefaultAtom"))))),
         "DefaultSeparator" => Rc::new(crate::grammar::new_scan(r"(\s*)")),
+        // Only between top-level items (where there's no danger of `#` meaning something else):
+        "TopLevelSeparator" => Rc::new(crate::grammar::new_scan(r"((?:\s|#[^\n]*)*)")),
         "DefaultAtom" => Rc::new(
             form_pat!((common (reserved_by_name_vec (call "DefaultWord"), reserved_names)))),
         "DefaultWord" => Rc::new(form_pat!((common (pick [(call "DefaultSeparator"),
@@ -593,12 +816,12 @@ pub fn add_form_at_the_alt(outer: Rc<FormPat>, inner: &FormPat) -> Option<FormPa
             if let Some(new_r) = add_form_at_the_alt(r.clone(), inner) {
                 return Some(Biased(l.clone(), Rc::new(new_r)));
             }
-            return None;
+            None
         }
         Alt(ref subs) => {
             let mut my_subs: Vec<Rc<FormPat>> = subs.clone();
             my_subs.push(Rc::new(inner.clone()));
-            return Some(Alt(my_subs));
+            Some(Alt(my_subs))
         }
         _ => None,
     }
@@ -836,12 +1059,12 @@ fn alg_eval() {
         Ok(assoc_n!("abc" => val!(i 9006), "def" => val!(b true)))
     );
 
-    assert_eq!(
+    assert_m!(
         neg_eval(
             &u!({Pat enum_pat => [* ["component"]] : choice1 [(at abc); (at def)]}),
             mt_env.set(negative_ret_val(), val!(enum "choice0", (i 12321)))
         ),
-        Err(())
+        Err(RuntimeError { body: RuntimeErr::PatternMismatch(_), .. })
     );
 
     // Evaluate enum expression
@@ -911,7 +1134,8 @@ fn recursive_types() {
 
     let ty_env = assoc_n!(
         "IntList" => int_list_ty.clone(),  // this is a type definition...
-        "il_direct" => int_list_ty.clone()  // ...and this is a value with a type
+        "il_direct" => int_list_ty.clone(),  // ...and this is a value with a type
+        "some_int" => ty!({"Type" "Int":})
         // TODO #3: ... distinguish between these kinds in the environment!
 
         // We should never have `vr`s in the environment unless "protected" by a μ.
@@ -948,8 +1172,13 @@ fn recursive_types() {
                 "name" => "Cons",
                 "component" => ["car", "cdr"],
                 "t" => (vr "IntList")
+            }, { "Pat" "enum_pat" => [* ["component"]] :
+                "name" => "Nil",
+                "component" => [],
+                "t" => (vr "IntList")
             }],
-            "arm" => [@"arm" (import ["p" = "scrutinee"] (vr "car"))]
+            "arm" => [@"arm" (import ["p" = "scrutinee"] (vr "car")),
+                             (import ["p" = "scrutinee"] (vr "some_int"))]
         }),
         ty_env.clone()),
         Ok(ty!({"Type" "Int":})));
@@ -962,8 +1191,13 @@ fn recursive_types() {
                 "name" => "Cons",
                 "component" => ["car", "cdr"],
                 "t" => (vr "IntList")
+            }, { "Pat" "enum_pat" => [* ["component"]] :
+                "name" => "Nil",
+                "component" => [],
+                "t" => (vr "IntList")
             }],
-            "arm" => [@"arm" (import ["p" = "scrutinee"] (vr "cdr"))]
+            "arm" => [@"arm" (import ["p" = "scrutinee"] (vr "cdr")),
+                             (import ["p" = "scrutinee"] (vr "il_direct"))]
         }),
         ty_env.clone()),
         Ok(int_list_ty.clone())
@@ -1,5 +1,5 @@
 use crate::{
-    ast::{Ast, Atom, Node},
+    ast::{Ast, AstContents::*},
     ast_walk::{
         LazyWalkReses,
         WalkRule::{Body, Custom, LiteralLike, NotWalked},
@@ -7,8 +7,10 @@ use crate::{
     beta::{Beta, Beta::*, ExportBeta},
     core_forms::{ast_to_name, strip_ee, vr_to_name},
     core_type_forms::{less_quoted_ty, more_quoted_ty},
+    earley::ParseContext,
     form::{EitherPN::*, Form},
     grammar::{
+        Associativity,
         FormPat::{self, *},
         SynEnv,
     },
@@ -18,7 +20,7 @@ use crate::{
         reify::Reifiable,
     },
     ty::{SynthTy, Ty, TyErr},
-    util::assoc::Assoc,
+    util::{assoc::Assoc, mbe::EnvMBE},
     walk_mode::WalkElt,
 };
 use std::rc::Rc;
@@ -123,14 +125,14 @@ fn macro_type(forall_ty_vars: &[Name], arguments: Vec<(Name, Ty)>, output: Ty) -
         // TODO: this can go wrong if a macro-defining macro collides two term names.
         // Fixing this probably requires rethinking how "component_name" works.
         // Perhaps not using structs at all might also work.
-        components.push(mbe!("component_name" => (, Atom(k.unhygienic_orig())),
+        components.push(mbe!("component_name" => (, raw_ast!(Atom(k.unhygienic_orig()))),
                              "component" => (, v.to_ast())));
     }
-    let argument_struct = Node(
+    let argument_struct = raw_ast!(Node(
         crate::core_forms::find_core_form("Type", "struct"),
         crate::util::mbe::EnvMBE::new_from_anon_repeat(components),
         ExportBeta::Nothing,
-    );
+    ));
     let mac_fn = u!({Type fn : [(, argument_struct)] (, output.to_ast())});
 
     if forall_ty_vars.is_empty() {
@@ -138,7 +140,7 @@ fn macro_type(forall_ty_vars: &[Name], arguments: Vec<(Name, Ty)>, output: Ty) -
     } else {
         ty!({"Type" "forall_type" :
             "body" => (import [* [forall "param"]] (, mac_fn)),
-            "param" => (,seq forall_ty_vars.iter().map(|n| { Atom(*n) }).collect::<Vec<_>>())
+            "param" => (,seq forall_ty_vars.iter().map(|n| { raw_ast!(Atom(*n)) }).collect::<Vec<_>>())
         })
     }
 }
@@ -186,7 +188,7 @@ fn type_macro_invocation(
     let _ = crate::ty_compare::is_subtype(
         &macro_type(&[], q_arguments.clone(), expected_return),
         &parts.get_res(n("macro_name"))?,
-        &parts,
+        parts,
     )
     .map_err(|e| crate::util::err::sp(e, parts.this_ast.clone()))?;
 
@@ -218,7 +220,7 @@ pub fn macro_invocation(
         grammar: Rc::new(form_pat!([
             // `type_macro_invocation` expects "macro_name" to be set
             (named "macro_name", (anyways (,
-                Ast::VariableReference(macro_name)
+                raw_ast!(VariableReference(macro_name))
             ))),
             (, grammar.clone())
         ])),
@@ -285,31 +287,29 @@ pub fn macro_invocation(
             // This code is like that for "apply".
             let mut env = implementation.env.clone();
             for (param, depth) in &grammar.binders() {
-                let nt = grammar.find_named_call(*param).unwrap();
-
-                if nt != n("DefaultAtom") && nt != n("Ident") {
-                    // TODO: why not for those two NTs?
-                    let rhs = parts.map_flatten_term_at_depth(
-                        *param,
-                        *depth,
-                        &|mut a: &Ast| {
-                            // Nuke all binding, since we're abandoning its context.
-                            // The user will† deposit this syntax inside a replacement binding form.
-                            // (†still not enforced until issue #31 is fixed)
-                            while let Ast::ExtendEnv(ref body, _)
-                            | Ast::ExtendEnvPhaseless(ref body, _) = a
-                            {
-                                a = &*body;
-                            }
-                            Value::from_ast(a)
-                        },
-                        &|vec: Vec<Value>| Value::Sequence(vec.into_iter().map(Rc::new).collect()),
-                    );
+                let rhs = parts.map_flatten_term_at_depth(
+                    *param,
+                    *depth,
+                    &|mut a: &Ast| {
+                        // Nuke all binding, since we're abandoning its context.
+                        // The user will† deposit this syntax inside a replacement binding form.
+                        // (†still not enforced until issue #31 is fixed)
+                        while let ExtendEnv(ref body, _)
+                        | ExtendEnvPhaseless(ref body, _) = a.c()
+                        {
+                            a = body;
+                        }
+                        Value::from_ast(a)
+                    },
+                    &|vec: Vec<Value>| Value::Sequence(vec.into_iter().map(Rc::new).collect()),
+                );
 
-                    env = env.set(*param, rhs);
-                }
+                env = env.set(*param, rhs);
             }
             let expanded = Ast::reflect(&crate::runtime::eval::eval(&implementation.body, env)?);
+            // The outermost node came from the macro definition's quotation,
+            //  but errors in it are best reported at the invocation site:
+            let expanded = expanded.with_loc(parts.this_ast.loc().or_else(|| expanded.loc()));
 
             // Expand any macros produced by expansion, or that were already present in subterms:
             Ok(crate::expand::expand(&expanded)?.reify())
@@ -318,24 +318,47 @@ pub fn macro_invocation(
     })
 }
 
-/// What should `t` be, if matched under a repetition?
-/// A tuple, driven by whatever names are `forall`ed in `env`.
-fn repeated_type(t: &Ty, env: &Assoc<Name, Ty>) -> Result<Ty, crate::ty::TypeError> {
-    let mut drivers = vec![];
-    for v in t.0.free_vrs() {
-        if env.find(&v) == Some(&Ty(Ast::VariableReference(v))) {
-            drivers.push(Ast::VariableReference(v));
+/// What should the types in `body` be, if matched under a repetition?
+/// Tuples, driven by whatever names are `forall`ed in `env`.
+/// A type that mentions none of those (e.g. `Ident`) is driven by its siblings' drivers instead.
+fn repeated_types(
+    body: &Assoc<Name, Ty>,
+    env: &Assoc<Name, Ty>,
+) -> Result<Assoc<Name, Ty>, crate::ty::TypeError>
+{
+    let mut sibling_drivers = vec![];
+    for t in body.iter_values() {
+        for driver in drivers_of(t, env) {
+            if !sibling_drivers.contains(&driver) {
+                sibling_drivers.push(driver);
+            }
         }
     }
+    sibling_drivers.sort_by_key(|d| d.sp());
 
-    if drivers.is_empty() {
-        ty_err!(NeedsDriver (()) at t.0);
-    }
+    body.map(|t| {
+        let mut drivers = drivers_of(t, env);
+        if drivers.is_empty() {
+            drivers = sibling_drivers.clone();
+        }
+        if drivers.is_empty() {
+            ty_err!(NeedsDriver (()) at t.0);
+        }
+
+        Ok(ty!({"Type" "dotdotdot" :
+            "driver" => (,seq drivers.into_iter().map(|d| raw_ast!(VariableReference(d)))),
+            "body" => (, t.0.clone())
+        }))
+    })
+    .lift_result()
+}
 
-    Ok(ty!({"Type" "dotdotdot" :
-        "driver" => (,seq drivers),
-        "body" => (, t.0.clone())
-    }))
+/// The names in `t` that are `forall`ed in `env`.
+fn drivers_of(t: &Ty, env: &Assoc<Name, Ty>) -> Vec<Name> {
+    t.0.free_vrs()
+        .into_iter()
+        .filter(|v| env.find(v) == Some(&Ty(raw_ast!(VariableReference(*v)))))
+        .collect()
 }
 
 pub fn make_core_macro_forms() -> SynEnv {
@@ -454,7 +477,7 @@ pub fn make_core_macro_forms() -> SynEnv {
         syntax_syntax!( ([(named "body", (call "Syntax")), (lit "*")]) Star {
             |parts| {
                 let body : Assoc<Name, Ty> = parts.get_res(n("body"))?;
-                body.map(|t| repeated_type(t, &parts.env)).lift_result()
+                repeated_types(&body, &parts.env)
             }
         } {
             |parts| {
@@ -464,7 +487,7 @@ pub fn make_core_macro_forms() -> SynEnv {
         syntax_syntax!( ([(named "body", (call "Syntax")), (lit "+")]) Plus {
             |parts| {
                 let body : Assoc<Name, Ty> = parts.get_res(n("body"))?;
-                body.map(|t| repeated_type(t, &parts.env)).lift_result()
+                repeated_types(&body, &parts.env)
             }
         } {
             |parts| {
@@ -492,6 +515,26 @@ pub fn make_core_macro_forms() -> SynEnv {
             plan_a => Rc::new(FormPat::reflect(&plan_a)),
             plan_b => Rc::new(FormPat::reflect(&plan_b))
         )) => ["plan_a" "plan_b"],
+        // Levels go from 0 (loosest) to 9 (tightest), as in Haskell.
+        syntax_syntax!( ([(named "assoc", (alt (lit "infixl"), (lit "infixr"), (lit "infix"))),
+                          (named "level", (scan r"\s*(\d)")),
+                          (delim "{", "{", (named "body", (call "Syntax")))]) Prec {
+            |parts| {
+                parts.get_res(n("body"))
+            }
+        } {
+            |parts| {
+                let assoc = match ast_to_name(&parts.get_term(n("assoc"))).orig_sp().as_str() {
+                    "infixl" => Associativity::Left,
+                    "infixr" => Associativity::Right,
+                    _ => Associativity::Non,
+                };
+                let level = ast_to_name(&parts.get_term(n("level"))).orig_sp().parse::<u8>()
+                    .unwrap_or_else(|_| icp!("scanned a non-digit"));
+                Ok(Prec(Rc::new(FormPat::reflect(&parts.get_res(n("body"))?)), level, assoc)
+                    .reify())
+            }
+        }) => ["body"],
         // `Named` switches to a positive mode for typechecking its body.
         // TODO: I don't think this makes sense, now that `Named` and `Call` are split apart:
         //   TODO: replace `binder` with a `Pat`, and make the following true:
@@ -512,14 +555,25 @@ pub fn make_core_macro_forms() -> SynEnv {
             }
         }) => ["part_name"],
         // `Call` without a type
-        syntax_syntax!( ((delim ",{", "{", (named "nt", atom))) Call {
-            |_| {
+        Rc::new(Form {
+            name: n("call"),
+            grammar: Rc::new(form_pat!((delim ",{", "{", (named "nt", atom)))),
+            type_compare: Both(NotWalked,NotWalked), // Not a type
+            synth_type: Both(cust_rc_box!(|parts| {
+                // Under a `Named`, only atoms (which don't have a syntax type) can go unannotated
+                let nt = ast_to_name(&parts.get_term(n("nt")));
+                if nt == n("DefaultAtom") || nt == n("Ident") {
+                    Ok(ty!({"Type" "Ident" :}))
+                } else {
+                    ty_err!(AnnotationRequired (()) at parts.this_ast)
+                }
+            }), cust_rc_box!(|_| {
                 Ok(Assoc::new()) // We should check that the nt is defined, but we can't here
-            }
-        } {
-            |parts| {
+            })),
+            eval: Positive(cust_rc_box!(|parts| {
                 Ok(Call(ast_to_name(&parts.get_term(n("nt")))).reify())
-            }
+            })),
+            quasiquote: Both(LiteralLike, LiteralLike)
         }) => [],
 
 
@@ -581,19 +635,19 @@ pub fn make_core_macro_forms() -> SynEnv {
                 let return_ty = parts.switch_mode::<SynthTy>().get_res(n("implementation"))?;
                 let mut arguments : Vec<(Name, Ty)> = parts.get_res(n("syntax"))?
                     .iter_pairs().cloned().collect();
-                arguments.sort_by(|lhs, rhs| lhs.0.cmp(&rhs.0) ); // Pick a canonical order
+                arguments.sort_by_key(|lhs| lhs.0); // Pick a canonical order
                 let ty_params = &parts.get_rep_term(n("param")).iter().map(
-                            |p| ast_to_name(p)).collect::<Vec<_>>();
+                            ast_to_name).collect::<Vec<_>>();
                 Ok(Assoc::new().set(ast_to_name(&parts.get_term(n("macro_name"))),
-                                    macro_type(&ty_params, arguments, return_ty)))
+                                    macro_type(ty_params, arguments, return_ty)))
             }
         } {
             |parts| {
                 // TODO: This is the right thing to do, right?
                 let macro_params = crate::beta::bound_from_export_beta(
-                    &ebeta!(["syntax"]), &parts.this_ast.node_parts(), 0);
+                    &ebeta!(["syntax"]), parts.this_ast.node_parts(), 0);
                 let implementation = strip_ee(
-                    &strip_ee(&parts.get_term(n("implementation")))).clone();
+                    strip_ee(&parts.get_term(n("implementation")))).clone();
 
                 let mut export = ExportBeta::Nothing;
                 let export_names = parts.get_rep_term(n("export")).iter()
@@ -624,39 +678,83 @@ pub fn make_core_macro_forms() -> SynEnv {
         "Beta" => Rc::new(beta_grammar))
 }
 
+/// The clauses of a syntax extension: `nt ::= rhs ;` replaces `nt`; `nt ::=also rhs ;` adds to it.
+/// (There has to be at least one, or else a top-level `extend_syntax` would be complete by itself.)
+pub fn syntax_extension_clauses() -> FormPat {
+    form_pat!((plus [(named "nt", atom),
+                     (named "operator", (alt (lit "::="), (lit "::=also"))),
+                     (named "rhs", (call "Syntax")),
+                     (lit ";")]))
+}
+
+/// Apply the clauses parsed by `syntax_extension_clauses` to the grammar in `pc`.
+pub fn perform_extension(pc: ParseContext, bnf_parts: &EnvMBE<Ast>) -> ParseContext {
+    let mut syn_env = pc.grammar.clone();
+    for (nt, also, rhs) in evaluate_extension(&pc, bnf_parts) {
+        syn_env = extend_nt(&syn_env, nt, also, rhs);
+    }
+
+    ParseContext { grammar: syn_env, type_ctxt: pc.type_ctxt, eval_ctxt: pc.eval_ctxt }
+}
+
+/// Each clause parsed by `syntax_extension_clauses`, as (nonterminal, `::=also`?, grammar).
+pub fn evaluate_extension(pc: &ParseContext, bnf_parts: &EnvMBE<Ast>) -> Vec<(Name, bool, FormPat)> {
+    let nts: Vec<Name> =
+        bnf_parts.get_rep_leaf_or_panic(n("nt")).iter().map(|a| ast_to_name(a)).collect();
+    let ops: Vec<bool> = bnf_parts
+        .get_rep_leaf_or_panic(n("operator"))
+        .iter()
+        .map(|a| a.c() == &Atom(n("::=also")))
+        .collect();
+    let rhses: Vec<&Ast> = bnf_parts.get_rep_leaf_or_panic(n("rhs"));
+
+    nts.into_iter()
+        .zip(ops)
+        .zip(rhses)
+        .map(|((nt, also), rhs)| {
+            (nt, also, FormPat::reflect(&crate::ast_walk::walk(rhs, &pc.eval_ctxt).unwrap()))
+        })
+        .collect()
+}
+
+/// Replace `nt` with `rhs`, or, if `also`, add `rhs` as an alternative.
+pub fn extend_nt(syn_env: &SynEnv, nt: Name, also: bool, rhs: FormPat) -> SynEnv {
+    syn_env.set(
+        nt,
+        Rc::new(if also {
+            form_pat!((alt (, rhs), (, (**syn_env.find_or_panic(&nt)).clone())))
+        } else {
+            rhs
+        }),
+    )
+}
+
+/// The types of the macros defined by `rhs` (the right-hand side of one clause).
+/// `extend_syntax` binds these in its body;
+///  a top-level extension binds them for the rest of the file.
+pub fn macros_defined_by(
+    rhs: &Ast,
+    ty_env: &Assoc<Name, Ty>,
+) -> Result<Assoc<Name, Ty>, crate::ty::TypeError> {
+    // This is what the `import_phaseless` in `extend_syntax` does:
+    let trivial_type_form = crate::core_type_forms::type_defn("unused", form_pat!((impossible)));
+    let trivial_ty = crate::ast_walk::walk::<SynthTy>(
+        &ast!({ trivial_type_form ; }),
+        &LazyWalkReses::new_wrapper(ty_env.clone()),
+    )?;
+    crate::ast_walk::walk::<crate::ty::UnpackTy>(
+        rhs,
+        &LazyWalkReses::new_wrapper(ty_env.clone()).with_context(trivial_ty),
+    )
+}
+
 pub fn extend_syntax() -> Rc<Form> {
-    use crate::earley::ParseContext;
     let perform_extension = move |pc: ParseContext, extension_info: Ast| -> ParseContext {
         let bnf_parts =
             // TODO: getting a `Shape` (the second element is the `(lit "in")`) must be a parser bug
-            extract!((&extension_info) Ast::Shape = (ref subs) =>
-                extract!((&subs[0]) Ast::IncompleteNode = (ref parts) => parts));
-
-        let nts: Vec<Name> =
-            bnf_parts.get_rep_leaf_or_panic(n("nt")).iter().map(|a| ast_to_name(*a)).collect();
-        let ops: Vec<bool> = bnf_parts
-            .get_rep_leaf_or_panic(n("operator"))
-            .iter()
-            .map(|a| a == &&Ast::Atom(n("::=also")))
-            .collect();
-        let rhses: Vec<&Ast> = bnf_parts.get_rep_leaf_or_panic(n("rhs"));
-
-        // Figure out the  the syntax extension:
-        let mut syn_env = pc.grammar;
-        for ((nt, extend), rhs) in nts.into_iter().zip(ops.into_iter()).zip(rhses.into_iter()) {
-            let rhs_form_pat =
-                FormPat::reflect(&crate::ast_walk::walk(rhs, &pc.eval_ctxt).unwrap());
-            syn_env = syn_env.set(
-                nt,
-                Rc::new(if extend {
-                    form_pat!((alt (, rhs_form_pat), (, (**syn_env.find_or_panic(&nt)).clone())))
-                } else {
-                    rhs_form_pat
-                }),
-            )
-        }
-
-        ParseContext { grammar: syn_env, type_ctxt: pc.type_ctxt, eval_ctxt: pc.eval_ctxt }
+            extract!((extension_info.c()) Shape = (ref subs) =>
+                extract!((subs[0].c()) IncompleteNode = (ref parts) => parts));
+        perform_extension(pc, bnf_parts)
     };
 
     let trivial_type_form = crate::core_type_forms::type_defn("unused", form_pat!((impossible)));
@@ -665,12 +763,7 @@ pub fn extend_syntax() -> Rc<Form> {
         name: n("extend_syntax"),
         grammar: Rc::new(form_pat!(
             [(lit "extend_syntax"),
-             (extend
-                [(star [(named "nt", atom),
-                   (named "operator", (alt (lit "::="), (lit "::=also"))),
-                   (named "rhs", (call "Syntax")),
-                   (lit ";")]),
-                 (lit "in")],
+             (extend [(, syntax_extension_clauses()), (lit "in")],
                 (named "body",
                     (import_phaseless [* ["rhs" == {trivial_type_form ; }]], (call "Expr"))),
                 perform_extension)])),
@@ -776,7 +869,7 @@ fn macro_definitions() {
             env.clone()
         ),
         Ok(assoc_n!(
-            "some_macro" => macro_type(&vec![n("T"), n("S")],
+            "some_macro" => macro_type(&[n("T"), n("S")],
                                        vec![(n("binding"), t_pat_type.clone()),
                                             (n("body"), s_expr_type.clone()),
                                             (n("val"), t_expr_type.clone())],
@@ -790,13 +883,13 @@ fn macro_types() {
     let t_expr_type = uty!({type_apply : (prim Expr) [T]});
 
     assert_eq!(
-        macro_type(&vec![], vec![(n("a"), int_expr_type.clone())], int_expr_type.clone()),
+        macro_type(&[], vec![(n("a"), int_expr_type.clone())], int_expr_type.clone()),
         uty!({fn :
             [{struct : [a {type_apply : (prim Expr) [{Int :}]}]}]
             {type_apply : (prim Expr) [{Int :}]}})
     );
     assert_eq!(
-        macro_type(&vec![n("T")], vec![(n("a"), t_expr_type.clone())], t_expr_type.clone()),
+        macro_type(&[n("T")], vec![(n("a"), t_expr_type.clone())], t_expr_type.clone()),
         uty!({forall_type : [T]
             {fn : [{struct : [a {type_apply : (prim Expr) [T]}]}]
                 {type_apply : (prim Expr) [T]}}})
@@ -815,19 +908,19 @@ fn type_basic_macro_invocation() {
         "int_var" => uty!({Int :}),
         "nat_var" => uty!({Nat :}),
         "basic_int_macro" =>
-            macro_type(&vec![], vec![(n("a"), int_expr_type.clone())], int_expr_type.clone()),
+            macro_type(&[], vec![(n("a"), int_expr_type.clone())], int_expr_type.clone()),
         "basic_t_macro" =>
-            macro_type(&vec![n("T")], vec![(n("a"), t_expr_type.clone())], t_expr_type.clone()),
+            macro_type(&[n("T")], vec![(n("a"), t_expr_type.clone())], t_expr_type.clone()),
         "basic_pattern_macro" =>
-            macro_type(&vec![n("T")], vec![(n("a"), t_pat_type.clone())], t_pat_type.clone()),
+            macro_type(&[n("T")], vec![(n("a"), t_pat_type.clone())], t_pat_type.clone()),
         "let_like_macro" =>
-            macro_type(&vec![n("T"), n("S")],
+            macro_type(&[n("T"), n("S")],
                        vec![(n("val"), t_expr_type.clone()),
                             (n("binding"), t_pat_type.clone()),
                             (n("body"), s_expr_type.clone())],
                        s_expr_type.clone()),
         "pattern_cond_like_macro" =>
-            macro_type(&vec![n("T"), n("S")],
+            macro_type(&[n("T"), n("S")],
                        vec![(n("t"), t_type_type.clone()),
                             (n("body"), t_pat_type.clone()),
                             (n("cond_expr"), int_expr_type.clone())], // (would really be a bool)
@@ -945,7 +1038,7 @@ fn type_ddd_macro() {
         "int_var" => uty!({Int :}),
         "nat_var" => uty!({Nat :}),
         "let_like_macro" =>
-            macro_type(&vec![n("T"), n("S")],
+            macro_type(&[n("T"), n("S")],
                        vec![(n("val"), t_rep_expr_type.clone()),
                             (n("binding"), t_rep_pat_type.clone()),
                             (n("body"), s_expr_type.clone())],
@@ -1,13 +1,13 @@
 use crate::{
-    ast::{Ast, Ast::*},
-    ast_walk::{squirrel_away, WalkRule::*},
+    ast::{Ast, AstContents::*},
+    ast_walk::{squirrel_away, LazyWalkReses, WalkRule::*},
     core_forms::vr_to_name,
     core_type_forms::{less_quoted_ty, more_quoted_ty, nt_is_positive, nt_to_type},
     form::{Both, Form, Negative, Positive},
     grammar::{FormPat, SynEnv},
     name::*,
-    runtime::eval::{Destructure, Eval, QQuote, QQuoteDestr},
-    ty::Ty,
+    runtime::eval::{Destructure, Eval, QQuote, QQuoteDestr, Value::AbstractSyntax},
+    ty::{SynthTy, Ty, TypeError, UnpackTy},
     util::assoc::Assoc,
     walk_mode::{NegativeWalkMode, WalkMode},
 };
@@ -107,22 +107,23 @@ fn change_mu_opacity(parts: crate::ast_walk::LazyWalkReses<MuProtect>) -> Result
         }
 
         if opacity + delta == 0 {
-            if let ExtendEnv(node, _) = parts.get_term(n("body")) {
-                return Ok(Ty(*node));
+            if let ExtendEnv(ref node, _) = *parts.get_term(n("body")).c() {
+                return Ok(Ty((**node).clone()));
             } else {
                 icp!("mal-formed mu_type")
             }
         }
     }
-    match parts.this_ast {
-        Ast::Node(f, mut mu_parts, export) => {
+    match parts.this_ast.c() {
+        Node(f, mu_parts, export) => {
+            let mut mu_parts = mu_parts.clone();
             if let Some(opacity) = opacity {
                 mu_parts.add_leaf(
                     n("opacity_for_different_phase"),
-                    Ast::Atom(n(&(opacity + delta).to_string())),
+                    raw_ast!(Atom(n(&(opacity + delta).to_string()))),
                 );
             }
-            Ok(Ty(Ast::Node(f, mu_parts, export)))
+            Ok(Ty(parts.this_ast.with_c(Node(f.clone(), mu_parts, export.clone()))))
         }
         _ => icp!(),
     }
@@ -141,6 +142,19 @@ impl WalkMode for MuProtect {
     fn get_walk_rule(f: &Form) -> crate::ast_walk::WalkRule<MuProtect> {
         if f.name == n("mu_type") {
             cust_rc_box!(change_mu_opacity)
+        } else if f.name == n("dotdotdot") {
+            // Drivers have to stay names (if unbound, they come from a different phase).
+            cust_rc_box!(|parts| {
+                let body = parts.get_res(n("body"))?;
+                match parts.this_ast.c() {
+                    Node(f, ddd_parts, export) => {
+                        let mut ddd_parts = ddd_parts.clone();
+                        ddd_parts.add_leaf(n("body"), body.concrete());
+                        Ok(Ty(parts.this_ast.with_c(Node(f.clone(), ddd_parts, export.clone()))))
+                    }
+                    _ => icp!(),
+                }
+            })
         } else {
             LiteralLike
         }
@@ -149,15 +163,18 @@ impl WalkMode for MuProtect {
 
     fn walk_var(name: Name, parts: &crate::ast_walk::LazyWalkReses<MuProtect>) -> Result<Ty, ()> {
         if parts.extra_info <= 0 {
-            return Ok(Ty(VariableReference(name)));
+            return Ok(Ty(raw_ast!(VariableReference(name))));
         }
-        Ok(parts.env.find(&name).map(Clone::clone).unwrap_or_else(|| {
+        Ok(parts.env.find(&name).cloned().unwrap_or_else(|| {
             ty!({"Type" "mu_type" :
-                "opacity_for_different_phase" => (, Ast::Atom(n(&parts.extra_info.to_string()))),
-                "param" => [(import [prot "param"] (, Ast::VariableReference(name)))],
-                "body" => (import [* [prot "param"]] (, Ast::VariableReference(name)))})
+                "opacity_for_different_phase" => (, raw_ast!(Atom(n(&parts.extra_info.to_string())))),
+                "param" => [(import [prot "param"] (, raw_ast!(VariableReference(name))))],
+                "body" => (import [* [prot "param"]] (, raw_ast!(VariableReference(name))))})
         }))
     }
+
+    // A name bound inside the type (e.g. by `forall`) isn't from a different phase:
+    fn underspecified(name: Name) -> Ty { Ty(raw_ast!(VariableReference(name))) }
 }
 
 impl WalkMode for UnusedNegativeMuProtect {
@@ -181,6 +198,13 @@ impl NegativeWalkMode for UnusedNegativeMuProtect {
 //  (It only makes sense inside a `quote`.)
 // However, this would leave us with one `unquote` form available per level of quotation
 
+/// Is an unquotation of something of type `ty`, where the grammar expects an `nt`,
+///  a reference to an interpolated name (e.g. `.[ ,[nm], : Int . (plus ,[nm], one) ].`)?
+fn is_ident_reference(nt: Name, ty: &Ty) -> bool {
+    (nt == n("Expr") || nt == n("Type"))
+        && matches!(ty.0.c(), Node(ref f, _, _) if f.name == n("Ident"))
+}
+
 /// Generate a (depth-1) unquoting form.
 /// `pos_quot` is true iff the quotation itself (and thus the interpolation) is positive.
 pub fn unquote(nt: Name, pos_quot: bool) -> Rc<FormPat> {
@@ -204,9 +228,10 @@ pub fn unquote_form(nt: Name, pos_quot: bool, depth: u8) -> Rc<Form> {
             //  so it's optional
             Rc::new(if pos_quot {
                 form_pat!((delim form_delim_start, "[",
-                    [(named "nt", (anyways (, VariableReference(nt)))),
+                    [(named "nt", (anyways (, raw_ast!(VariableReference(nt))))),
                      (alt
                         [],
+                        [(name_lit__by_name nt), (lit "|")],
                         [(name_lit__by_name nt),
                          (call "DefaultSeparator"), (scan r"(<)"),
                          (named "ty_annot", (call "Type")),
@@ -214,9 +239,10 @@ pub fn unquote_form(nt: Name, pos_quot: bool, depth: u8) -> Rc<Form> {
                      (named "body", (-- depth (call "Expr")))]))
             } else {
                 form_pat!((delim form_delim_start, "[",
-                    [(named "nt", (anyways (, VariableReference(nt)))),
+                    [(named "nt", (anyways (, raw_ast!(VariableReference(nt))))),
                      (alt
                         [],
+                        [(name_lit__by_name nt), (lit "|")],
                         [(name_lit__by_name nt),
                          (call "DefaultSeparator"), (scan r"(<)"),
                          (named "ty_annot", (call "Type")),
@@ -224,10 +250,29 @@ pub fn unquote_form(nt: Name, pos_quot: bool, depth: u8) -> Rc<Form> {
                          (lit "|")]),
                      (named "body", (-- depth (call "Pat")))]))
             }),
-        type_compare: Positive(NotWalked), // this is not a type form
+        type_compare: // this is not a type form...
+            if nt == n("Ident") {
+                // ...but a quoted type can have one as a `forall` parameter. Leave it alone:
+                Both(cust_rc_box!(|parts| Ok(Ty(parts.this_ast))),
+                     cust_rc_box!(|parts| {
+                         if parts.context_elt().0 == parts.this_ast {
+                             Ok(Assoc::new())
+                         } else {
+                             Err(crate::ty::TyErr::Mismatch(
+                                 parts.context_elt().clone(), Ty(parts.this_ast)))
+                         }
+                     }))
+            } else {
+                Positive(NotWalked)
+            },
         synth_type:
+            // Interpolated atoms (usually binders) aren't walked by the forms that contain them,
+            //  so the quotation checks them itself (see `check_interpolated_atoms`).
+            if nt == n("Ident") {
+                Both(cust_rc_box!(|_| Ok(ty!({"Type" "Ident" :}))),
+                     cust_rc_box!(|_| Ok(Assoc::new())))
             // `nt_is_positive` and `pos_quot` have opposite roles from `quote`
-            if nt_is_positive(nt) {
+            } else if nt_is_positive(nt) {
                 //  For example: (this quotation could be positive or negative)
                 // (nt_is_positive is true in this example, though)
                 // ` '[Expr | .[a : Int . ,[Expr<String> | body], ]. ]' `
@@ -241,6 +286,11 @@ pub fn unquote_form(nt: Name, pos_quot: bool, depth: u8) -> Rc<Form> {
                             // TODO: check annotation if present
 
                             let mut res = unquote_parts.get_res(n("body"))?; // `Expr<String>`
+                            if depth == 1 && is_ident_reference(nt, &res) {
+                                // It refers to an interpolated binder, so anything goes:
+                                return Ok(<crate::ty_compare::Subtype as WalkMode>::underspecified(
+                                    n("interpolated")));
+                            }
                             for _ in 0..(depth-1) {
                                 res = less_quoted_ty(&res, None, &ast_for_errors)?;
                             } // HACK: we only know the last `nt` to expect
@@ -253,7 +303,7 @@ pub fn unquote_form(nt: Name, pos_quot: bool, depth: u8) -> Rc<Form> {
                             let expected_type = unquote_parts.get_res(n("ty_annot"))?;
 
                             let mut ctxt_elt = expected_type.clone();
-                            for _ in 0..(depth-1) {
+                            if depth > 1 {
                                 unimplemented!("We may need a stack of what NTs are quoted")
                             }
                             ctxt_elt = more_quoted_ty(&ctxt_elt, nt);
@@ -276,7 +326,7 @@ pub fn unquote_form(nt: Name, pos_quot: bool, depth: u8) -> Rc<Form> {
                                                       -(i32::from(depth)));
 
                         let mut ctxt_elt = ctxt_elt;
-                        for _ in 0..(depth-1) {
+                        if depth > 1 {
                             unimplemented!("We may need a stack of what NTs are quoted")
                         }
                         ctxt_elt = more_quoted_ty(&ctxt_elt, nt);
@@ -310,7 +360,15 @@ pub fn unquote_form(nt: Name, pos_quot: bool, depth: u8) -> Rc<Form> {
             Both( // TODO: double-check that `pos` and `neg` don't matter here
                 cust_rc_box!( move | unquote_parts | {
                     let lq_parts = unquote_parts.switch_mode::<Eval>();
-                    crate::ast_walk::walk::<Eval>(lq_parts.get_term_ref(n("body")), &lq_parts)
+                    let res =
+                        crate::ast_walk::walk::<Eval>(lq_parts.get_term_ref(n("body")), &lq_parts)?;
+                    // An `Ident` where an expression or type belongs is a reference:
+                    if let AbstractSyntax(ref a) = res {
+                        if let (Atom(name), true) = (a.c(), nt == n("Expr") || nt == n("Type")) {
+                            return Ok(AbstractSyntax(a.with_c(VariableReference(*name))));
+                        }
+                    }
+                    Ok(res)
                 }),
                 cust_rc_box!( move | unquote_parts | {
                     let context = unquote_parts.context_elt().clone();
@@ -338,12 +396,14 @@ pub fn dotdotdot(nt: Name) -> Rc<FormPat> {
 
 // Once it's possible to write `where Mode::Elt = Ty and Mode::Err = <whatever>`,
 //  this can be turned into a function.
-// The behavior of `...[]...` is identical in positive and negative modes.
+// The behavior of `...[]...` is nearly identical in positive and negative modes.
+// Produces the `LazyWalkReses` to get the body's result from,
+//  along with the drivers of the `:::[]:::` type, if the repetition has an unknown length.
 macro_rules! ddd_type__body {
     ($ddd_parts:expr) => {
         {
             let drivers : Vec<Name> = $ddd_parts.get_rep_term(n("driver")).into_iter().map(|a| {
-                match a {
+                match a.c() {
                     QuoteLess(ref d, _) => vr_to_name(d),
                     _ => icp!()
                 }
@@ -357,12 +417,16 @@ macro_rules! ddd_type__body {
 
             let mut walked_env = Assoc::new();
 
-            let repeats = match ddd_parts_uq.env.find(&drivers[0]) {
-                Some(&Ty(Node(ref form, ref parts, _))) if form.name == n("tuple") => {
-                    parts.get_rep_leaf_or_panic(n("component")).len()
+            let (repeats, abstract_drivers) = match ddd_parts_uq.env.find(&drivers[0]) {
+                Some(Ty(t)) if matches!(t.c(), Node(f, _, _) if f.name == n("tuple")) => {
+                    (t.node_parts().get_rep_leaf_or_panic(n("component")).len(), None)
                 }
                 // TODO: what if some are `tuple` and others are `dotdotdot`?
-                Some(&Ty(Node(ref form, _, _))) if form.name == n("dotdotdot") => 1,
+                Some(Ty(t)) if matches!(t.c(), Node(f, _, _) if f.name == n("dotdotdot")) => {
+                    let ty_drivers = t.node_parts().get_rep_leaf_or_panic(n("driver"))
+                        .into_iter().cloned().collect::<Vec<Ast>>();
+                    (1, Some(ty_drivers))
+                }
                 Some(other_t) => {
                     ty_err!(UnableToDestructure(other_t.clone(), n("tuple"))
                                 at ddd_parts_uq.this_ast);
@@ -373,24 +437,24 @@ macro_rules! ddd_type__body {
             for i in 0..repeats {
                 for (name, ty) in ddd_parts_uq.env.iter_pairs() {
                     if drivers.contains(name) {
-                        walked_env = walked_env.set(*name, match ty {
-                            Ty(Node(ref form, ref parts, _)) if form.name == n("tuple") => {
+                        walked_env = walked_env.set(*name, match *ty.0.c() {
+                            Node(ref form, ref parts, _) if form.name == n("tuple") => {
                                 Ty(parts.get_rep_leaf_or_panic(n("component"))[i].clone())
                             }
-                            Ty(Node(ref form, ref parts, _))
+                            Node(ref form, ref parts, _)
                                 if form.name == n("dotdotdot") =>
                             {
                                 Ty(parts.get_leaf_or_panic(&n("body")).clone())
                             }
-                            t => ty_err!(UnableToDestructure(t.clone(), n("tuple"))
-                                            at t.0),
+                            _ => ty_err!(UnableToDestructure(ty.clone(), n("tuple"))
+                                            at ty.0),
                         });
                     } else {
                         walked_env = walked_env.set(*name, ty.clone());
                     }
                 }
             }
-            ddd_parts_uq.with_environment(walked_env).quote_more(None).get_res(n("body"))
+            (ddd_parts_uq.with_environment(walked_env).quote_more(None), abstract_drivers)
         }
     };
 }
@@ -405,8 +469,28 @@ pub fn dotdotdot_form(nt: Name) -> Rc<Form> {
              (named "body", (call_by_name nt))]))),
         type_compare: Positive(NotWalked), // this is not a type form
         synth_type: Both(
-            cust_rc_box!(|ddd_parts| { ddd_type__body!(ddd_parts) }),
-            cust_rc_box!(|ddd_parts| { ddd_type__body!(ddd_parts) }),
+            cust_rc_box!(|ddd_parts| {
+                let (body_parts, abstract_drivers) = ddd_type__body!(ddd_parts);
+                let res = body_parts.get_res(n("body"))?;
+                // If we don't know how many repetitions there are, we don't know how many types:
+                Ok(match abstract_drivers {
+                    Some(ty_drivers) => ty!({"Type" "dotdotdot" :
+                        "driver" => (,seq ty_drivers), "body" => (, res.concrete())}),
+                    None => res,
+                })
+            }),
+            cust_rc_box!(|ddd_parts| {
+                let (body_parts, abstract_drivers) = ddd_type__body!(ddd_parts);
+                // Likewise, we expect the `:::[]:::` type for the whole repetition:
+                let body_parts = match (abstract_drivers, body_parts.context_elt().0.c()) {
+                    (Some(_), Node(f, ddd_ty_parts, _)) if f.name == n("dotdotdot") => {
+                        let body_ty = Ty(ddd_ty_parts.get_leaf_or_panic(&n("body")).clone());
+                        body_parts.with_context(body_ty)
+                    }
+                    _ => body_parts,
+                };
+                body_parts.get_res(n("body"))
+            }),
         ),
         // An evaluate-time version of this might be a good idea;
         //  it might be all that's needed to implement variable-number-of-argument functions.
@@ -423,7 +507,7 @@ pub fn dotdotdot_form(nt: Name) -> Rc<Form> {
             let drivers: Vec<Name> = ddd_parts_uq
                 .get_rep_term(n("driver"))
                 .into_iter()
-                .map(|a| match a {
+                .map(|a| match a.c() {
                     QuoteLess(ref d, _) => vr_to_name(d),
                     _ => icp!(),
                 })
@@ -462,7 +546,7 @@ pub fn dotdotdot_form(nt: Name) -> Rc<Form> {
             }
 
             // HACK: this signals to `LiteralLike` that it needs to splice the sequence
-            Ok(Value::from_ast(&Shape(reps)))
+            Ok(Value::from_ast(&raw_ast!(Shape(reps))))
         })),
     })
 }
@@ -476,6 +560,134 @@ pub fn dotdotdot_form(nt: Name) -> Rc<Form> {
 // Everything (except `unquote` and `dotdotdot`!) is LiteralLike.
 // Furthermore, the direction of the walk is determined by the direction of the original quotation.
 
+// Interpolated atoms (e.g. `.[ ,[name], : Int . ⋯ ].`) are usually binders,
+//  and the forms that bind them don't walk them, so the quotation has to check them itself.
+
+/// Find the `Ident` unquotations in `a` (quoted syntax),
+///  along with the drivers of each `...[]...` that each is under.
+fn interpolated_atoms(a: &Ast, drivers: &[Vec<Name>], res: &mut Vec<(Ast, Vec<Vec<Name>>)>) {
+    match a.c() {
+        Node(f, parts, _)
+            if f.name == n("unquote")
+                && parts.get_leaf(n("nt")).map(vr_to_name) == Some(n("Ident")) =>
+        {
+            res.push((parts.get_leaf_or_panic(&n("body")).clone(), drivers.to_vec()));
+        }
+        Node(f, parts, _) if f.name == n("dotdotdot") => {
+            let mut drivers = drivers.to_vec();
+            drivers.push(
+                parts
+                    .get_rep_leaf_or_panic(n("driver"))
+                    .into_iter()
+                    .map(|d| match d.c() {
+                        QuoteLess(ref d, _) => vr_to_name(d),
+                        _ => icp!(),
+                    })
+                    .collect(),
+            );
+            interpolated_atoms(parts.get_leaf_or_panic(&n("body")), &drivers, res);
+        }
+        Node(_, parts, _) => {
+            parts.map(&mut |part| interpolated_atoms(part, drivers, res));
+        }
+        ExtendEnv(body, _) | ExtendEnvPhaseless(body, _) => interpolated_atoms(body, drivers, res),
+        // (Other unquotations' bodies are under `QuoteLess`, and are checked by their own forms.
+        //  Nested quotations are checked when they're walked.)
+        _ => {}
+    }
+}
+
+/// Check that an interpolated atom (the body of an `Ident` unquotation) is an `Ident`,
+///  once for each repetition of the `...[]...`s (if any) it's under.
+fn check_interpolated_atom(
+    body: &Ast,
+    drivers: &[Vec<Name>],
+    parts: &LazyWalkReses<SynthTy>,
+) -> Result<(), TypeError>
+{
+    let (group, rest) = match drivers.split_first() {
+        None => {
+            let res = match body.c() {
+                QuoteLess(ref lq_body, _) => crate::ast_walk::walk::<SynthTy>(lq_body, parts)?,
+                _ => icp!("malformed unquotation"),
+            };
+            crate::ty_compare::is_subtype(&res, &ty!({"Type" "Ident" :}), parts)
+                .map_err(|e| crate::util::err::sp(e, body.clone()))?;
+            return Ok(());
+        }
+        Some(split) => split,
+    };
+
+    // This is like `ddd_type__body`, except that we're already at the unquoted level.
+    let repeats = match parts.env.find(&group[0]) {
+        Some(Ty(t)) if matches!(t.c(), Node(f, _, _) if f.name == n("tuple")) => {
+            t.node_parts().get_rep_leaf_or_panic(n("component")).len()
+        }
+        Some(Ty(t)) if matches!(t.c(), Node(f, _, _) if f.name == n("dotdotdot")) => 1,
+        Some(other_t) => ty_err!(UnableToDestructure(other_t.clone(), n("tuple")) at body),
+        None => ty_err!(UnboundName(group[0]) at body),
+    };
+    for i in 0..repeats {
+        let mut env = parts.env.clone();
+        for driver in group {
+            let driver_ty = match parts.env.find(driver) {
+                Some(driver_ty) => driver_ty,
+                None => ty_err!(UnboundName(*driver) at body),
+            };
+            env = env.set(*driver, match driver_ty.0.c() {
+                Node(ref form, ref parts, _) if form.name == n("tuple") => {
+                    match parts.get_rep_leaf_or_panic(n("component")).get(i) {
+                        Some(component) => Ty((*component).clone()),
+                        None => ty_err!(UnableToDestructure(driver_ty.clone(), n("tuple")) at body),
+                    }
+                }
+                Node(ref form, ref parts, _) if form.name == n("dotdotdot") => {
+                    Ty(parts.get_leaf_or_panic(&n("body")).clone())
+                }
+                _ => ty_err!(UnableToDestructure(driver_ty.clone(), n("tuple")) at body),
+            });
+        }
+        check_interpolated_atom(body, rest, &parts.with_environment(env))?;
+    }
+    Ok(())
+}
+
+/// The quoted body, without its `QuoteMore`.
+fn quoted_body<Mode: WalkMode>(quote_parts: &LazyWalkReses<Mode>) -> Ast {
+    match quote_parts.get_term_ref(n("body")).c() {
+        QuoteMore(ref body, _) => (**body).clone(),
+        _ => icp!("malformed quotation"),
+    }
+}
+
+/// Check the atoms interpolated into an expression quotation.
+fn check_interpolated_atoms(quote_parts: &LazyWalkReses<SynthTy>) -> Result<(), TypeError> {
+    let mut atoms = vec![];
+    interpolated_atoms(&quoted_body(quote_parts), &[], &mut atoms);
+    for (body, drivers) in atoms {
+        check_interpolated_atom(&body, &drivers, quote_parts)?;
+    }
+    Ok(())
+}
+
+/// Bind the patterns that match atoms in a pattern quotation (each matches an `Ident`).
+fn unpack_interpolated_atoms(
+    quote_parts: &LazyWalkReses<UnpackTy>,
+) -> Result<Assoc<Name, Ty>, TypeError> {
+    let mut atoms = vec![];
+    interpolated_atoms(&quoted_body(quote_parts), &[], &mut atoms);
+    let mut res = Assoc::new();
+    for (body, _) in atoms {
+        let body = match body.c() {
+            QuoteLess(ref body, _) => (**body).clone(),
+            _ => icp!("malformed unquotation"),
+        };
+        let ident_parts = quote_parts.with_context(ty!({"Type" "Ident" :}));
+        res = res.set_assoc(&crate::ast_walk::walk::<UnpackTy>(&body, &ident_parts)?);
+    }
+    Ok(res)
+}
+
 pub fn quote(pos: bool) -> Rc<Form> {
     use crate::{
         earley::ParseContext,
@@ -483,15 +695,15 @@ pub fn quote(pos: bool) -> Rc<Form> {
     };
 
     let perform_quotation = move |pc: ParseContext, starter_info: Ast| -> ParseContext {
-        let starter_nt = match starter_info {
-            IncompleteNode(ref parts) => vr_to_name(&parts.get_leaf_or_panic(&n("nt"))),
+        let starter_nt = match starter_info.c() {
+            IncompleteNode(ref parts) => vr_to_name(parts.get_leaf_or_panic(&n("nt"))),
             _ => icp!("malformed quotation"),
         };
         fn already_has_unquote(fp: &FormPat) -> bool {
             match *fp {
-                Alt(ref parts) => parts.iter().any(|sub_fp| already_has_unquote(&*sub_fp)),
+                Alt(ref parts) => parts.iter().any(|sub_fp| already_has_unquote(sub_fp)),
                 Biased(ref plan_a, ref plan_b) => {
-                    already_has_unquote(&*plan_a) || already_has_unquote(&*plan_b)
+                    already_has_unquote(plan_a) || already_has_unquote(plan_b)
                 }
                 Scope(ref f, _) => f.name == n("unquote"),
                 _ => false,
@@ -503,10 +715,16 @@ pub fn quote(pos: bool) -> Rc<Form> {
         let new_grammar = pc
             .grammar
             .keyed_map_borrow_f(&mut |nt: &Name, nt_def: &Rc<FormPat>| {
-                if already_has_unquote(nt_def)
-               // HACK: this is to avoid hitting "starterer". TODO: find a better way
-               || (nt != &n("Expr") && nt != &n("Pat") && nt != &n("Type"))
-                {
+                if already_has_unquote(nt_def) {
+                    nt_def.clone()
+                } else if nt == &n("DefaultAtom") {
+                    // Binder names (and other atoms) can be interpolated; they have type `Ident`.
+                    Rc::new(Biased(
+                        unquote(n("Ident"), pos),
+                        Rc::new(Biased(dotdotdot(n("Ident")), nt_def.clone())),
+                    ))
+                } else if nt != &n("Expr") && nt != &n("Pat") && nt != &n("Type") {
+                    // HACK: this is to avoid hitting "starterer". TODO: find a better way
                     nt_def.clone()
                 } else {
                     // TODO: maybe we should only insert `dotdotdot` in repetition positions?
@@ -520,7 +738,7 @@ pub fn quote(pos: bool) -> Rc<Form> {
                 n("QuotationBody"),
                 Rc::new(form_pat!(
                     // HACK: The `nt` from outside isn't in the same Scope, it seems:
-                    [(named "nt", (anyways (, VariableReference(starter_nt)))),
+                    [(named "nt", (anyways (, raw_ast!(VariableReference(starter_nt))))),
                      (alt
                         [],
                         [(call "DefaultSeparator"), (scan r"(<)"),
@@ -543,6 +761,7 @@ pub fn quote(pos: bool) -> Rc<Form> {
         type_compare: Both(NotWalked, NotWalked), // Not a type
         synth_type: if pos {
             Positive(cust_rc_box!(|quote_parts| {
+                check_interpolated_atoms(&quote_parts)?;
                 if nt_is_positive(vr_to_name(&quote_parts.get_term(n("nt")))) {
                     // TODO #9: if the user provides an annotation, check it!
                     Ok(ty!({"Type" "type_apply" :
@@ -583,23 +802,25 @@ pub fn quote(pos: bool) -> Rc<Form> {
             Negative(cust_rc_box!(|quote_parts| {
                 // There's no need for a type annotation
                 let nt = vr_to_name(&quote_parts.get_term(n("nt")));
+                let atoms_env = unpack_interpolated_atoms(&quote_parts)?;
                 if nt_is_positive(nt) {
                     // TODO #9: check that this matches the type annotation, if provided!
-                    quote_parts.get_res(n("body"))
+                    Ok(quote_parts.get_res(n("body"))?.set_assoc(&atoms_env))
                 } else {
                     let new_context =
                         less_quoted_ty(quote_parts.context_elt(), Some(nt), &quote_parts.this_ast)?;
                     // TODO #9: check that this matches the type annotation, if provided!
-                    quote_parts.with_context(new_context).get_res(n("body"))
+                    let body_env = quote_parts.with_context(new_context).get_res(n("body"))?;
+                    Ok(body_env.set_assoc(&atoms_env))
                 }
             }))
         },
         eval: if pos {
             Positive(cust_rc_box!(|quote_parts| {
                 let mq_parts = quote_parts.switch_mode::<QQuote>().quote_more(None);
-                match mq_parts.get_term_ref(n("body")) {
+                match mq_parts.get_term_ref(n("body")).c() {
                     // Strip the `QuoteMore`:
-                    QuoteMore(ref a, _) => crate::ast_walk::walk::<QQuote>(&*a, &mq_parts),
+                    QuoteMore(ref a, _) => crate::ast_walk::walk::<QQuote>(a, &mq_parts),
                     _ => icp!(),
                 }
             }))
@@ -609,10 +830,10 @@ pub fn quote(pos: bool) -> Rc<Form> {
 
                 let mq_parts =
                     quote_parts.switch_mode::<QQuoteDestr>().quote_more(None).with_context(context);
-                match mq_parts.get_term_ref(n("body")) {
+                match mq_parts.get_term_ref(n("body")).c() {
                     // Strip the `QuoteMore`:
                     QuoteMore(ref body, _) => {
-                        crate::ast_walk::walk::<QQuoteDestr>(&*body, &mq_parts)
+                        crate::ast_walk::walk::<QQuoteDestr>(body, &mq_parts)
                     }
                     _ => icp!(),
                 }
@@ -642,7 +863,7 @@ fn quote_unquote_eval_basic() {
         expr: &Ast,
         env: Assoc<Name, Value>,
         qenv: Assoc<Name, Value>,
-    ) -> Result<Value, ()>
+    ) -> Result<Value, crate::runtime::eval::RuntimeError>
     {
         crate::ast_walk::walk::<Eval>(expr, &LazyWalkReses::new_mq_wrapper(env, vec![qenv]))
     }
@@ -652,7 +873,7 @@ fn quote_unquote_eval_basic() {
         env: Assoc<Name, Value>,
         qenv: Assoc<Name, Value>,
         ctxt: Value,
-    ) -> Result<Assoc<Name, Value>, ()>
+    ) -> Result<Assoc<Name, Value>, crate::runtime::eval::RuntimeError>
     {
         crate::ast_walk::walk::<Destructure>(
             pat,
@@ -1173,7 +1394,7 @@ fn use_dotdotdot() {
         expr: &Ast,
         eval_env: Assoc<Name, Value>,
         eval_qenv: Assoc<Name, Value>,
-    ) -> Result<Value, ()>
+    ) -> Result<Value, crate::runtime::eval::RuntimeError>
     {
         crate::ast_walk::walk::<Eval>(
             expr,
@@ -1295,7 +1516,7 @@ fn use_dotdotdot() {
             ddd_abs_env.clone(),
             qenv.clone()
         ),
-        Ok(uty!({type_apply : (prim Expr) [{tuple : [{Int :}]}]}))
+        Ok(uty!({type_apply : (prim Expr) [{tuple : [{dotdotdot : [T] {Int :}}]}]}))
     );
 }
 
@@ -45,7 +45,7 @@
 use crate::{
     ast::*,
     ast_walk::{
-        walk,
+        walk, LazyWalkReses,
         WalkRule::{self, *},
     },
     core_forms::{ast_to_name, vr_to_name},
@@ -104,8 +104,24 @@ thread_local! {
     })
 }
 
+thread_local! {
+    // Not needed by the user.
+    // Stands in for a type annotation that the user left out (e.g. on a `lambda` parameter).
+    // It synthesizes to a fresh underdetermined type, which unification then (hopefully) solves.
+    pub static inferred_type : Rc<Form> = Rc::new(Form {
+        name: n("inferred_type"),
+        grammar: Rc::new(form_pat!([])),
+        type_compare: Both(LiteralLike, LiteralLike),
+        synth_type: Positive(cust_rc_box!(|_| {
+            Ok(<crate::ty_compare::Subtype as WalkMode>::underspecified(n("param")))
+        })),
+        quasiquote: Both(LiteralLike, LiteralLike),
+        eval: Positive(NotWalked)
+    })
+}
+
 pub fn get__primitive_type(called: Name) -> Ty {
-    ty!({primitive_type.with(|p_t| p_t.clone()) ; "name" => (, Atom(called))})
+    ty!({primitive_type.with(|p_t| p_t.clone()) ; "name" => (, raw_ast!(Atom(called)))})
 }
 
 fn is_primitive(form: &Rc<Form>) -> bool { form == &primitive_type.with(|p_t| p_t.clone()) }
@@ -115,9 +131,9 @@ pub fn make_core_syn_env_types() -> SynEnv {
     // "For programming languages ... three levels have proved sufficient."
 
     // kinds
-    let _type_kind = simple_form("Type", form_pat!((lit "*")));
-    let _higher_kind = simple_form(
-        "higher",
+    let type_kind_form = simple_form("type_kind", form_pat!((lit "*")));
+    let higher_kind_form = simple_form(
+        "higher_kind",
         form_pat!(
         (delim "k[", "[",
             [ (star (named "param", (call "kind"))), (lit "->"), (named "res", (call "kind"))])),
@@ -129,7 +145,30 @@ pub fn make_core_syn_env_types() -> SynEnv {
         form_pat!((delim "[", "[",
                 [ (star (named "param", (call "Type"))), (lit "->"),
                   (named "ret", (call "Type") ) ])),
-        LiteralLike, // synth is normal
+        cust_rc_box!(move |fn_parts| {
+            // A `:::[ ]:::` parameter whose drivers are known produces a tuple of types;
+            //  each of those is a parameter of its own.
+            let mut params = vec![];
+            for (param_stx, param_res) in fn_parts
+                .get_rep_term(n("param"))
+                .iter()
+                .zip(fn_parts.get_rep_res(n("param"))?)
+            {
+                match (param_stx.c(), param_res.0.c()) {
+                    (Node(stx_f, _, _), Node(res_f, tuple_parts, _))
+                        if stx_f.name == n("dotdotdot") && res_f.name == n("tuple") =>
+                    {
+                        let components = tuple_parts.get_rep_leaf_or_panic(n("component"));
+                        params.extend(components.into_iter().cloned());
+                    }
+                    _ => params.push(param_res.concrete()),
+                }
+            }
+            let res = ty!({"Type" "fn" :
+                "param" => (,seq params),
+                "ret" => (, fn_parts.get_res(n("ret"))?.concrete())});
+            Ok(Ty(res.0.with_loc(fn_parts.this_ast.loc())))
+        }),
         Both(
             LiteralLike,
             cust_rc_box!(move |fn_parts| {
@@ -139,18 +178,63 @@ pub fn make_core_syn_env_types() -> SynEnv {
 
                 let expd_params = fn_parts.get_rep_term(n("param"));
                 let actl_params = actual_parts.get_rep_leaf_or_panic(n("param"));
-                if expd_params.len() != actl_params.len() {
+
+                // A `:::[ ]:::` parameter stands for however many parameters are left over
+                //  (unless we're comparing it against another one).
+                let is_ddd = |p: &Ast| matches!(p.c(), Node(f, _, _) if f.name == n("dotdotdot"));
+                let ddd_idx = if actl_params.iter().any(|p| is_ddd(p)) {
+                    None
+                } else {
+                    expd_params.iter().position(is_ddd)
+                };
+                let length_ok = match ddd_idx {
+                    Some(_) => expd_params.len() <= actl_params.len() + 1,
+                    None => expd_params.len() == actl_params.len(),
+                };
+                if !length_ok {
                     return Err(TyErr::LengthMismatch(
                         actl_params.iter().map(|&a| Ty(a.clone())).collect(),
                         expd_params.len(),
                     ));
                 }
-                for (p_expected, p_got) in expd_params.iter().zip(actl_params.iter()) {
-                    // Parameters have reversed subtyping:
+                let mut actl_params: Vec<Ast> = actl_params.into_iter().cloned().collect();
+                if let Some(ddd_idx) = ddd_idx {
+                    let rest_len = expd_params.len() - ddd_idx - 1;
+                    let rest = actl_params.split_off(actl_params.len() - rest_len);
+                    let group = actl_params.split_off(ddd_idx);
+                    let ddd_parts = match expd_params[ddd_idx].c() {
+                        Node(_, ddd_parts, _) => ddd_parts,
+                        _ => icp!(),
+                    };
+                    let drivers: Vec<Ast> =
+                        ddd_parts.get_rep_leaf_or_panic(n("driver")).into_iter().cloned().collect();
+                    let body = Ty(ddd_parts.get_leaf_or_panic(&n("body")).clone());
+                    let envs = ddd_driver_envs(&drivers, group.len(), &fn_parts.env)?;
+                    for (env, p_got) in envs.into_iter().zip(group.iter()) {
+                        // Like the other parameters, reversed:
+                        let _: Assoc<Name, Ty> = walk::<Subtype>(
+                            p_got,
+                            &fn_parts.with_environment(env).with_context(body.clone()),
+                        )
+                        .map_err(unreverse_mismatch)?;
+                    }
+                    actl_params.extend(rest);
+                }
+
+                let paired_params = expd_params
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| Some(*i) != ddd_idx)
+                    .map(|(_, p)| p)
+                    .zip(actl_params.iter());
+                for (p_expected, p_got) in paired_params {
+                    // Parameters have reversed subtyping
+                    //  (so un-reverse any mismatch, to keep "got" and "expected" straight):
                     let _: Assoc<Name, Ty> = walk::<Subtype>(
-                        *p_got,
+                        p_got,
                         &fn_parts.with_context(Ty::new(p_expected.clone())),
-                    )?;
+                    )
+                    .map_err(unreverse_mismatch)?;
                 }
 
                 walk::<Subtype>(
@@ -201,13 +285,13 @@ pub fn make_core_syn_env_types() -> SynEnv {
                         }
                         found = true;
                         let _ = walk::<Subtype>(
-                            &got_ty,
+                            got_ty,
                             &struct_parts.with_context(Ty(exp_ty.clone())),
                         )?;
                     }
                     if !found {
                         return Err(TyErr::NonexistentStructField(
-                            ast_to_name(&got_name),
+                            ast_to_name(got_name),
                             struct_parts.context_elt().clone(),
                         ));
                     }
@@ -229,7 +313,12 @@ pub fn make_core_syn_env_types() -> SynEnv {
         "forall_type",
         form_pat!([(lit "forall"), (star (named "param", atom)), (lit "."),
                        (named "body", (import [* [forall "param"]], (call "Type")))]),
-        LiteralLike, // synth is normal
+        cust_rc_box!(move |forall_parts| {
+            // synth is normal, once we know the body is a proper type:
+            let body_env = protect_all(&forall_parts.env, &forall_parts.get_rep_term(n("param")));
+            expect_type_kind(forall_parts.get_term_ref(n("body")), &body_env)?;
+            synth_body_literally(&forall_parts)
+        }),
         Both(
             LiteralLike,
             cust_rc_box!(move |forall_parts| {
@@ -258,6 +347,32 @@ pub fn make_core_syn_env_types() -> SynEnv {
         ),
     );
 
+    // `{ Show<T> ⋯ => τ }`: a `τ` that can only be used where there are `Show<T>` instances.
+    // It only makes sense directly under a `forall` (see `type_classes.rs`).
+    let constrained_type = type_defn_complex(
+        "constrained_type",
+        form_pat!((delim "{", "{",
+            [(star (named "constraint", (call "Type"))), (lit "=>"),
+             (named "body", (call "Type"))])),
+        cust_rc_box!(move |con_parts| {
+            // `Show<T>` isn't a type application: it names a class, not the dictionary type.
+            let mut constraints = vec![];
+            for constraint in con_parts.get_rep_term(n("constraint")) {
+                let (class, arg) = match crate::type_classes::constraint_parts(&constraint) {
+                    Some(parts) => parts,
+                    None => ty_err!(UnableToDestructure(Ty(constraint.clone()), n("type_apply"))
+                                    at constraint),
+                };
+                let arg = synth_type(&arg.0, con_parts.env.clone())?;
+                constraints.push(crate::type_classes::constraint(class, &arg));
+            }
+            Ok(ty!({"Type" "constrained_type" :
+                "constraint" => (,seq constraints),
+                "body" => (, con_parts.get_res(n("body"))?.concrete())}))
+        }),
+        Both(LiteralLike, LiteralLike),
+    );
+
     // This behaves slightly differently than the `mu` from Pierce's book,
     //  because we need to support mutual recursion.
     // In particular, it relies on having a binding for `param` in the environment!
@@ -267,7 +382,19 @@ pub fn make_core_syn_env_types() -> SynEnv {
         "mu_type",
         form_pat!([(lit "mu_type"), (star (named "param", (import [prot "param"], varref))),
              (lit "."), (named "body", (import [* [prot "param"]], (call "Type")))]),
-        LiteralLike,
+        cust_rc_box!(move |mu_parts| {
+            let params: Vec<Ast> = mu_parts
+                .get_rep_term(n("param"))
+                .iter()
+                .map(|p| match p.c() {
+                    ExtendEnv(ref p, _) => (**p).clone(),
+                    _ => p.clone(),
+                })
+                .collect();
+            let body_env = protect_all(&mu_parts.env, &params);
+            expect_type_kind(mu_parts.get_term_ref(n("body")), &body_env)?;
+            synth_body_literally(&mu_parts)
+        }),
         Both(
             LiteralLike,
             cust_rc_box!(move |mu_parts| {
@@ -290,13 +417,15 @@ pub fn make_core_syn_env_types() -> SynEnv {
                 // Apply the Amber rule; assume the `mu`ed names are subtypes to subtype the bodies
                 let mut amber_environment = mu_parts.env.clone();
                 for (&ee_r, ee_l) in r_params.iter().zip(l_params.iter()) {
-                    let (p_r, p_l) = if let (ExtendEnv(r, _), ExtendEnv(l, _)) = (ee_r, ee_l) {
+                    let (p_r, p_l) = if let (ExtendEnv(r, _), ExtendEnv(l, _)) =
+                        (ee_r.c(), ee_l.c())
+                    {
                         (&**r, &**l)
                     } else {
                         icp!("ill-formed mu_type")
                     };
                     if p_r == p_l // short-circuit if the names are the same...
-                        || mu_parts.env.find(&vr_to_name(&*p_r)) // ...or Amber assumed so already
+                        || mu_parts.env.find(&vr_to_name(p_r)) // ...or Amber assumed so already
                              == Some(&Ty(p_l.clone()))
                     {
                         continue;
@@ -329,29 +458,35 @@ pub fn make_core_syn_env_types() -> SynEnv {
         form_pat!((delim ":::[", "[", [(star (named "driver", varref)), (lit ">>"),
                                        (named "body", (call "Type"))])  ),
         cust_rc_box!(move |ddd_ty_parts| {
+            // A driver from an enclosing phase (e.g. for a repetition inside a quotation)
+            //  is as abstract as it gets.
             let drivers: Vec<(Name, Ty)> = ddd_ty_parts
                 .get_rep_term(n("driver"))
                 .iter()
-                .map(|a: &Ast| vr_to_name(a))
-                .zip(ddd_ty_parts.get_rep_res(n("driver"))?)
-                .collect();
+                .map(|a: &Ast| {
+                    let name = vr_to_name(a);
+                    if ddd_ty_parts.env.find(&name).is_none()
+                        && crate::ty_compare::abstract_in_outer_phase(name, &ddd_ty_parts)
+                    {
+                        return Ok((name, Ty(a.clone())));
+                    }
+                    Ok((name, walk(a, &ddd_ty_parts)?))
+                })
+                .collect::<Result<_, _>>()?;
 
             if drivers.is_empty() {
                 ty_err!(NeedsDriver(()) at ddd_ty_parts.this_ast);
             }
-            let still_abstract = match drivers[0].1 {
-                Ty(VariableReference(_)) => true,
-                _ => false,
-            };
+            let still_abstract = matches!((drivers[0].1).0.c(), VariableReference(_));
 
             for (_, ty) in &drivers[1..] {
-                match ty {
-                    Ty(VariableReference(_)) => {
+                match ty.0.c() {
+                    VariableReference(_) => {
                         if !still_abstract {
                             panic!("TODO: can we allow mixed abstractness here?")
                         }
                     }
-                    Ty(_) => {
+                    _ => {
                         if still_abstract {
                             panic!("TODO: can we allow mixed abstractness here?")
                         }
@@ -377,16 +512,15 @@ pub fn make_core_syn_env_types() -> SynEnv {
                         let components: Vec<Ast>
                             = tuple_type_parts.get_rep_leaf_or_panic(n("component"))
                                 .into_iter().cloned().collect();
-                        if len.is_none() {
-                            len = Some(components.len())
-                        } else {
-                            if len != Some(components.len()) {
+                        match len {
+                            None => len = Some(components.len()),
+                            Some(len) if len != components.len() => {
                                 ty_err!(
                                     LengthMismatch(
-                                        components.into_iter().map(Ty::new).collect(),
-                                        len.unwrap())
+                                        components.into_iter().map(Ty::new).collect(), len)
                                     at ddd_ty_parts.this_ast)
                             }
+                            Some(_) => {}
                         }
                         rebinds.push((name, components));
                     });
@@ -415,9 +549,8 @@ pub fn make_core_syn_env_types() -> SynEnv {
         Both(
             LiteralLike,
             cust_rc_box!(move |ddd_parts| {
-                use crate::ast_walk::Clo;
-                let tuple_parts = match ddd_parts.context_elt() {
-                    Ty(Node(ref f, ref tuple_parts, _))
+                let tuple_parts = match ddd_parts.context_elt().0.c() {
+                    Node(ref f, ref tuple_parts, _)
                         if f == &crate::core_forms::find("Type", "tuple") =>
                     {
                         tuple_parts
@@ -431,78 +564,23 @@ pub fn make_core_syn_env_types() -> SynEnv {
                     }
                 };
 
-                let tuple_size = tuple_parts.get_rep_leaf_or_panic(n("component")).len();
-                let mut driver_components: Vec<Vec<Ty>> = vec![];
-
-                crate::ty_compare::unification.with(|unif| {
-                    for driver in ddd_parts.get_rep_term(n("driver")) {
-                        let driver_resolved = crate::ty_compare::resolve(
-                            Clo { it: Ty(driver.clone()), env: ddd_parts.env.clone() },
-                            &unif.borrow(),
-                        );
-                        if let Clo { it: Ty(Node(ref f, ref driver_parts, _)), env: _ } =
-                            driver_resolved
-                        {
-                            if f == &crate::ty_compare::underdetermined_form.with(Clone::clone) {
-                                // Force the underdetermined driver to be a
-                                //  tuple whose elements are still underdetermined.
-                                let mut undet_components = vec![];
-                                for _ in 0..tuple_size {
-                                    undet_components.push(Subtype::underspecified(n("tup")));
-                                }
-                                let blank_tuple = ty!({"Type" "tuple" :
-                            "component" => (,seq
-                                undet_components
-                                    .iter().map(|t| t.concrete()).collect::<Vec<_>>())});
-
-                                unif.borrow_mut().insert(
-                                    ast_to_name(driver_parts.get_leaf_or_panic(&n("id"))),
-                                    Clo { it: blank_tuple, env: Assoc::new() }, // has no var refs
-                                );
-                                driver_components.push(undet_components);
-                            } else if f == &crate::core_forms::find("Type", "tuple") {
-                                driver_components.push(
-                                    driver_parts
-                                        .get_rep_leaf_or_panic(n("component"))
-                                        .into_iter()
-                                        .map(|c| Ty(c.clone()))
-                                        .collect(),
-                                );
-                            } else {
-                                return Err(TyErr::UnableToDestructure(
-                                    driver_resolved.it,
-                                    n("tuple"),
-                                ));
-                            }
-                        } else {
-                            return Err(TyErr::UnableToDestructure(driver_resolved.it, n("tuple")));
-                        }
-                    }
-
-                    let mut reses = Assoc::new();
-
-                    for i in 0..tuple_size {
-                        let mut env = ddd_parts.env.clone();
-                        for (driver, components) in
-                            ddd_parts.get_rep_term(n("driver")).iter().zip(driver_components.iter())
-                        {
-                            let driver_name = vr_to_name(&driver);
-                            env = env.set(driver_name, components[i].clone());
-                        }
-
-                        let this_component =
-                            Ty(tuple_parts.get_rep_leaf_or_panic(n("component"))[i].clone());
-
-                        reses = reses.set_assoc(
-                            &ddd_parts
-                                .with_environment(env)
-                                .with_context(this_component)
-                                .get_res(n("body"))?,
-                        );
-                    }
+                let components = tuple_parts.get_rep_leaf_or_panic(n("component"));
+                let envs = ddd_driver_envs(
+                    &ddd_parts.get_rep_term(n("driver")),
+                    components.len(),
+                    &ddd_parts.env,
+                )?;
 
-                    Ok(reses)
-                })
+                let mut reses = Assoc::new();
+                for (env, component) in envs.into_iter().zip(components) {
+                    reses = reses.set_assoc(
+                        &ddd_parts
+                            .with_environment(env)
+                            .with_context(Ty(component.clone()))
+                            .get_res(n("body"))?,
+                    );
+                }
+                Ok(reses)
             }),
         ),
     );
@@ -527,9 +605,21 @@ pub fn make_core_syn_env_types() -> SynEnv {
         // TODO: shouldn't it be "args"?
         cust_rc_box!(move |tapp_parts| {
             use crate::util::mbe::EnvMBE;
+            let rator = tapp_parts.get_term(n("type_rator"));
+            let arg_terms = tapp_parts.get_rep_term(n("arg"));
+            let expected_kind = constructor_kind(arg_terms.len());
+            match kind_of(&rator, &tapp_parts.env) {
+                Some(ref got_kind) if got_kind != &expected_kind => ty_err!(
+                    KindMismatch(Ty(rator.clone()), got_kind.clone(), expected_kind) at rator),
+                _ => {}
+            }
+            for arg in &arg_terms {
+                expect_type_kind(arg, &tapp_parts.env)?;
+            }
+
             let arg_res = tapp_parts.get_rep_res(n("arg"))?;
             let rator_res = tapp_parts.get_res(n("type_rator"))?;
-            match rator_res.0 {
+            match rator_res.0.c() {
                 VariableReference(rator_vr) => {
                     // e.g. `X<int, Y>` underneath `mu X. ...`
 
@@ -538,7 +628,7 @@ pub fn make_core_syn_env_types() -> SynEnv {
                     //  we wish to avoid aliasing problems at the type level.
                     // In System F, this is avoided by performing capture-avoiding substitution.
                     let mut new__tapp_parts = EnvMBE::new_from_leaves(
-                        assoc_n!("type_rator" => VariableReference(rator_vr)),
+                        assoc_n!("type_rator" => raw_ast!(VariableReference(*rator_vr))),
                     );
 
                     let mut args = vec![];
@@ -549,8 +639,12 @@ pub fn make_core_syn_env_types() -> SynEnv {
                     }
                     new__tapp_parts.add_anon_repeat(args, None);
 
-                    if let Node(ref f, _, ref exp) = tapp_parts.this_ast {
-                        Ok(Ty::new(Node(/* forall */ f.clone(), new__tapp_parts, exp.clone())))
+                    if let Node(ref f, _, ref exp) = tapp_parts.this_ast.c() {
+                        Ok(Ty::new(tapp_parts.this_ast.with_c(Node(
+                            /* forall */ f.clone(),
+                            new__tapp_parts,
+                            exp.clone(),
+                        ))))
                     } else {
                         icp!()
                     }
@@ -558,7 +652,7 @@ pub fn make_core_syn_env_types() -> SynEnv {
                 Node(ref got_f, ref lhs_parts, ref exports) if is_primitive(got_f) => {
                     // Like the above; don't descend into `Expr`
                     let mut new__tapp_parts = EnvMBE::new_from_leaves(assoc_n!("type_rator" =>
-                            Node(got_f.clone(), lhs_parts.clone(), exports.clone())));
+                            raw_ast!(Node(got_f.clone(), lhs_parts.clone(), exports.clone()))));
                     let mut args = vec![];
                     for individual__arg_res in arg_res {
                         args.push(EnvMBE::new_from_leaves(
@@ -567,8 +661,12 @@ pub fn make_core_syn_env_types() -> SynEnv {
                     }
                     new__tapp_parts.add_anon_repeat(args, None);
 
-                    if let Node(ref f, _, ref exp) = tapp_parts.this_ast {
-                        Ok(Ty::new(Node(/* forall */ f.clone(), new__tapp_parts, exp.clone())))
+                    if let Node(ref f, _, ref exp) = tapp_parts.this_ast.c() {
+                        Ok(Ty::new(tapp_parts.this_ast.with_c(Node(
+                            /* forall */ f.clone(),
+                            new__tapp_parts,
+                            exp.clone(),
+                        ))))
                     } else {
                         icp!()
                     }
@@ -577,7 +675,9 @@ pub fn make_core_syn_env_types() -> SynEnv {
                     // This might ought to be done by a specialized `beta`...
                     let params = forall_type__parts.get_rep_leaf_or_panic(n("param"));
                     if params.len() != arg_res.len() {
-                        panic!("[kind error] wrong number of arguments");
+                        // (Usually caught above, unless the `forall` was written in place)
+                        ty_err!(KindMismatch(Ty(rator.clone()), constructor_kind(params.len()),
+                                             expected_kind) at rator);
                     }
                     let mut new__ty_env = tapp_parts.env.clone();
                     for (name, actual_type) in params.iter().zip(arg_res) {
@@ -593,9 +693,7 @@ pub fn make_core_syn_env_types() -> SynEnv {
                     )
                 }
 
-                _ => {
-                    panic!("[kind error] {} is not a forall.", rator_res);
-                }
+                _ => ty_err!(KindMismatch(Ty(rator.clone()), type_kind(), expected_kind) at rator),
             }
         }),
         Both(LiteralLike, LiteralLike),
@@ -611,14 +709,190 @@ pub fn make_core_syn_env_types() -> SynEnv {
         type_defn("Int", form_pat!((name_lit "Int"))),
         type_defn("Nat", form_pat!((name_lit "Nat"))),
         type_defn("Float", form_pat!((name_lit "Float"))),
+        type_defn("I8", form_pat!((name_lit "I8"))),
+        type_defn("I16", form_pat!((name_lit "I16"))),
+        type_defn("I32", form_pat!((name_lit "I32"))),
+        type_defn("I64", form_pat!((name_lit "I64"))),
+        type_defn("U8", form_pat!((name_lit "U8"))),
+        type_defn("U16", form_pat!((name_lit "U16"))),
+        type_defn("U32", form_pat!((name_lit "U32"))),
+        type_defn("U64", form_pat!((name_lit "U64"))),
+        type_defn("String", form_pat!((name_lit "String"))),
         enum_type.clone(),
         struct_type.clone(),
         tuple_type.clone(),
         forall_type.clone(),
+        constrained_type.clone(),
         dotdotdot_type.clone(),
         mu_type.clone(),
         type_apply.clone()
-        ]), Rc::new(VarRef(Rc::new(Call(n("DefaultAtom"))))))))
+        ]), Rc::new(VarRef(Rc::new(Call(n("DefaultAtom"))))))),
+        "kind" => Rc::new(forms_to_form_pat![type_kind_form, higher_kind_form]))
+}
+
+/// The kind of proper types (the types that values have), `*`.
+pub fn type_kind() -> Ast { ast!({"kind" "type_kind" :}) }
+
+/// The kind of a type constructor with `arity` parameters (e.g. `k[* * -> *]`).
+pub fn constructor_kind(arity: usize) -> Ast {
+    ast!({"kind" "higher_kind" :
+        "param" => (,seq vec![type_kind(); arity]),
+        "res" => (, type_kind())})
+}
+
+/// The kind of the type expression `t`, if we can tell.
+/// A `forall` type that has been given a name (e.g. `List`) is a type constructor,
+///  but a `forall` written in place is just the type of a polymorphic value.
+pub fn kind_of(t: &Ast, env: &Assoc<Name, Ty>) -> Option<Ast> {
+    use crate::core_forms::find_core_form;
+    let forall_type = find_core_form("Type", "forall_type");
+    match *t.c() {
+        VariableReference(name) => match env.find(&name) {
+            // Protected (e.g. by `mu`), so we can't tell:
+            Some(Ty(ref defn)) if defn.c() == &VariableReference(name) => None,
+            Some(Ty(ref defn)) if matches!(defn.c(), Node(ref f, _, _) if is_primitive(f)) => None,
+            Some(Ty(ref defn)) => Some(match defn.destructure(forall_type) {
+                Some(forall_parts) => {
+                    constructor_kind(forall_parts.get_rep_leaf_or_panic(n("param")).len())
+                }
+                None => type_kind(),
+            }),
+            None => None, // (someone else will complain)
+        },
+        // `Expr`, `Pat`, etc. take one argument, but they're handled specially:
+        Node(ref f, _, _) if is_primitive(f) => None,
+        Node(ref f, ref parts, _) if f == &find_core_form("Type", "type_apply") => {
+            let rator_kind = kind_of(parts.get_leaf_or_panic(&n("type_rator")), env)?;
+            rator_kind
+                .destructure(find_core_form("kind", "higher_kind"))
+                .map(|k_parts| k_parts.get_leaf_or_panic(&n("res")).clone())
+        }
+        Node(_, _, _) => Some(type_kind()),
+        _ => None,
+    }
+}
+
+/// Complain unless `t` is a proper type (e.g. `List<Int>`, not `List`).
+/// Parameters are compared with the subtyping relation reversed, which swaps "got" and "expected".
+fn unreverse_mismatch(e: TyErr) -> TyErr {
+    match e {
+        TyErr::Mismatch(got, expd) => TyErr::Mismatch(expd, got),
+        e => e,
+    }
+}
+
+fn expect_type_kind(t: &Ast, env: &Assoc<Name, Ty>) -> Result<(), crate::ty::TypeError> {
+    let t = match *t.c() {
+        ExtendEnv(ref body, _) => &**body,
+        _ => t,
+    };
+    match kind_of(t, env) {
+        Some(ref got) if got != &type_kind() => {
+            ty_err!(KindMismatch(Ty(t.clone()), got.clone(), type_kind()) at t)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Like `LiteralLike`, for forms where only the `body` (under an `import`) needs synthesizing.
+fn synth_body_literally(parts: &LazyWalkReses<SynthTy>) -> Result<Ty, crate::ty::TypeError> {
+    let body = parts.get_res(n("body"))?.concrete();
+    match *parts.this_ast.c() {
+        Node(ref f, ref node_parts, ref exports) => {
+            let mut new_parts = node_parts.clone();
+            new_parts.add_leaf(n("body"), match *node_parts.get_leaf_or_panic(&n("body")).c() {
+                ExtendEnv(_, ref beta) => raw_ast!(ExtendEnv(Box::new(body), beta.clone())),
+                _ => body,
+            });
+            Ok(Ty(parts.this_ast.with_c(Node(f.clone(), new_parts, exports.clone()))))
+        }
+        _ => icp!(),
+    }
+}
+
+/// For each of the `size` repetitions of a `:::[ ]:::` type,
+///  `env` with the `drivers` bound to that repetition's component of their (tuple) types.
+/// Underdetermined drivers are forced to be tuples of underdetermined types.
+fn ddd_driver_envs(
+    drivers: &[Ast],
+    size: usize,
+    env: &Assoc<Name, Ty>,
+) -> Result<Vec<Assoc<Name, Ty>>, TyErr>
+{
+    use crate::ast_walk::Clo;
+    let mut driver_components: Vec<Vec<Ty>> = vec![];
+
+    crate::ty_compare::unification.with(|unif| {
+        for driver in drivers {
+            let driver_resolved = crate::ty_compare::resolve(
+                Clo { it: Ty(driver.clone()), env: env.clone() },
+                &unif.borrow(),
+            );
+            if let Node(ref f, ref driver_parts, _) = *driver_resolved.it.0.c() {
+                if f == &crate::ty_compare::underdetermined_form.with(Clone::clone) {
+                    // Force the underdetermined driver to be a
+                    //  tuple whose elements are still underdetermined.
+                    let mut undet_components = vec![];
+                    for _ in 0..size {
+                        undet_components.push(Subtype::underspecified(n("tup")));
+                    }
+                    let blank_tuple = ty!({"Type" "tuple" :
+                        "component" => (,seq
+                            undet_components.iter().map(|t| t.concrete()).collect::<Vec<_>>())});
+
+                    unif.borrow_mut().insert(
+                        ast_to_name(driver_parts.get_leaf_or_panic(&n("id"))),
+                        Clo { it: blank_tuple, env: Assoc::new() }, // has no var refs
+                    );
+                    driver_components.push(undet_components);
+                } else if f == &crate::core_forms::find("Type", "tuple") {
+                    let components: Vec<Ty> = driver_parts
+                        .get_rep_leaf_or_panic(n("component"))
+                        .into_iter()
+                        .map(|c| Ty(c.clone()))
+                        .collect();
+                    if components.len() != size {
+                        return Err(TyErr::LengthMismatch(components, size));
+                    }
+                    driver_components.push(components);
+                } else {
+                    return Err(TyErr::UnableToDestructure(driver_resolved.it, n("tuple")));
+                }
+            } else if let VariableReference(name) = *driver_resolved.it.0.c() {
+                if env.find(&name).is_none() {
+                    return Err(TyErr::UnboundName(name));
+                }
+                return Err(TyErr::UnableToDestructure(driver_resolved.it, n("tuple")));
+            } else {
+                return Err(TyErr::UnableToDestructure(driver_resolved.it, n("tuple")));
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok((0..size)
+        .map(|i| {
+            let mut env = env.clone();
+            for (driver, components) in drivers.iter().zip(driver_components.iter()) {
+                env = env.set(vr_to_name(driver), components[i].clone());
+            }
+            env
+        })
+        .collect())
+}
+
+/// `env`, but with `params` (which are atoms or variable references) bound to themselves.
+fn protect_all(env: &Assoc<Name, Ty>, params: &[Ast]) -> Assoc<Name, Ty> {
+    let mut res = env.clone();
+    for param in params {
+        match *param.c() {
+            Atom(p) | VariableReference(p) => {
+                res = res.set(p, Ty(raw_ast!(VariableReference(p))));
+            }
+            _ => {} // e.g. an unquotation
+        }
+    }
+    res
 }
 
 // TODO #4: this should be extensible for when the syntax environment is extended...
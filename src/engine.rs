@@ -0,0 +1,1555 @@
+//! The library's interface: an `Engine` is a session of Unseemly, with its own grammar
+//!  (syntax extensions included), types, and values, that Rust code can feed source text to.
+//! (This is what the REPL is built on.)
+
+use crate::{
+    ast::Ast,
+    core_forms::{find_core_form, outermost_form},
+    earley::{AmbiguityPolicy, ParseError},
+    grammar::FormPat,
+    name::{n, Name},
+    program::TopLevel,
+    runtime::{
+        eval::{eval, RuntimeErr, RuntimeError, Value, BIF},
+        reify::HostFunction,
+    },
+    ty::{synth_type, Ty, TypeError},
+    util::diagnostics::{reporting_warnings_to, Diagnose, Diagnostic, Warnings},
+};
+use std::{path::Path, rc::Rc};
+
+/// Anything that can go wrong with a program, from parsing to evaluation.
+#[derive(Clone, PartialEq)]
+pub enum Error {
+    Parse(ParseError),
+    Type(TypeError),
+    /// Expansion and evaluation both report `RuntimeError`s.
+    Runtime(RuntimeError),
+    /// A problem with a file as a whole: it can't be read, an import fails, etc.
+    Program(String),
+}
+
+impl Diagnose for Error {
+    fn diagnostic(&self) -> Diagnostic {
+        match *self {
+            Error::Parse(ref e) => e.diagnostic(),
+            Error::Type(ref e) => e.diagnostic(),
+            Error::Runtime(ref e) => e.diagnostic(),
+            Error::Program(ref msg) => Diagnostic::new(msg.clone(), None),
+        }
+    }
+
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        match *self {
+            Error::Parse(ref e) => e.diagnostics(),
+            _ => vec![self.diagnostic()],
+        }
+    }
+}
+
+/// The report, without color.
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let reports: Vec<String> = self.diagnostics().iter().map(|d| d.render(false)).collect();
+        write!(f, "{}", reports.join("\n"))
+    }
+}
+
+// Force pretty version
+impl std::fmt::Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Error { Error::Parse(e) }
+}
+
+impl From<Box<ParseError>> for Error {
+    fn from(e: Box<ParseError>) -> Error { Error::Parse(*e) }
+}
+
+impl From<TypeError> for Error {
+    fn from(e: TypeError) -> Error { Error::Type(e) }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(e: RuntimeError) -> Error { Error::Runtime(e) }
+}
+
+/// An Unseemly session. Programs are parsed with the session's grammar,
+///  and can refer to whatever has been defined in it.
+#[derive(Clone, Default)]
+pub struct Engine {
+    top: TopLevel,
+    /// Warnings from parsing and typechecking, waiting for `take_warnings`.
+    /// (Clones share it.)
+    warnings: Warnings,
+}
+
+impl Engine {
+    /// Just the core language.
+    pub fn new() -> Engine { Engine::default() }
+
+    /// Parse an expression.
+    pub fn parse(&self, program: &str) -> Result<Ast, Error> {
+        reporting_warnings_to(&self.warnings, || self.top.parse(&outermost_form(), program))
+    }
+
+    /// Parse and synthesize the type of an expression.
+    pub fn typecheck(&self, program: &str) -> Result<Ty, Error> {
+        let ast = self.parse(program)?;
+        reporting_warnings_to(&self.warnings, || self.top.typecheck(&ast))
+    }
+
+    /// Parse and typecheck an expression, and expand away all of its macros
+    ///  (making its use of class instances explicit, too).
+    pub fn expand(&self, program: &str) -> Result<Ast, Error> {
+        let ast = self.parse(program)?;
+        Ok(reporting_warnings_to(&self.warnings, || self.top.elaborate(&ast))?.1)
+    }
+
+    /// Parse, typecheck, expand, and evaluate an expression.
+    pub fn eval(&self, program: &str) -> Result<Value, Error> {
+        let ast = self.parse(program)?;
+        Ok(reporting_warnings_to(&self.warnings, || self.top.eval(&ast))?.1)
+    }
+
+    /// Like `eval`, but skipping the typechecker (so runtime errors can be... anything).
+    pub fn eval_without_typechecking(&self, program: &str) -> Result<Value, Error> {
+        let core_ast = crate::expand::expand(&self.parse(program)?)?;
+        Ok(eval(&core_ast, self.top.val_env.clone())?)
+    }
+
+    /// Parse a type, and resolve the names in it.
+    pub fn canonicalize_type(&self, t: &str) -> Result<Ty, Error> {
+        let ast = self.parse_type(t)?;
+        Ok(reporting_warnings_to(&self.warnings, || synth_type(&ast, self.top.ty_env.clone()))?)
+    }
+
+    fn parse_type(&self, t: &str) -> Result<Ast, Error> {
+        reporting_warnings_to(&self.warnings, || self.top.parse(&FormPat::Call(n("Type")), t))
+    }
+
+    /// Evaluate `program`, and bind `name` to the result for the rest of the session.
+    pub fn define(&mut self, name: &str, program: &str) -> Result<Value, Error> {
+        let ast = self.parse(program)?;
+        let warnings = self.warnings.clone();
+        reporting_warnings_to(&warnings, || self.top.define_value(n(name), &ast))
+    }
+
+    /// Bind `name` to the type `t` for the rest of the session.
+    pub fn define_type(&mut self, name: &str, t: &str) -> Result<Ty, Error> {
+        let ast = self.parse_type(t)?;
+        let warnings = self.warnings.clone();
+        reporting_warnings_to(&warnings, || self.top.define_type(n(name), &ast))
+    }
+
+    /// Make the Rust function `f` available as `name`.
+    /// Its type comes from the `Reifiable` implementations of its parameter and return types
+    ///  (`BigInt` is `Int`, `bool` is `Bool`, `String` is `String`, etc.).
+    /// Smaller Rust integers, like `i32`, are `Int`s too; passing one that doesn't fit
+    ///  is a `BifArgMismatch`.
+    pub fn define_fn<Args, F: HostFunction<Args>>(&mut self, name: &str, f: F) -> Ty {
+        let ty = Ty(F::ty());
+        self.top.bind(n(name), ty.clone(), f.reify_function());
+        ty
+    }
+
+    /// Make `f` available as `name`, with the Unseemly type `t`, which must be a function type.
+    /// `f` is only called with the right number of arguments, but it should still check them
+    ///  (they might not have been typechecked), returning `RuntimeErr::BifArgMismatch` if need be.
+    pub fn define_builtin<F>(&mut self, name: &str, t: &str, f: F) -> Result<Ty, Error>
+    where F: Fn(Vec<Value>) -> Result<Value, RuntimeError> + 'static {
+        let ty = self.canonicalize_type(t)?;
+        let arity = arity(&ty, &self.parse_type(t)?)?;
+        let bif = move |args: Vec<Value>| {
+            if args.len() == arity {
+                f(args)
+            } else {
+                Err(RuntimeError::new(RuntimeErr::ArgCountMismatch(arity, args)))
+            }
+        };
+        self.top.bind(n(name), ty.clone(), Value::BuiltInFunction(BIF(Rc::new(bif))));
+        Ok(ty)
+    }
+
+    /// Extend the session's grammar. `clauses` is what goes between `extend_syntax` and `in`.
+    pub fn extend_syntax(&mut self, clauses: &str) -> Result<(), Error> {
+        self.run(&format!("extend_syntax {}", clauses)).map(|_| ())
+    }
+
+    /// Run a sequence of top-level items (see `program.rs`), returning the value of the final
+    ///  expression, if there is one. Bindings made before an error are kept.
+    pub fn run(&mut self, program: &str) -> Result<Option<Value>, Error> {
+        let warnings = self.warnings.clone();
+        reporting_warnings_to(&warnings, || self.top.run(program))
+    }
+
+    /// Like `run`, but with the contents of a file (relative `import`s are relative to it).
+    /// (Warnings from the modules it imports show up here, too.)
+    pub fn run_file(&mut self, path: &Path) -> Result<Option<Value>, Error> {
+        let warnings = self.warnings.clone();
+        reporting_warnings_to(&warnings, || self.top.run_file(path))
+    }
+
+    /// Warnings (e.g. about unreachable `match` arms) from parsing and typechecking
+    ///  since the last call.
+    pub fn take_warnings(&self) -> Vec<Diagnostic> { self.warnings.replace(vec![]) }
+
+    /// Whether syntax that can be parsed in more than one way is an error (the default),
+    ///  or just a warning.
+    pub fn set_ambiguity_policy(&mut self, policy: AmbiguityPolicy) { self.top.ambiguity = policy; }
+
+    /// The names of all the values defined in the session.
+    pub fn value_names(&self) -> Vec<Name> { self.top.val_env.iter_keys().cloned().collect() }
+}
+
+/// How many arguments a function of type `ty` takes (looking past any `forall`).
+fn arity(ty: &Ty, loc: &Ast) -> Result<usize, TypeError> {
+    use crate::ast::AstContents::*;
+    let mut fn_ty = ty.0.clone();
+    loop {
+        fn_ty = match *fn_ty.c() {
+            ExtendEnv(ref body, _) => (**body).clone(),
+            Node(ref form, ref parts, _) if form.name == n("forall_type") => {
+                parts.get_leaf_or_panic(&n("body")).clone()
+            }
+            _ => break,
+        }
+    }
+    let parts = Ty(fn_ty).destructure(find_core_form("Type", "fn"), loc)?;
+    Ok(parts.get_rep_leaf_or_panic(n("param")).len())
+}
+
+#[test]
+fn engine_basics() {
+    let mut engine = Engine::new();
+    assert_eq!(engine.eval("(plus one two)"), Ok(val!(i 3)));
+    assert_eq!(engine.define("six", "(times two three)"), Ok(val!(i 6)));
+    assert_eq!(engine.typecheck("six"), Ok(uty!({Int :})));
+    assert_eq!(engine.define_type("IntToInt", "[Int -> Int]"), Ok(uty!({fn : [{Int :}] {Int :}})));
+    assert_m!(engine.define("inc", ".[ x : Int . (plus x one) ]."), Ok(_));
+    assert_eq!(engine.typecheck("inc"), engine.canonicalize_type("IntToInt"));
+    assert!(engine.value_names().contains(&n("inc")));
+
+    assert_m!(engine.parse("(plus one"), Err(Error::Parse(_)));
+    assert_m!(engine.typecheck("(plus one true)"), Err(Error::Type(_)));
+    assert_m!(engine.eval("(div one zero)"), Err(Error::Runtime(_)));
+    assert_m!(engine.eval_without_typechecking("(plus one true)"), Err(Error::Runtime(_)));
+    assert_m!(engine.run_file(Path::new("/nonexistent.≉")), Err(Error::Program(_)));
+    let err = engine.typecheck("(plus one true)").unwrap_err();
+    assert!(format!("{}", err).starts_with("error[Mismatch]"), "{}", err);
+
+    assert_eq!(
+        engine.extend_syntax(
+            "Expr ::=also forall . '{ [ lit ,{ DefaultToken }, = 'inc_twice'
+                e := ( ,{ Expr<Int> }, ) ] }' inc_twice -> .{ '[Expr | (inc (inc ,[e],)) ]' }. ;"
+        ),
+        Ok(())
+    );
+    let expanded = format!("{}", engine.expand("inc_twice six").unwrap());
+    assert!(!expanded.contains("inc_twice"), "{}", expanded);
+    assert_eq!(engine.eval("inc_twice six"), Ok(val!(i 8)));
+
+    // Sessions are independent:
+    assert_m!(Engine::new().eval("inc_twice six"), Err(Error::Parse(_)));
+}
+
+#[test]
+fn host_functions() {
+    use num::BigInt;
+    let mut engine = Engine::new();
+
+    let ty = engine.define_fn("gcd", |a: BigInt, b: BigInt| num::Integer::gcd(&a, &b));
+    assert_eq!(ty, uty!({fn : [{Int :}; {Int :}] {Int :}}));
+    assert_eq!(engine.eval("(gcd 12 (times 3 6))"), Ok(val!(i 6)));
+    assert_m!(engine.typecheck("(gcd 12 true)"), Err(Error::Type(_)));
+
+    engine.define_fn("both", |a: bool, b: bool| a && b);
+    assert_eq!(engine.eval("(both (zero? 0) (zero? 1))"), Ok(val!(b false)));
+    engine.define_fn("answer", || BigInt::from(42));
+    assert_eq!(engine.eval("(plus (answer) 0)"), Ok(val!(i 42)));
+
+    let ty = engine.define_fn("shout", |s: String| s.to_uppercase());
+    assert_eq!(ty, uty!({fn : [{String :}] {String :}}));
+    assert_eq!(engine.eval(r#"(shout "hi")"#), Ok(Value::Text("HI".to_owned())));
+    engine.define_fn("big", |a: i32, b: u8| a.checked_shl(u32::from(b) * 8).is_some());
+    assert_eq!(engine.eval("(big one one)"), Ok(val!(b true)));
+    let err = engine.eval("(big one 256)").unwrap_err().to_string();
+    assert!(err.contains("BifArgMismatch"), "{}", err);
+    let err = engine.eval("(big 0x8000_0000 one)").unwrap_err().to_string();
+    assert!(err.contains("BifArgMismatch"), "{}", err);
+    engine.define_fn("len", |s: String| s.len());
+    assert_eq!(engine.eval(r#"(plus (len "four") one)"#), Ok(val!(i 5)));
+
+    // Without the typechecker, bad arguments are caught at runtime:
+    let err = engine.eval_without_typechecking("(gcd 12 true)").unwrap_err().to_string();
+    assert!(err.contains("BifArgMismatch"), "{}", err);
+    for prog in &["(gcd 12)", "(gcd 1 2 3)"] {
+        let err = engine.eval_without_typechecking(prog).unwrap_err().to_string();
+        assert!(err.contains("ArgCountMismatch"), "{}", err);
+    }
+
+    let ty = engine.define_builtin("count_args", "forall T . [T T -> Int]", |args| {
+        Ok(Value::Int(BigInt::from(args.len())))
+    });
+    assert_m!(ty, Ok(_));
+    assert_eq!(engine.eval("(count_args true false)"), Ok(val!(i 2)));
+    let err = engine.eval_without_typechecking("(count_args 1)").unwrap_err().to_string();
+    assert!(err.contains("ArgCountMismatch"), "{}", err);
+
+    assert_m!(engine.define_builtin("not_fn", "Int", |_| Ok(val!(i 0))), Err(Error::Type(_)));
+    assert_m!(engine.define_builtin("bad", "[Int ->", |_| Ok(val!(i 0))), Err(Error::Parse(_)));
+    assert_m!(engine.eval("(not_fn)"), Err(Error::Type(_)));
+}
+
+#[test]
+fn simple_end_to_end_eval() {
+    let engine = Engine::new();
+    assert_eq!(engine.eval("(zero? zero)"), Ok(val!(b true)));
+
+    assert_eq!(engine.eval("(plus one one)"), Ok(val!(i 2)));
+
+    assert_eq!(engine.eval("(.[x : Int  y : Int . (plus x y)]. one one)"), Ok(val!(i 2)));
+
+    assert_eq!(
+        engine.eval(
+            "((fix .[ again : [ -> [ Int -> Int ]] .
+            .[ n : Int .
+                match (zero? n) {
+                    +[True]+ => one
+                    +[False]+ => (times n ((again) (minus n one))) } ]. ].) five)"
+        ),
+        Ok(val!(i 120))
+    );
+}
+
+#[test]
+fn inferred_parameter_types() {
+    let mut engine = Engine::new();
+    let typecheck = |e: &Engine, prog: &str| e.typecheck(prog).map(|t| t.to_string());
+
+    assert_eq!(typecheck(&engine, ".[x . (plus x one)]."), Ok("[ Int -> Int ]".to_string()));
+    assert_eq!(
+        typecheck(&engine, ".[f  x : Int . (plus (f x) one)]."),
+        Ok("[ [ Int -> Int ] Int -> Int ]".to_string())
+    );
+    assert_eq!(typecheck(&engine, "(.[x . x]. one)"), Ok("Int".to_string()));
+
+    // Unconstrained parameters become `forall` parameters at the top level:
+    assert_eq!(typecheck(&engine, ".[x . x]."), Ok("forall T . [ T -> T ]".to_string()));
+    engine.define("id", ".[x . x].").unwrap();
+    assert_eq!(engine.eval("(zero? (id (id zero)))"), Ok(val!(b true)));
+    assert_eq!(engine.eval("((id zero?) (id one))"), Ok(val!(b false)));
+
+    assert_eq!(
+        engine.eval(
+            "((fix .[ again .
+            .[ n .
+                match (zero? n) {
+                    +[True]+ => one
+                    +[False]+ => (times n ((again) (minus n one))) } ]. ].) five)"
+        ),
+        Ok(val!(i 120))
+    );
+
+    let err = engine.typecheck(".[x . (plus x (zero? x))].").unwrap_err().to_string();
+    assert!(err.contains("Mismatch"), "{}", err);
+
+    // Only top-level definitions are polymorphic; pattern-bound names aren't generalized:
+    assert_eq!(engine.eval("match .[x . x]. { id => (id one) }"), Ok(val!(i 1)));
+    let err = engine.typecheck("match .[x . x]. { id => ((id zero?) (id one)) }").unwrap_err();
+    assert!(err.to_string().contains("Mismatch"), "{}", err);
+}
+
+#[test]
+fn match_exhaustiveness() {
+    let mut engine = Engine::new();
+    let unmatched = |e: &Engine, prog: &str| match e.typecheck(prog) {
+        Err(Error::Type(e)) => match e.body {
+            crate::ty::TyErr::NonExhaustiveMatch(_, witness) => witness,
+            other => panic!("expected a non-exhaustive match, got {}", other),
+        },
+        other => panic!("expected a non-exhaustive match, got {:?}", other),
+    };
+
+    // `Bool` is an enum:
+    assert_m!(engine.typecheck("match (zero? one) { +[True]+ => one  +[False]+ => two }"), Ok(_));
+    assert_eq!(unmatched(&engine, "match (zero? one) { +[True]+ => one }"), "+[False]+");
+    assert_eq!(engine.take_warnings(), vec![]);
+
+    engine
+        .define_type("List", "forall T . mu_type List . enum { Nil () Cons (T List<T>) }")
+        .unwrap();
+    assert_m!(
+        engine.typecheck(
+            ".[l : List<Int> . match unfold l { +[Nil]+ => zero  +[Cons h t]+ => h }]."
+        ),
+        Ok(_)
+    );
+    assert_eq!(
+        unmatched(&engine, ".[l : List<Int> . match unfold l { +[Cons h t]+ => h }]."),
+        "+[Nil]+"
+    );
+
+    // Nested patterns:
+    let pair = "**[(zero? one) (zero? two)]**";
+    assert_eq!(
+        unmatched(
+            &engine,
+            &format!(
+                "match {} {{ **[+[True]+ x]** => one  **[+[False]+ +[True]+]** => two }}",
+                pair
+            )
+        ),
+        "**[+[False]+ +[False]+]**"
+    );
+    assert_eq!(
+        unmatched(&engine, "match *[a: (zero? one) b: one]* { *[b: x a: +[True]+]* => x }"),
+        "*[a: +[False]+ b: _]*"
+    );
+    assert_eq!(unmatched(&engine, "match one { 0 => zero  1 => one }"), "_");
+    assert_m!(engine.typecheck("match one { 0 => zero  n => n }"), Ok(_));
+
+    // Unreachable arms are only a warning:
+    assert_m!(
+        engine.typecheck(&format!(
+            "match {} {{ **[+[True]+ x]** => one  **[y +[True]+]** => two
+                **[+[True]+ +[False]+]** => three  z => four }}",
+            pair
+        )),
+        Ok(_)
+    );
+    let warnings = engine.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].code, Some("UnreachableArm".to_owned()));
+    assert!(warnings[0].render(false).starts_with("warning[UnreachableArm]"));
+    assert_eq!(engine.take_warnings(), vec![]);
+
+    // Running code typechecks it and its expansion, but only warns once, about what was written:
+    assert_m!(engine.eval("match true { +[True]+ => one  +[False]+ => two  x => three }"), Ok(_));
+    let warnings = engine.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].render(false),
+        "warning[UnreachableArm]: unreachable arm `x`
+ --> <input>:1:49
+  |
+1 | match true { +[True]+ => one  +[False]+ => two  x => three }
+  |                                                 ^ earlier arms match everything this does"
+    );
+}
+
+#[test]
+fn type_classes() {
+    let mut engine = Engine::new();
+    let prog = "class Eq T { eq : [T T -> Bool] }
+# The type of `a` and `b` is generalized, so this takes an `Eq` instance:
+neq := .[ a b . match (eq a b) { +[True]+ => false  +[False]+ => true } ].
+";
+    assert_eq!(engine.run(prog), Ok(None));
+    // (Names may get printed with vegetables attached.)
+    let neq_ty = engine.typecheck("neq").unwrap().to_string();
+    assert!(neq_ty.starts_with("forall T") && neq_ty.contains(" . { Eq<T"), "{}", neq_ty);
+    assert!(neq_ty.contains("> => [ T"), "{}", neq_ty);
+
+    engine.run(include_str!("examples/.unseemly_prelude")).unwrap();
+    let prog = "instance Eq<Int> := *[ eq: .[ a : Int  b : Int . (equal? a b) ]. ]*
+Color t= enum { Red () Green () }
+instance Eq<Color> := *[ eq: .[ a : Color  b : Color .
+    match **[a b]** { **[+[Red]+ +[Red]+]** => true  **[+[Green]+ +[Green]+]** => true
+                      **[x y]** => false } ]. ]*
+instance forall T . { Eq<T> => Eq<List<T>> } := *[ eq: .[ a : List<T>  b : List<T> .
+    match **[unfold a unfold b]** {
+        **[+[Nil]+ +[Nil]+]** => true
+        **[+[Cons ah at]+ +[Cons bh bt]+]** =>
+            match (eq ah bh) { +[True]+ => (eq at bt)  +[False]+ => false }
+        **[x y]** => false } ]. ]*
+";
+    assert_eq!(engine.run(prog), Ok(None));
+    assert_eq!(engine.eval("(eq one one)"), Ok(val!(b true)));
+    assert_eq!(engine.eval("(neq one two)"), Ok(val!(b true)));
+    assert_eq!(engine.eval("(neq +[Red]+ : Color  +[Red]+ : Color)"), Ok(val!(b false)));
+    assert_eq!(engine.eval("(eq list_123 list_123)"), Ok(val!(b true)));
+    assert_eq!(engine.eval("(neq list_123 list_23)"), Ok(val!(b true)));
+
+    // The dictionary-passing is explicit after expansion:
+    let expanded = format!("{}", engine.expand("(eq one two)").unwrap());
+    assert!(expanded.contains("Eq_instance"), "{}", expanded);
+
+    // Macros can use methods; each expansion gets the instance for its own type:
+    engine
+        .extend_syntax(
+            "Expr ::=also forall T . '{ [ lit ,{ DefaultToken }, = 'same'
+                a := ( ,{ Expr<T> }, )  b := ( ,{ Expr<T> }, ) ] }'
+                same -> .{ '[Expr | (eq ,[a], ,[b],) ]' }. ;",
+        )
+        .unwrap();
+    assert_eq!(engine.eval("same one one"), Ok(val!(b true)));
+    engine.define("red", "+[Red]+ : Color").unwrap();
+    assert_eq!(
+        engine.eval(
+            "match same one two { +[True]+ => false  +[False]+ => same red red }"
+        ),
+        Ok(val!(b true))
+    );
+
+    let instance_err = |e: &Engine, prog: &str| match e.typecheck(prog) {
+        Err(Error::Type(e)) => e.body.message(),
+        other => panic!("expected an instance error, got {:?}", other),
+    };
+    assert_eq!(
+        instance_err(&engine, "(eq true false)"),
+        "there's no `Eq` instance for `enum { True () False () }`"
+    );
+    assert_eq!(
+        instance_err(&engine, "(eq (eq one one) true)"),
+        "there's no `Eq` instance for `enum { True () False () }`"
+    );
+    // Even after expansion, the error is about the method reference:
+    let rendered = |e: &Engine, prog: &str| match e.eval(prog) {
+        Err(e) => crate::util::diagnostics::Diagnose::diagnostic(&e).render(false),
+        other => panic!("expected an instance error, got {:?}", other),
+    };
+    assert_eq!(
+        rendered(&engine, "(eq true false)"),
+        "error[NoInstance]: there's no `Eq` instance for `enum { True () False () }`
+ --> <input>:1:2
+  |
+1 | (eq true false)
+  |  ^^ this needs a `Eq` instance"
+    );
+    // (A macro's references are in its definition.)
+    assert!(
+        rendered(&engine, "same true false").contains(
+            "3 |                 same -> .{ '[Expr | (eq ,[a], ,[b],) ]' }. ;
+  |                                      ^^ this needs a `Eq` instance"
+        )
+    );
+    // Nothing could ever determine what `x` is:
+    let err = instance_err(&engine, "(.[ f . one ]. .[ x . (eq x x) ].)");
+    assert!(err.starts_with("can't tell which `Eq` instance"), "{}", err);
+
+    let err = engine.run("instance Eq<Int> := *[ eq: .[ a : Int  b : Int . false ]. ]*");
+    assert_m!(err, Err(Error::Program(_)));
+    let err = engine.run("instance Eq<Bool> := *[ eq: .[ a : Int  b : Int . false ]. ]*");
+    assert_m!(err, Err(Error::Type(_)));
+    // The failed instance doesn't stick around:
+    assert_m!(engine.typecheck("(eq true false)"), Err(Error::Type(_)));
+}
+
+#[test]
+fn kind_errors() {
+    let mut engine = Engine::new();
+    engine.define_type("Pair", "forall A B . **[A B]**").unwrap();
+    let kind_err = |e: &Engine, prog: &str| match e.typecheck(prog) {
+        Err(Error::Type(e)) => match e.body {
+            crate::ty::TyErr::KindMismatch(ty, got, expd) => {
+                (ty.to_string(), got.to_string(), expd.to_string())
+            }
+            other => panic!("expected a kind error, got {}", other),
+        },
+        other => panic!("expected a kind error, got {:?}", other),
+    };
+
+    assert_m!(engine.typecheck(".[p : Pair<Int Int> . p]."), Ok(_));
+    assert_eq!(
+        kind_err(&engine, ".[p : Pair<Int> . p]."),
+        ("Pair".to_string(), "k[ * * -> * ]k".to_string(), "k[ * -> * ]k".to_string())
+    );
+    assert_eq!(
+        kind_err(&engine, ".[p : Int<Int> . p]."),
+        ("Int".to_string(), "*".to_string(), "k[ * -> * ]k".to_string())
+    );
+    assert_eq!(
+        kind_err(&engine, ".[p : Pair<Pair Int> . p]."),
+        ("Pair".to_string(), "k[ * * -> * ]k".to_string(), "*".to_string())
+    );
+    assert_eq!(
+        kind_err(&engine, ".[p : forall T . Pair . p]."),
+        ("Pair".to_string(), "k[ * * -> * ]k".to_string(), "*".to_string())
+    );
+    assert_eq!(
+        kind_err(&engine, ".[p : mu_type L . Pair . p]."),
+        ("Pair".to_string(), "k[ * * -> * ]k".to_string(), "*".to_string())
+    );
+
+    // A `forall` written in place is an ordinary (polymorphic) type:
+    assert_m!(engine.typecheck(".[p : Pair<forall T . [T -> T]  Int> . p]."), Ok(_));
+
+    let err = engine.define_type("Bad", "forall T . [Pair<T> -> T]").unwrap_err().to_string();
+    assert!(err.contains("KindMismatch"), "{}", err);
+}
+
+#[test]
+fn end_to_end_error_locations() {
+    let engine = Engine::new();
+    let program = "(plus one\n   (plus one (zero? one)))";
+    let err = engine.typecheck(program).unwrap_err().to_string();
+    assert!(err.contains("error[Mismatch]: expected `Int`, got `Bool`"), "{}", err);
+    assert!(err.contains("<input>:2:14"), "{}", err);
+    assert!(err.contains("   (plus one (zero? one)))"), "{}", err);
+    assert!(err.contains("             ^^^^^^^^^^^ this has type `Bool`"), "{}", err);
+
+    let err = engine.typecheck("(plus one true)").unwrap_err().to_string();
+    assert!(err.contains("error[Mismatch]: expected `Int`, got `Bool`"), "{}", err);
+    assert!(err.contains("<input>:1:11"), "{}", err);
+    assert!(err.contains("          ^^^^ this has type `Bool`"), "{}", err);
+
+    let program = "(plus one
+   (plus one ]one))";
+    let err = engine.typecheck(program).unwrap_err().to_string();
+    assert!(err.contains("<input>:2:14"), "{}", err);
+}
+
+#[test]
+fn end_to_end_int_literals() {
+    let engine = Engine::new();
+    assert_eq!(engine.eval("(plus 1_000 (minus 0xff 0b11))"), Ok(val!(i 1252)));
+    assert_eq!(
+        engine.eval(
+            "match (times 2 0x10) { 0 => false  0b10_0000 => true  x => false }"
+        ),
+        Ok(val!(b true))
+    );
+    assert_eq!(
+        engine.eval("match 7 { 0 => zero  x => x }"),
+        Ok(val!(i 7))
+    );
+    // Usable (and preserved) inside quasiquotation:
+    assert_eq!(
+        engine.eval(
+            "match '[Expr | (plus 0x1F 2)]' { '[Expr | (plus 0x1F ,[Expr<Int> | e],)]' => e }"
+        ),
+        engine.eval("'[Expr | 2]'")
+    );
+    let unparsed = format!("{}", engine.parse("(plus 0x1F 0b101)").unwrap());
+    assert!(unparsed.contains("0x1F 0b101"), "{}", unparsed);
+
+    // `0x1F` is one token, not `0` applied to `x1F`:
+    assert!(engine.eval("(plus 0 x1F)").is_err());
+    assert!(engine.typecheck(r#"match "one" { 1 => 1 }"#).is_err());
+    // A prefix with no digits after it is a parse error:
+    for bad in &["0x_", "0b__", "(plus 0x one)", "match one { 0b_ => one  x => x }"] {
+        assert!(engine.parse(bad).is_err(), "{}", bad);
+    }
+    assert_eq!(engine.eval("(plus 0x_f 0b_1)"), Ok(val!(i 16)));
+}
+
+#[test]
+fn end_to_end_int_library() {
+    let engine = Engine::new();
+    let ev = |prog: &str| engine.eval(prog);
+    assert_eq!(ev("(div 7 2)"), Ok(val!(i 3)));
+    assert_eq!(ev("(div (negate 7) 2)"), Ok(val!(i -4)));
+    assert_eq!(ev("(mod (negate 7) 2)"), Ok(val!(i 1)));
+    assert_eq!(ev("(mod 7 (negate 2))"), Ok(val!(i -1)));
+    assert_eq!(ev("(abs (negate 7))"), Ok(val!(i 7)));
+    let two_to_the_hundred = "1267650600228229401496703205376".parse().unwrap();
+    assert_eq!(ev("(pow 2 100)"), Ok(Value::Int(two_to_the_hundred)));
+    assert_eq!(ev("(less? 1 2)"), Ok(val!(b true)));
+    assert_eq!(ev("(greater? 1 2)"), Ok(val!(b false)));
+    assert_eq!(ev("(less_or_equal? 2 2)"), Ok(val!(b true)));
+    assert_eq!(ev("(greater_or_equal? 1 2)"), Ok(val!(b false)));
+    assert_eq!(ev("(bit_and 0b1100 0b1010)"), Ok(val!(i 0b1000)));
+    assert_eq!(ev("(bit_or 0b1100 0b1010)"), Ok(val!(i 0b1110)));
+    assert_eq!(ev("(bit_xor 0b1100 0b1010)"), Ok(val!(i 0b0110)));
+    assert_eq!(ev("(bit_and (negate 1) 0xff)"), Ok(val!(i 0xff)));
+    assert_eq!(ev("(shift_left 1 70)"), ev("(pow 2 70)"));
+    assert_eq!(ev("(shift_right 0x100 4)"), Ok(val!(i 0x10)));
+
+    for prog in &["(div 1 0)", "(mod 1 (minus 1 1))"] {
+        let err = ev(prog).unwrap_err().to_string();
+        assert!(err.contains("DivisionByZero"), "{}", err);
+    }
+    let err = ev("(pow 2 (negate 1))").unwrap_err().to_string();
+    assert!(err.contains("ArgOutOfRange"), "{}", err);
+    // Results that would be enormous are refused:
+    let huge = ["(pow 3 100_000_000)", "(shift_left 1 100_000_000_000)", "(pow 2 0x1_0000_0000)"];
+    for prog in &huge {
+        let err = ev(prog).unwrap_err().to_string();
+        assert!(err.contains("ArgOutOfRange"), "{}", err);
+    }
+    assert_eq!(ev("(pow (negate 1) 100_000_000_001)"), Ok(val!(i -1)));
+    assert_eq!(ev("(shift_right 0x100 100_000_000_000)"), Ok(val!(i 0)));
+}
+
+#[test]
+fn end_to_end_strings() {
+    let engine = Engine::new();
+    assert_eq!(
+        engine.eval(r#"(string_concat "a\tb" (int_to_string ten))"#),
+        Ok(Value::Text("a\tb10".to_owned()))
+    );
+    assert_eq!(engine.eval(r#"(string_length "\u{1F600}\"")"#), Ok(val!(i 2)));
+    assert_eq!(engine.eval(r#""\u{41}\u{0000A9}""#), Ok(Value::Text("A©".to_owned())));
+    // A malformed `\u` escape is a parse error:
+    for bad in &[r#""\u41""#, r#""\u{}""#, r#""\u{4g}""#, r#""\u{D800}""#, r#""\u{110000}""#] {
+        assert!(engine.parse(bad).is_err(), "{}", bad);
+    }
+    assert_eq!(engine.eval(r#"(string_less? "abc" "abd")"#), Ok(val!(b true)));
+    assert_eq!(
+        engine.eval(r#"(string_to_int (substring "x42y" one three))"#),
+        Ok(val!(i 42))
+    );
+    assert_eq!(engine.eval("(char_code_at (from_char_code ten) zero)"), Ok(val!(i 10)));
+    assert_eq!(format!("{}", Value::Text("a\"b".to_owned())), r#""a\"b""#);
+    let unparsed = format!("{}", engine.parse(r#"(string_concat "a\"" "\n")"#).unwrap());
+    assert!(unparsed.contains(r#""a\"" "\n""#), "{}", unparsed);
+
+    assert!(engine.typecheck(r#"(plus one "one")"#).is_err());
+    let err = engine.eval(r#"(substring "abc" two one)"#).unwrap_err().to_string();
+    assert!(err.contains("IndexOutOfBounds"), "{}", err);
+}
+
+#[test]
+fn end_to_end_fixed_width_and_float() {
+    use crate::runtime::numbers::IntWidth::*;
+    let engine = Engine::new();
+    let ev = |prog: &str| engine.eval(prog);
+    let signed = |w, i: i64| Ok(Value::Signed(w, i));
+    let unsigned = |w, i: u64| Ok(Value::Unsigned(w, i));
+    assert_eq!(ev("(i32_plus 40i32 2i32)"), signed(I32, 42));
+    assert_eq!(ev("(u8_minus 0xFFu8 0b1u8)"), unsigned(U8, 254));
+    assert_eq!(ev("(i8_div (int_to_i8 (negate 7)) 2i8)"), signed(I8, -4));
+    assert_eq!(ev("(i8_mod (int_to_i8 (negate 7)) 2i8)"), signed(I8, 1));
+    assert_eq!(ev("(i64_to_int (i64_times 3i64 4i64))"), Ok(val!(i 12)));
+    assert_eq!(ev("(u16_less? 1u16 2u16)"), Ok(val!(b true)));
+    assert_eq!(ev("(wrap_to_u8 256)"), unsigned(U8, 0));
+    assert_eq!(ev("(wrap_to_i8 128)"), signed(I8, -128));
+    assert_eq!(ev("(u64_plus 0xFFFF_FFFF_FFFF_FFFEu64 1u64)"), unsigned(U64, u64::MAX));
+    let i64_min = "(int_to_i64 (negate 0x8000_0000_0000_0000))";
+    assert_eq!(ev(&format!("(i64_mod {} (int_to_i64 (negate 1)))", i64_min)), signed(I64, 0));
+    assert_eq!(format!("{}", ev("(u32_plus 1u32 1u32)").unwrap()), "2u32");
+
+    // Fixed-width arithmetic is checked:
+    let err = ev("(u8_plus 255u8 1u8)").unwrap_err().to_string();
+    assert!(err.contains("Overflow"), "{}", err);
+    assert!(err.contains("`256` doesn't fit in `U8`"), "{}", err);
+    let err = ev("(int_to_i32 0x8000_0000)").unwrap_err().to_string();
+    assert!(err.contains("Overflow"), "{}", err);
+    let err = ev("(u64_minus 0u64 1u64)").unwrap_err().to_string();
+    assert!(err.contains("`-1` doesn't fit in `U64`"), "{}", err);
+    let err = ev("(i64_times 0x4000_0000_0000_0000i64 2i64)").unwrap_err().to_string();
+    assert!(err.contains("`9223372036854775808` doesn't fit in `I64`"), "{}", err);
+    let err = ev(&format!("(i64_div {} (int_to_i64 (negate 1)))", i64_min)).unwrap_err();
+    assert!(err.to_string().contains("Overflow"), "{}", err);
+    let err = ev("(u64_mod 1u64 0u64)").unwrap_err().to_string();
+    assert!(err.contains("DivisionByZero"), "{}", err);
+    // ...and so are literals:
+    let err = engine.typecheck("256u8").unwrap_err().to_string();
+    assert!(err.contains("LiteralOutOfRange"), "{}", err);
+    let err = engine.typecheck("1.5e999").unwrap_err().to_string();
+    assert!(err.contains("LiteralOutOfRange"), "{}", err);
+    assert!(err.contains("`1.5e999` doesn't fit in `Float`"), "{}", err);
+    assert_eq!(ev("(float_less? 1e308 1.5e-999)"), Ok(val!(b false)));
+    // A fixed-width literal needs a digit, too:
+    assert_m!(engine.typecheck("0x_u8"), Err(Error::Parse(_)));
+    assert_m!(engine.typecheck("0bu16"), Err(Error::Parse(_)));
+    assert_eq!(ev("0x_fu8"), unsigned(U8, 15));
+    // There are no implicit conversions:
+    assert_m!(engine.typecheck("(i32_plus 1i32 1)"), Err(Error::Type(_)));
+    assert_m!(engine.typecheck("(i32_plus 1i32 1i64)"), Err(Error::Type(_)));
+
+    assert_eq!(ev("(float_plus 1.5 2.25)"), Ok(Value::Float(3.75)));
+    assert_eq!(ev("(float_times 1e3 2.5E-1)"), Ok(Value::Float(250.0)));
+    assert_eq!(ev("(float_less? 0.1 1_000.0)"), Ok(val!(b true)));
+    assert_eq!(ev("(float_to_int (float_div 7.0 (int_to_float 2)))"), Ok(val!(i 3)));
+    assert_eq!(ev("(float_to_int (float_minus 0.0 2.5))"), Ok(val!(i -2)));
+    assert_eq!(ev("(float_to_string (float_div 1.0 0.0))"), Ok(Value::Text("inf".to_owned())));
+    let err = ev("(float_to_int (float_div 0.0 0.0))").unwrap_err().to_string();
+    assert!(err.contains("`NaN` doesn't fit in `Int`"), "{}", err);
+    let err = ev("(float_to_int (float_div 1.0 0.0))").unwrap_err().to_string();
+    assert!(err.contains("Overflow"), "{}", err);
+    assert_m!(engine.typecheck("(float_plus 1.0 1)"), Err(Error::Type(_)));
+
+    assert_eq!(ev("(compare 2.5 1.0)"), Ok(val!(i 1)));
+    assert_eq!(ev("(eq? 3u8 3u8)"), Ok(val!(b true)));
+}
+
+#[test]
+fn end_to_end_structural_comparison() {
+    let mut engine = Engine::new();
+    engine.run(include_str!("examples/.unseemly_prelude")).unwrap();
+    assert_eq!(engine.eval("(eq? one one)"), Ok(val!(b true)));
+    assert_eq!(engine.eval("(compare one two)"), Ok(val!(i -1)));
+    assert_eq!(engine.eval(r#"(compare "b" "a")"#), Ok(val!(i 1)));
+    assert_eq!(engine.eval("(eq? (zero? one) false)"), Ok(val!(b true)));
+    assert_eq!(engine.eval("(eq? *[a: one b: true]* *[b: true a: one]*)"), Ok(val!(b true)));
+    assert_eq!(engine.eval("(eq? list_123 list_123)"), Ok(val!(b true)));
+    assert_eq!(engine.eval("(compare list_123 list_23)"), Ok(val!(i -1)));
+    assert_eq!(engine.eval("(compare list_3 list_23)"), Ok(val!(i 1)));
+    assert_m!(engine.typecheck("(eq? one true)"), Err(Error::Type(_)));
+
+    // Syntax is compared up to alpha-equivalence:
+    assert_eq!(
+        engine.eval("(eq? '[Expr | .[ x : Int . x ]. ]' '[Expr | .[ y : Int . y ]. ]')"),
+        Ok(val!(b true))
+    );
+    assert_eq!(
+        engine.eval("(eq? '[Expr | .[ x : Int . one ]. ]' '[Expr | .[ y : Int . two ]. ]')"),
+        Ok(val!(b false))
+    );
+    assert_eq!(
+        engine.eval("(compare '[Expr | (plus one two)]' '[Expr | (plus one two)]')"),
+        Ok(val!(i 0))
+    );
+    // ...consistently: alpha-equivalent syntax is on the same side of everything else.
+    let cmp_y = |e: &Engine, name: &str| {
+        e.eval(&format!(
+            "(compare '[Expr | .[ {0} : Int . {0} ]. ]' '[Expr | .[ y : Int . one ]. ]')",
+            name
+        ))
+    };
+    assert_eq!(cmp_y(&engine, "x"), cmp_y(&engine, "z"));
+
+    let err = engine.eval("(eq? *[f: .[x : Int . x]. ]* *[f: .[x : Int . x]. ]*)").unwrap_err();
+    let err = err.to_string();
+    assert!(err.contains("Incomparable"), "{}", err);
+    assert_m!(engine.eval("(compare plus plus)"), Err(Error::Runtime(_)));
+}
+
+#[test]
+fn end_to_end_runtime_errors() {
+    let engine = Engine::new();
+    let program = "(.[x : Int .\n  match +[A]+ : enum { A () B () } { +[B]+ => x } ]. one)";
+    let err = engine.typecheck(program).unwrap_err().to_string();
+    assert!(err.contains("`+[A]+` is not matched"), "{}", err);
+    let err = engine.eval_without_typechecking(program).unwrap_err().to_string();
+    assert!(err.contains("MatchFailure"), "{}", err);
+    assert!(err.contains("<input>:2:3"), "{}", err);
+    assert!(err.contains("called from"), "{}", err);
+    assert!(err.contains("<input>:1:1"), "{}", err);
+
+    let err = engine.eval_without_typechecking("(plus one nonexistent)").unwrap_err().to_string();
+    assert!(err.contains("`nonexistent` is not defined"), "{}", err);
+
+    let err = engine.eval_without_typechecking("(plus one zero?)").unwrap_err().to_string();
+    assert!(err.contains("BifArgMismatch"), "{}", err);
+
+    // Without the typechecker, all sorts of things can go wrong, but none of them crash:
+    let program = "(plus one\n  (plus one))";
+    let err = engine.eval_without_typechecking(program).unwrap_err().to_string();
+    assert!(err.contains("ArgCountMismatch"), "{}", err);
+    assert!(err.contains("expected 2 arguments, but got 1"), "{}", err);
+    assert!(err.contains("<input>:2:3"), "{}", err);
+    for prog in &["(plus one two three)", "(.[x : Int . x]. one two)", "(.[x : Int . x].)"] {
+        let err = engine.eval_without_typechecking(prog).unwrap_err().to_string();
+        assert!(err.contains("ArgCountMismatch"), "{}", err);
+    }
+    let err = engine.eval_without_typechecking("(one two)").unwrap_err().to_string();
+    assert!(err.contains("`1` is not a function"), "{}", err);
+    let err = engine
+        .eval_without_typechecking("match one { +[A]+ => one  *[a : x]* => x  **[]** => two }")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("MatchFailure"), "{}", err);
+    let err = engine.eval_without_typechecking("(fix plus)").unwrap_err().to_string();
+    assert!(err.contains("BifArgMismatch"), "{}", err);
+}
+
+#[test]
+fn end_to_end_int_list_tools() {
+    let mut engine = Engine::new();
+    assert_m!(
+        engine.define_type("IntList", "mu_type IntList . enum { Nil () Cons (Int IntList) }"),
+        Ok(_)
+    );
+
+    assert_m!(engine.define_type("IntListUF", "enum { Nil () Cons (Int IntList) }"), Ok(_));
+
+    assert_m!(
+        engine.define("mt_ilist", "fold +[Nil]+ : enum { Nil () Cons (Int IntList) } : IntList"),
+        Ok(_)
+    );
+
+    assert_m!(
+        engine.define("ilist_3", "fold +[Cons three mt_ilist]+ : IntListUF : IntList"),
+        Ok(_)
+    );
+
+    assert_m!(
+        engine.define("ilist_23", "fold +[Cons two ilist_3]+ : IntListUF : IntList"),
+        Ok(_)
+    );
+
+    assert_m!(
+        engine.define("ilist_123", "fold +[Cons one ilist_23]+ : IntListUF : IntList"),
+        Ok(_)
+    );
+
+    assert_m!(
+        engine.define(
+            "sum_int_list",
+            "(fix .[again : [-> [IntList -> Int]] .
+             .[ lst : IntList .
+                 match unfold lst {
+                     +[Nil]+ => zero +[Cons hd tl]+ => (plus hd ((again) tl))} ]. ]. )"
+        ),
+        Ok(_)
+    );
+
+    assert_eq!(engine.eval("(sum_int_list ilist_123)"), Ok(val!(i 6)));
+
+    assert_m!(
+        engine.define(
+            "int_list_len",
+            "(fix .[again : [-> [IntList -> Int]] .
+             .[ lst : IntList .
+                 match unfold lst {
+                     +[Nil]+ => zero +[Cons hd tl]+ => (plus one ((again) tl))} ]. ].)"
+        ),
+        Ok(_)
+    );
+
+    assert_eq!(engine.eval("(int_list_len ilist_123)"), Ok(val!(i 3)));
+}
+
+#[test]
+fn end_to_end_list_tools() {
+    let mut engine = Engine::new();
+    assert_m!(
+        engine.define_type("List", "forall T . mu_type List . enum { Nil () Cons (T List<T>) }"),
+        Ok(_)
+    );
+
+    assert_m!(engine.define_type("ListUF", "forall T . enum { Nil () Cons (T List<T>) }"), Ok(_));
+
+    assert_m!(
+        engine.define(
+            "mt_list",
+            "fold +[Nil]+ : enum { Nil () Cons (Int List < Int > ) } : List < Int > "
+        ),
+        Ok(_)
+    );
+
+    assert_m!(
+        engine.define("list_3", "fold +[Cons three mt_list]+ : ListUF<Int> : List<Int>"),
+        Ok(_)
+    );
+
+    assert_m!(
+        engine.define("list_23", "fold +[Cons two list_3]+ : ListUF<Int> : List<Int>"),
+        Ok(_)
+    );
+
+    assert_m!(
+        engine.define("list_123", "fold +[Cons one list_23]+ : ListUF<Int> : List<Int>"),
+        Ok(_)
+    );
+
+    assert_m!(
+        engine.define(
+            "list_len",
+            "forall S . (fix .[again : [-> [List<S> -> Int]] .
+            .[ lst : List<S> .
+                match unfold lst {
+                    +[Nil]+ => zero
+                    +[Cons hd tl]+ => (plus one ((again) tl))} ]. ].)"
+        ),
+        Ok(_)
+    );
+
+    assert_eq!(engine.eval("(list_len list_123)"), Ok(val!(i 3)));
+
+    assert_m!(
+        engine.define(
+            "map",
+            "forall T S . (fix  .[again : [-> [List<T>  [T -> S] -> List<S> ]] .
+            .[ lst : List<T>   f : [T -> S] .
+                match unfold lst {
+                    +[Nil]+ => fold +[Nil]+ : ListUF<S> : List<S>
+                    +[Cons hd tl]+ =>
+                      fold +[Cons (f hd) ((again) tl f)]+ : ListUF<S> : List<S> } ]. ].)"
+        ),
+        Ok(_)
+    );
+    // TODO: what should even happen if you have `forall` not on the "outside"?
+    // It should probably be an error to have a value typed with an underdetermined type.
+
+    // TODO: it's way too much of a pain to define each different expected result list.
+    assert_m!(engine.eval("(map list_123 .[x : Int . (plus x one)]. )"), Ok(_));
+
+    assert_m!(engine.eval("(map list_123 .[x : Int . (equal? x two)]. )"), Ok(_));
+}
+
+#[test]
+fn end_to_end_quotation_basic() {
+    let engine = Engine::new();
+    assert_m!(engine.eval("'[Expr | .[ x : Int . x ]. ]'"), Ok(_));
+
+    assert_m!(engine.eval("'[Expr | (plus five five) ]'"), Ok(_));
+
+    assert_m!(engine.eval("'[Expr | '[Expr | (plus five five) ]' ]'"), Ok(_));
+
+    //≫ .[s : Expr<Int> . '[Expr | ( ,[Expr | s], '[Expr | ,[Expr | s], ]')]' ].
+}
+#[test]
+fn subtyping_direction() {
+    let mut engine = Engine::new();
+    // Let's check to make sure that "supertype" and "subtype" never got mixed up:
+
+    assert_m!(engine.define("ident", "forall T . .[ a : T . a ]."), Ok(_));
+
+    assert_eq!(engine.eval("(ident five)"), Ok(val!(i 5)));
+
+    assert_m!(engine.eval("( .[ a : [Int -> Int] . a]. ident)"), Ok(_));
+
+    assert_m!(engine.eval("( .[ a : forall T . [T -> T] . a]. .[a : Int . a].)"), Err(_));
+
+    assert_m!(engine.eval(".[ a : struct {} . a]."), Ok(_));
+
+    assert_m!(
+        engine.eval("( .[ a : struct {normal : Int extra : Int} . a]. *[normal : one]*)"),
+        Err(_)
+    );
+
+    assert_m!(
+        engine.eval(
+            "( .[ a : struct {normal : Int} . a]. *[normal : one extra : five]*)"
+        ),
+        Ok(_)
+    );
+}
+
+#[test]
+fn end_to_end_quotation_advanced() {
+    let mut engine = Engine::new();
+    assert_eq!(
+        engine.eval(
+            "(.[five_e : Expr < Int >.
+                '[Expr | (plus five ,[five_e],) ]' ].
+                '[Expr | five]')"
+        ),
+        engine.eval("'[Expr | (plus five five) ]'")
+    );
+
+    // Pass the wrong type (not really a test of quotation)
+    assert_m!(
+        engine.typecheck(
+            "(.[five_e : Expr<Int> .
+                '[Expr | (plus five ,[five_e],) ]' ].
+                '[Expr | true]')"
+        ),
+        Err(_)
+    );
+
+    // Interpolate the wrong type
+    assert_m!(
+        engine.typecheck(
+            "(.[five_e : Expr<Bool> .
+                '[Expr | (plus five ,[five_e],) ]' ].
+                '[Expr | true]')"
+        ),
+        Err(_)
+    );
+
+    // Interpolate the wrong type (no application needed to find the error)
+    assert_m!(
+        engine.typecheck(".[five_e : Expr<Bool> . '[Expr | (plus five ,[five_e],) ]' ]."),
+        Err(_)
+    );
+
+    assert_m!(
+        engine.eval(
+            "forall T . .[type : Type<T>   rhs : Expr<T>
+                . '[Expr | (.[x : ,[Type<T> | type], . eight].  ,[rhs], )]' ]."
+        ),
+        Ok(_)
+    );
+
+    assert_m!(engine.eval("'[Pat<Nat> | x]'"), Ok(_));
+
+    // Actually import a pattern of quoted syntax:
+    assert_eq!(
+        engine.eval(
+            "match '[Expr | (plus one two) ]' {
+                 '[Expr<Int> | (plus ,[Expr<Int> | e], two) ]' => e }"
+        ),
+        Ok(val!(ast (vr "one")))
+    );
+
+    // In order to have "traditional", non-type-annotated `let`, we want to ... reify T, I guess?
+    // But the whole language has parametricity kinda baked in, and that seems to make it hard?
+    // I think the solution is to build `let` into the language;
+    //  if a macro wants to have non-annotated binding, it's probably expandable to `let` anyways.
+    assert_m!(
+        engine.define(
+            "let",
+            "forall T S . .[binder : Pat<T>
+                        type : Type<T>
+                        rhs : Expr<T>
+                        body : Expr<S> .
+             '[ Expr | (.[x : ,[type],
+                     . match x { ,[Pat<T> | binder], => ,[body], } ].
+                 ,[rhs],)]' ]."
+        ),
+        Ok(_)
+    );
+
+    without_freshening! {
+        assert_eq!(
+            engine.eval(
+                "(let  '[Pat<Int> | y]'
+                       '[Type<Int> | Int]'
+                       '[Expr<Int> | eight]'
+                       '[Expr<Int> | five]')"),
+            engine.eval("'[Expr<Int> | (.[x : Int . match x {y => five}].  eight)]'"));
+    }
+
+    //  // We need tuple literals before we can test this:
+    //  assert_m!(engine.define("let-multi",
+    //      "forall T . .[ binder : **[ :::[T >> Ident<T> ]::: ]**
+    //                     type : **[ :::[T >> Type<T> ]::: ]**
+    //                     rhs : **[ :::[T >> Expr<T> ]::: ]**
+    //                     body : Expr<S> .
+    //          '[Expr | (.[ ...[, binder , >> ,[Ident | binder],]...
+    //                       : ...[, type , >> ,[Type | type], ]... .
+    //                    ,[body], ].
+    //                      ...[, Expr , | ,[rhs], ]... ) ]'
+    //                       "),
+    //       Ok(_));
+
+    //  without_freshening! {
+    //      assert_eq!(
+    //          engine.eval(
+    //              "(let-multi  '[Ident<Int> | y]'
+    //                     '[Type<Int> | Int]'
+    //                     '[Expr<Int> | eight]'
+    //                     '[Expr<Int> | five]')"),
+    //          engine.eval("'[Expr<Int> | (.[x : Int . match x {y => five}].  eight)]'"));
+    //  }
+}
+
+#[test]
+fn end_to_end_interpolated_atoms() {
+    let engine = Engine::new();
+    without_freshening! {
+        // Pull a binder out of quoted syntax...
+        assert_eq!(
+            engine.eval(
+                "match '[Expr | .[ x : Int . eight ]. ]' {
+                     '[Expr | .[ ,[Ident | nm], : Int . ,[Expr<Int> | b], ]. ]' => nm }"
+            ),
+            Ok(val!(ast "x"))
+        );
+        // ...and put it somewhere else:
+        assert_eq!(
+            engine.eval(
+                "match '[Expr | .[ x : Int . eight ]. ]' {
+                     '[Expr | .[ ,[Ident | nm], : Int . ,[Expr<Int> | b], ]. ]' =>
+                         '[Expr | *[ ,[nm], : ,[b], ]* ]' }"
+            ),
+            engine.eval("'[Expr | *[ x : eight ]* ]'")
+        );
+    }
+    assert_m!(
+        engine.typecheck(
+            ".[ names : **[Ident Ident]** .
+                '[Type | forall ...[,names, >> ,[names], ]... . Int ]' ]."
+        ),
+        Ok(_)
+    );
+
+    // An interpolated name can be referred to, too:
+    without_freshening! {
+        let add_one = engine
+            .eval(
+                "match '[Expr | .[ x : Int . eight ]. ]' {
+                     '[Expr | .[ ,[Ident | nm], : Int . ,[Expr<Int> | b], ]. ]' =>
+                         '[Expr | .[ ,[nm], : Int . (plus ,[nm], one) ]. ]' }",
+            )
+            .unwrap();
+        assert_eq!(Ok(add_one), engine.eval("'[Expr | .[ x : Int . (plus x one) ]. ]'"));
+        let id_ty = engine
+            .eval(
+                "match '[Expr | .[ x : Int . eight ]. ]' {
+                     '[Expr | .[ ,[Ident | nm], : Int . ,[Expr<Int> | b], ]. ]' =>
+                         '[Type | forall ,[nm], . [ ,[nm], -> ,[nm], ] ]' }",
+            )
+            .unwrap();
+        assert_eq!(Ok(id_ty), engine.eval("'[Type | forall x . [ x -> x ] ]'"));
+    }
+    assert_m!(
+        engine.typecheck(".[ nm : Ident . '[Expr | .[ ,[nm], : Int . (plus ,[nm], one) ]. ]' ]."),
+        Ok(_)
+    );
+    assert_m!(
+        engine.typecheck(".[ nm : Ident . '[Type | forall ,[nm], . [ ,[nm], -> ,[nm], ] ]' ]."),
+        Ok(_)
+    );
+
+    // Only `Ident`s can be interpolated as atoms:
+    let err = engine
+        .typecheck(".[ e : Expr<Int> . '[Expr | .[ ,[e], : Int . five ]. ]' ].")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Mismatch"), "{}", err);
+    assert_m!(
+        engine.typecheck(
+            ".[ names : **[Ident Int]** .
+                '[Type | forall ...[,names, >> ,[names], ]... . Int ]' ]."
+        ),
+        Err(_)
+    );
+
+    // A binding form, written as a macro:
+    let fn1_prog = "extend_syntax
+            Expr ::=also forall A R . '{
+                [
+                    lit ,{ DefaultToken }, = 'fn1'
+                    arg := ( ,{ DefaultAtom }, )
+                    lit ,{ DefaultToken }, = ':'
+                    arg_type := ( ,{ Type<A> }, )
+                    lit ,{ DefaultToken }, = '->'
+                    body := ( ,{ Expr<R> }, <-- arg = arg_type )
+                ]
+            }' fn1_macro -> .{
+                '[Expr | .[ ,[arg], : ,[arg_type], . ,[body], ]. ]'
+            }. ;
+        in
+        (fn1 x : Int -> (plus x x) four)";
+    assert_eq!(engine.eval(fn1_prog), Ok(val!(i 8)));
+}
+
+#[test]
+fn end_to_end_variadic_functions() {
+    let engine = Engine::new();
+
+    // `:::[ ]:::` in the parameter list of a function type stands for one parameter per argument:
+    let letfn_macro = "extend_syntax
+            Expr ::=also forall A R T . '{
+                [
+                    lit ,{ DefaultToken }, = 'letfn'
+                    fn_name := ( ,{ DefaultAtom }, )
+                    lit ,{ DefaultToken }, = '('
+                    [
+                        arg := ( ,{ DefaultAtom }, )
+                        lit ,{ DefaultToken }, = ':'
+                        arg_type := ( ,{ Type<A> }, )
+                    ] *
+                    lit ,{ DefaultToken }, = ')'
+                    lit ,{ DefaultToken }, = '->'
+                    ret_type := ( ,{ Type<R> }, )
+                    lit ,{ DefaultToken }, = '='
+                    fn_body := ( ,{ Expr<R> }, <-- ...[arg = arg_type]... )
+                    lit ,{ DefaultToken }, = 'in'
+                    body := ( ,{ Expr<T> },
+                        <-- fn_name = [ :::[arg_type >> arg_type]::: -> ret_type ] )
+                ]
+            }' letfn_macro -> .{
+                '[Expr | (.[ ,[fn_name], : [ ...[,arg_type, >> ,[arg_type],]... -> ,[ret_type], ]
+                             . ,[body], ].
+                          .[ ...[,arg, >> ,[arg],]... : ...[,arg_type, >> ,[arg_type],]...
+                             . ,[fn_body], ].) ]'
+            }. ;
+        in ";
+
+    assert_eq!(
+        engine.eval(&format!(
+            "{} letfn sum3 (a : Int  b : Int  c : Int) -> Int = (plus a (plus b c))
+                in (sum3 one two three)",
+            letfn_macro
+        )),
+        Ok(val!(i 6))
+    );
+
+    // The parameters don't need to have the same type:
+    assert_eq!(
+        engine.eval(&format!(
+            "{} letfn twice (x : Int  f : [Int -> Int]) -> Int = (f (f x))
+                in (twice three .[n : Int . (plus n n)].)",
+            letfn_macro
+        )),
+        Ok(val!(i 12))
+    );
+
+    let err = engine
+        .typecheck(&format!(
+            "{} letfn sum3 (a : Int  b : Int  c : Int) -> Int = (plus a (plus b c))
+                in (sum3 one two)",
+            letfn_macro
+        ))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("LengthMismatch"), "{}", err);
+
+    let err = engine
+        .typecheck(&format!(
+            "{} letfn twice (x : Int  f : [Int -> Int]) -> Int = (f (f x))
+                in (twice .[n : Int . (plus n n)]. three)",
+            letfn_macro
+        ))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Mismatch"), "{}", err);
+}
+
+#[test]
+fn end_to_end_ambiguity() {
+    let mut engine = Engine::new();
+
+    // Without a precedence declaration, `+` doesn't know how to group:
+    let add = "extend_syntax
+            Expr ::=also forall . '{ [
+                lhs := ( ,{ Expr<Int> }, )
+                lit ,{ DefaultToken }, = '+'
+                rhs := ( ,{ Expr<Int> }, )
+            ] }' add -> .{ '[Expr | (plus ,[lhs], ,[rhs],)]' }. ;
+        in ";
+
+    assert_eq!(engine.eval(&format!("{} one + two", add)), Ok(val!(i 3)));
+    let err = engine.eval(&format!("{} one + two + three", add)).unwrap_err();
+    match err {
+        Error::Parse(ref e) => {
+            assert_eq!(e.loc.as_ref().map(|l| l.snippet()), Some("one + two + three".to_owned()));
+        }
+        _ => panic!("expected a parse error, got {}", err),
+    }
+    let err = err.to_string();
+    assert!(err.starts_with("error: Ambiguous syntax"), "{}", err);
+    assert!(err.contains("lhs = `one + two`, rhs = `three`"), "{}", err);
+    assert!(err.contains("lhs = `one`, rhs = `two + three`"), "{}", err);
+
+    // Repeated parts are described, too:
+    let dbl = "extend_syntax
+            Expr ::=also forall . '{ [
+                lit ,{ DefaultToken }, = 'dbl'
+                arg := ( ,{ Expr<Int> }, )
+            ] }' dbl -> .{ '[Expr | (plus ,[arg], ,[arg],)]' }. ;
+        in ";
+    let err = engine.eval(&format!("{} (plus dbl three four)", dbl)).unwrap_err().to_string();
+    assert!(err.contains("rand = [`dbl`, `three`, `four`]"), "{}", err);
+    assert!(err.contains("rand = [`dbl three`, `four`]"), "{}", err);
+
+    // Either way, it's six:
+    engine.set_ambiguity_policy(AmbiguityPolicy::Warn);
+    assert_eq!(engine.eval(&format!("{} one + two + three", add)), Ok(val!(i 6)));
+    let warnings = engine.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].code, Some("Ambiguity".to_owned()));
+    assert!(warnings[0].render(false).starts_with("warning[Ambiguity]"));
+
+    // Other engines are unaffected:
+    assert!(Engine::new().eval(&format!("{} one + two + three", add)).is_err());
+    // ...and keep their warnings to themselves:
+    let mut other = Engine::new();
+    other.set_ambiguity_policy(AmbiguityPolicy::Warn);
+    assert_eq!(other.eval(&format!("{} one + two + three", add)), Ok(val!(i 6)));
+    assert_eq!(engine.take_warnings(), vec![]);
+    assert_eq!(other.take_warnings().len(), 1);
+}
+
+#[test]
+fn end_to_end_parse_recovery() {
+    let engine = Engine::new();
+
+    // Each of these is reported, rather than just the first:
+    let program = "(plus (times one @@ two) (plus .[ a : Int . ]. [Int -> ]))";
+    let err = engine.eval(program).unwrap_err();
+    let diags = err.diagnostics();
+    assert_eq!(diags.len(), 3, "{}", err);
+    // Nonterminals are described by what they can start with:
+    assert!(diags[0].notes[0].msg.starts_with("expected one of: DefaultAtom, `'[`, `(`, `)`,"));
+    assert!(diags[1].notes[0].msg.contains("`.[`, `extend_syntax`"), "{}", err);
+    assert!(diags[1].notes[0].msg.contains("int_literal, string_literal"), "{}", err);
+    assert!(!diags[1].notes[0].msg.contains("Expr"), "{}", err);
+    match err {
+        Error::Parse(ref e) => assert_eq!(e.also.len(), 2),
+        _ => panic!("expected a parse error, got {}", err),
+    }
+
+    // Running off the end lists what could have come next:
+    let err = engine.eval("(plus one").unwrap_err().to_string();
+    assert!(err.contains("Could not parse past"), "{}", err);
+    assert!(err.contains("expected one of: DefaultAtom, `'[`, `(`, `)`,"), "{}", err);
+
+    let error_locs = |program: &str| -> Vec<(usize, usize)> {
+        let err = Engine::new().run(program).unwrap_err();
+        err.diagnostics().iter().map(|d| d.span.as_ref().unwrap().line_col()).collect()
+    };
+    // The same spot isn't reported twice, and getting stuck at a resync point isn't reported:
+    assert_eq!(error_locs("a := (plus one ;"), vec![(1, 16)]);
+
+    // `;` and `in` are places to resync:
+    assert_eq!(
+        error_locs("extend_syntax Expr ::=also @@ ; Pat ::=also @@ ; in one"),
+        vec![(1, 28), (1, 45)]
+    );
+    assert_eq!(
+        error_locs("extend_syntax\n    Expr ::=also @@ ;\n    Pat ::=also @@ ;\n(plus one two)"),
+        vec![(2, 18), (3, 17)]
+    );
+    assert_eq!(
+        error_locs("let_type T = Int -> U = T in (plus one two"),
+        vec![(1, 18), (1, 43)]
+    );
+}
+
+#[test]
+fn end_to_end_precedence() {
+    let engine = Engine::new();
+
+    let infix = |assoc: &str, op: &str, f: &str, name: &str, ret: &str| {
+        format!(
+            "Expr ::=also {assoc} {{ forall . '{{ [
+                lhs := ( ,{{ Expr<Int> }}, )
+                lit ,{{ DefaultToken }}, = '{op}'
+                rhs := ( ,{{ Expr<Int> }}, )
+            ] }}' {name} -> .{{ '[Expr<{ret}> | ({f} ,[lhs], ,[rhs],)]' }}. }} ;",
+            assoc = assoc,
+            op = op,
+            f = f,
+            name = name,
+            ret = ret
+        )
+    };
+    let arith = format!(
+        "extend_syntax {} {} {} {} {} in ",
+        infix("infixl 6", "+", "plus", "add", "Int"),
+        infix("infixl 6", "-", "minus", "sub", "Int"),
+        infix("infixl 7", "*", "times", "mul", "Int"),
+        infix("infixr 5", "-->", "minus", "sub_r", "Int"),
+        infix("infix 4", "==", "equal?", "eq", "Bool")
+    );
+
+    // `*` binds tighter than `+`, regardless of which comes first:
+    assert_eq!(engine.eval(&format!("{} one + two * three", arith)), Ok(val!(i 7)));
+    assert_eq!(engine.eval(&format!("{} two * three + one", arith)), Ok(val!(i 7)));
+    // `-` is left-associative, `-->` is right-associative:
+    assert_eq!(engine.eval(&format!("{} ten - three - two", arith)), Ok(val!(i 5)));
+    assert_eq!(engine.eval(&format!("{} ten --> three --> two", arith)), Ok(val!(i 9)));
+    assert_eq!(engine.eval(&format!("{} ten - three --> two - one", arith)), Ok(val!(i 6)));
+    // Parentheses are still function application, so use a lambda to group things:
+    assert_eq!(
+        engine.eval(&format!("{} (.[ x : Int . x * two ]. one + two)", arith)),
+        Ok(val!(i 6))
+    );
+
+    // `==` is non-associative:
+    assert_eq!(engine.eval(&format!("{} one + one == two", arith)), Ok(val!(b true)));
+    assert!(engine.eval(&format!("{} one == one == one", arith)).is_err());
+}
+
+#[test]
+fn language_building() {
+    let engine = Engine::new();
+    assert_eq!(
+        engine.eval(
+            r"extend_syntax
+                DefaultSeparator ::= /((?:\s|#[^\n]*)*)/ ;
+            in
+                # Now we have comments! (just not after the last token)
+            five"
+        ),
+        Ok(val!(i 5))
+    );
+
+    let bound_wrong_prog = "extend_syntax
+            Expr ::=also forall T S . '{
+                [
+                    lit ,{ DefaultToken }, = 'let'
+                    [
+                        pat := ( ,{ Pat<S> }, )
+                        lit ,{ DefaultToken }, = '='
+                        value := ( ,{ Expr<S> }, )
+                        lit ,{ DefaultToken }, = ';'
+                    ] *
+                    lit ,{ DefaultToken }, = 'in'
+                    body := ( ,{ Expr<T> }, <-- ...[pat = value]... )
+                ]
+            }' let_macro -> .{
+                '[Expr |
+                    match ...[,value, >> ,[value], ]...
+                        { ...[,pat, >> ,[pat],]... => ,[body], } ]'
+            }. ;
+        in
+        let x = eight ;
+            y = times ;
+        in (plus x y)";
+    let bound_wrong_ast = crate::grammar::parse(
+        &crate::core_forms::outermost_form(),
+        &crate::core_forms::get_core_forms(),
+        crate::runtime::core_values::get_core_envs(),
+        bound_wrong_prog,
+    )
+    .unwrap();
+
+    assert_m!(
+        crate::ty::synth_type(&bound_wrong_ast, crate::runtime::core_values::core_types()),
+        ty_err_p!(Mismatch(y, x)) => {
+            assert_eq!(x, uty!({Int :}));
+            assert_eq!(y, uty!({fn : [{Int :}; {Int :}] {Int :}}));
+        }
+    );
+
+    let inner_expr_wrong_prog = "extend_syntax
+            Expr ::=also forall T S . '{
+                [
+                    lit ,{ DefaultToken }, = 'let'
+                    [
+                        pat := ( ,{ Pat<S> }, )
+                        lit ,{ DefaultToken }, = '='
+                        value := ( ,{ Expr<S> }, )
+                        lit ,{ DefaultToken }, = ';'
+                    ] *
+                    lit ,{ DefaultToken }, = 'in'
+                    body := ( ,{ Expr< T > }, <-- ...[pat = value]... )
+                ]
+            }' let_macro -> .{
+                '[Expr |
+                    match ...[,value, >> ,[value], ]...
+                        { ...[,pat, >> ,[pat],]... => ,[body], } ]'
+            }. ;
+        in
+        let x = eight ;
+            y = four ;
+        in (plus x times)";
+    let inner_expr_wrong_ast = crate::grammar::parse(
+        &crate::core_forms::outermost_form(),
+        &crate::core_forms::get_core_forms(),
+        crate::runtime::core_values::get_core_envs(),
+        inner_expr_wrong_prog,
+    )
+    .unwrap();
+
+    assert_m!(
+        crate::ty::synth_type(&inner_expr_wrong_ast, crate::runtime::core_values::core_types()),
+        ty_err_p!(Mismatch(times, x)) => {
+            assert_eq!(x, uty!({Int :}));
+            assert_eq!(times, uty!({fn : [{Int :}; {Int :}] {Int :}}));
+        }
+    );
+
+    // TODO: leaving out the `**[ ]**` results in an ICP; it should be a static error.
+
+    let let_macro_prog = "extend_syntax
+            Expr ::=also forall T S . '{
+                [
+                    lit ,{ DefaultToken }, = 'let'
+                    [
+                        pat := ( ,{ Pat<S> }, )
+                        lit ,{ DefaultToken }, = '='
+                        value := ( ,{ Expr<S> }, )
+                        lit ,{ DefaultToken }, = ';'
+                    ] *
+                    lit ,{ DefaultToken }, = 'in'
+                    body := ( ,{ Expr<T> }, <-- ...[pat = value]... )
+                ]
+            }' let_macro -> .{
+                '[Expr |
+                    match **[...[,value, >> ,[value], ]... ]**
+                        { **[...[,pat, >> ,[pat],]... ]** => ,[body], } ]'
+            }. ;
+        in
+        let x = eight ;
+            y = four ;
+        in (plus y (plus x y))";
+    assert_eq!(engine.eval(let_macro_prog), Ok(val!(i 16)));
+}
+
+
@@ -0,0 +1,287 @@
+//! Checking the arms of a `match` against the type of its scrutinee:
+//!  every value should be matched by some arm, and every arm should be able to match something.
+//!
+//! This is the "pattern matrix" approach from Maranget's "Warnings for pattern matching" (2007):
+//!  each arm is a row, and we ask whether a new row is "useful" (matches something that the
+//!  rows above it don't). An arm that isn't useful is unreachable,
+//!  and if a wildcard after the last arm would be useful, the match isn't exhaustive.
+//!
+//! Only enums, structs, and tuples can be covered by listing their constructors;
+//!  integer literals can only be covered by a variable.
+//! Syntax quotations get the benefit of the doubt (see `optimistic`).
+
+use crate::{
+    ast::{Ast, AstContents::*},
+    ast_walk::Clo,
+    core_forms::{ast_to_name, find_core_form, parse_int_literal},
+    name::*,
+    ty::{Ty, TypeError},
+    util::{assoc::Assoc, diagnostics::Diagnostic},
+};
+use num::bigint::BigInt;
+use std::fmt;
+
+/// Just enough about a pattern to tell what it matches.
+#[derive(Clone, Debug, PartialEq)]
+enum Pat {
+    /// Matches anything (i.e., a variable).
+    Wild,
+    /// We can't tell what it matches (e.g., an unquotation inside a quoted pattern,
+    ///  or an unexpanded macro). We give it the benefit of the doubt:
+    ///  it might match anything, and no arm is unreachable because of it.
+    Opaque,
+    Ctor(Ctor, Vec<Pat>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Ctor {
+    Enum(Name),
+    /// The field names, in the order the struct type lists them.
+    Struct(Vec<Name>),
+    Tuple,
+    Int(BigInt),
+    /// A syntax quotation; two of them only overlap (as far as we can tell) if they're identical.
+    Quote(Ast),
+}
+
+/// A value that no arm matches, written as a pattern.
+impl fmt::Display for Pat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let spaced = |subs: &[Pat]| subs.iter().map(|s| format!(" {}", s)).collect::<String>();
+        match *self {
+            Pat::Wild | Pat::Opaque => write!(f, "_"),
+            Pat::Ctor(Ctor::Enum(name), ref subs) => write!(f, "+[{}{}]+", name, spaced(subs)),
+            Pat::Ctor(Ctor::Struct(ref names), ref subs) => {
+                let fields: Vec<String> =
+                    names.iter().zip(subs).map(|(name, s)| format!("{}: {}", name, s)).collect();
+                write!(f, "*[{}]*", fields.join(" "))
+            }
+            Pat::Ctor(Ctor::Tuple, ref subs) => {
+                let subs: Vec<String> = subs.iter().map(|s| s.to_string()).collect();
+                write!(f, "**[{}]**", subs.join(" "))
+            }
+            Pat::Ctor(Ctor::Int(ref i), _) => write!(f, "{}", i),
+            Pat::Ctor(Ctor::Quote(ref ast), _) => write!(f, "{}", ast),
+        }
+    }
+}
+
+/// Every way to construct a value of type `t`, with the types of each constructor's components.
+/// `None` if there are too many to list (e.g. `Int`).
+fn signature(t: &Ty, env: &Assoc<Name, Ty>) -> Option<Vec<(Ctor, Vec<Ty>)>> {
+    let Clo { it: t, .. } = crate::ty_compare::unification.with(|unif| {
+        crate::ty_compare::resolve(Clo { it: t.clone(), env: env.clone() }, &unif.borrow())
+    });
+    let tys = |asts: Vec<&Ast>| -> Vec<Ty> { asts.into_iter().map(|a| Ty(a.clone())).collect() };
+
+    if let Some(parts) = t.0.destructure(find_core_form("Type", "enum")) {
+        Some(
+            parts
+                .march_all(&[n("name")])
+                .iter()
+                .map(|arm| {
+                    (
+                        Ctor::Enum(ast_to_name(arm.get_leaf_or_panic(&n("name")))),
+                        tys(arm.get_rep_leaf_or_panic(n("component"))),
+                    )
+                })
+                .collect(),
+        )
+    } else if let Some(parts) = t.0.destructure(find_core_form("Type", "struct")) {
+        let names = parts.get_rep_leaf_or_panic(n("component_name")).into_iter().map(ast_to_name);
+        let components = tys(parts.get_rep_leaf_or_panic(n("component")));
+        Some(vec![(Ctor::Struct(names.collect()), components)])
+    } else {
+        t.0.destructure(find_core_form("Type", "tuple"))
+            .map(|parts| vec![(Ctor::Tuple, tys(parts.get_rep_leaf_or_panic(n("component"))))])
+    }
+}
+
+/// The types of the components of `ctor`, a constructor of `t`.
+fn component_types(t: &Ty, ctor: &Ctor, env: &Assoc<Name, Ty>) -> Vec<Ty> {
+    signature(t, env)
+        .and_then(|sig| sig.into_iter().find(|(c, _)| c == ctor))
+        .map(|(_, tys)| tys)
+        .unwrap_or_default()
+}
+
+/// Simplify the pattern `pat`, which matches values of type `t`.
+fn lower(pat: &Ast, t: &Ty, env: &Assoc<Name, Ty>) -> Pat {
+    let (form, parts) = match *pat.c() {
+        Atom(_) => return Pat::Wild,
+        Node(ref form, ref parts, _) => (form, parts),
+        _ => return Pat::Opaque,
+    };
+    let lower_all = |subs: Vec<&Ast>, tys: &[Ty]| -> Vec<Pat> {
+        subs.into_iter().zip(tys).map(|(sub, t)| lower(sub, t, env)).collect()
+    };
+
+    // If it typechecked, the constructors will be there, and the component counts will agree.
+    if form == &find_core_form("Pat", "enum_pat") {
+        let ctor = Ctor::Enum(ast_to_name(parts.get_leaf_or_panic(&n("name"))));
+        let tys = component_types(t, &ctor, env);
+        Pat::Ctor(ctor, lower_all(parts.get_rep_leaf_or_panic(n("component")), &tys))
+    } else if form == &find_core_form("Pat", "struct_pat") {
+        match signature(t, env).and_then(|sig| sig.into_iter().next()) {
+            Some((Ctor::Struct(names), tys)) => {
+                let pat_names = parts.get_rep_leaf_or_panic(n("component_name"));
+                let pat_subs = parts.get_rep_leaf_or_panic(n("component"));
+                // Fields not mentioned in the pattern can be anything:
+                let subs = names
+                    .iter()
+                    .zip(&tys)
+                    .map(|(name, t)| {
+                        match pat_names.iter().position(|p| ast_to_name(p) == *name) {
+                            Some(i) => lower(pat_subs[i], t, env),
+                            None => Pat::Wild,
+                        }
+                    })
+                    .collect();
+                Pat::Ctor(Ctor::Struct(names), subs)
+            }
+            _ => Pat::Opaque,
+        }
+    } else if form == &find_core_form("Pat", "tuple_pat") {
+        let tys = component_types(t, &Ctor::Tuple, env);
+        Pat::Ctor(Ctor::Tuple, lower_all(parts.get_rep_leaf_or_panic(n("component")), &tys))
+    } else if form == &find_core_form("Pat", "int_pat") {
+        let lit = ast_to_name(parts.get_leaf_or_panic(&n("body"))).orig_sp();
+        Pat::Ctor(Ctor::Int(parse_int_literal(&lit)), vec![])
+    } else if form.name == n("quote_pat") {
+        Pat::Ctor(Ctor::Quote(pat.clone()), vec![])
+    } else {
+        Pat::Opaque
+    }
+}
+
+/// Treat `Opaque` patterns as matching anything.
+/// So are syntax quotations: they're often used just to pull apart syntax of a known shape.
+fn optimistic(pat: &Pat) -> Pat {
+    match *pat {
+        Pat::Opaque | Pat::Ctor(Ctor::Quote(_), _) => Pat::Wild,
+        Pat::Ctor(ref c, ref subs) => Pat::Ctor(c.clone(), subs.iter().map(optimistic).collect()),
+        Pat::Wild => Pat::Wild,
+    }
+}
+
+/// The rows that can match a value built with `ctor`, with its components spread out.
+/// (`Opaque` patterns are assumed not to match.)
+fn specialize(rows: &[Vec<Pat>], ctor: &Ctor, arity: usize) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| {
+            let mut res = match row[0] {
+                Pat::Wild => vec![Pat::Wild; arity],
+                Pat::Ctor(ref c, ref subs) if c == ctor => subs.clone(),
+                _ => return None,
+            };
+            res.extend_from_slice(&row[1..]);
+            Some(res)
+        })
+        .collect()
+}
+
+/// The rows that match anything in the first column, without that column.
+fn default_rows(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    rows.iter().filter(|row| row[0] == Pat::Wild).map(|row| row[1..].to_vec()).collect()
+}
+
+fn heads_include(rows: &[Vec<Pat>], ctor: &Ctor) -> bool {
+    rows.iter().any(|row| matches!(row[0], Pat::Ctor(ref c, _) if c == ctor))
+}
+
+/// The constructors of `sig`, if the first column of `rows` mentions all of them.
+fn complete<'a>(
+    sig: &'a Option<Vec<(Ctor, Vec<Ty>)>>,
+    rows: &[Vec<Pat>],
+) -> Option<&'a Vec<(Ctor, Vec<Ty>)>> {
+    sig.as_ref().filter(|sig| sig.iter().all(|(c, _)| heads_include(rows, c)))
+}
+
+/// Can `row` match something that none of `rows` do? (Each column has the type in `tys`.)
+fn useful(rows: &[Vec<Pat>], row: &[Pat], tys: &[Ty], env: &Assoc<Name, Ty>) -> bool {
+    let (t, rest_tys) = match tys.split_first() {
+        None => return rows.is_empty(),
+        Some(split) => split,
+    };
+    let then = |c: &Ctor, subs: Vec<Pat>, sub_tys: Vec<Ty>| {
+        let arity = subs.len();
+        let row: Vec<Pat> = subs.into_iter().chain(row[1..].iter().cloned()).collect();
+        let tys: Vec<Ty> = sub_tys.into_iter().chain(rest_tys.iter().cloned()).collect();
+        useful(&specialize(rows, c, arity), &row, &tys, env)
+    };
+
+    match row[0] {
+        Pat::Ctor(ref c, ref subs) => then(c, subs.clone(), component_types(t, c, env)),
+        // If nothing could match a wildcard here, nothing could match an opaque pattern either:
+        Pat::Wild | Pat::Opaque => {
+            let sig = signature(t, env);
+            match complete(&sig, rows) {
+                Some(sig) => sig
+                    .iter()
+                    .any(|(c, sub_tys)| then(c, vec![Pat::Wild; sub_tys.len()], sub_tys.clone())),
+                None => useful(&default_rows(rows), &row[1..], rest_tys, env),
+            }
+        }
+    }
+}
+
+/// A row of values (as patterns) that none of `rows` match, if there is one.
+fn missing(rows: &[Vec<Pat>], tys: &[Ty], env: &Assoc<Name, Ty>) -> Option<Vec<Pat>> {
+    let (t, rest_tys) = match tys.split_first() {
+        None => return if rows.is_empty() { Some(vec![]) } else { None },
+        Some(split) => split,
+    };
+    let sig = signature(t, env);
+    match complete(&sig, rows) {
+        Some(sig) => sig.iter().find_map(|(c, sub_tys)| {
+            let tys: Vec<Ty> = sub_tys.iter().chain(rest_tys).cloned().collect();
+            let mut witness = missing(&specialize(rows, c, sub_tys.len()), &tys, env)?;
+            let rest = witness.split_off(sub_tys.len());
+            Some(std::iter::once(Pat::Ctor(c.clone(), witness)).chain(rest).collect())
+        }),
+        None => {
+            let rest = missing(&default_rows(rows), rest_tys, env)?;
+            // Prefer naming a constructor that isn't mentioned at all:
+            let head = sig
+                .iter()
+                .flatten()
+                .find(|(c, _)| !heads_include(rows, c))
+                .map(|(c, sub_tys)| Pat::Ctor(c.clone(), vec![Pat::Wild; sub_tys.len()]))
+                .unwrap_or(Pat::Wild);
+            Some(std::iter::once(head).chain(rest).collect())
+        }
+    }
+}
+
+/// Check the patterns of the arms of a `match` on a value of type `scrutinee_ty`.
+/// Values that no arm matches are an error (reported at `loc`);
+///  arms that can never be reached get a warning.
+pub fn check_match(
+    scrutinee_ty: &Ty,
+    pats: &[Ast],
+    env: &Assoc<Name, Ty>,
+    loc: &Ast,
+) -> Result<(), TypeError>
+{
+    let tys = [scrutinee_ty.clone()];
+    let mut rows: Vec<Vec<Pat>> = vec![];
+    for pat in pats {
+        let row = vec![lower(pat, scrutinee_ty, env)];
+        if !useful(&rows, &row, &tys, env) {
+            // Binders in `pat` have been freshened; show them as written:
+            let written = crate::alpha::map_names(pat, &mut |nm| nm.unhygienic_orig());
+            crate::util::diagnostics::warn(
+                Diagnostic::new(format!("unreachable arm `{}`", written), pat.some_loc())
+                    .code("UnreachableArm")
+                    .label("earlier arms match everything this does".to_owned()),
+            );
+        }
+        rows.push(row);
+    }
+
+    let rows: Vec<Vec<Pat>> = rows.iter().map(|row| row.iter().map(optimistic).collect()).collect();
+    if let Some(witness) = missing(&rows, &tys, env) {
+        ty_err!(NonExhaustiveMatch(scrutinee_ty.clone(), witness[0].to_string()) at loc.clone());
+    }
+    Ok(())
+}
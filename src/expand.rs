@@ -47,11 +47,12 @@ impl WalkMode for ExpandMacros {
 
     fn walk_var(
         name: Name,
-        _parts: &crate::ast_walk::LazyWalkReses<ExpandMacros>,
+        parts: &crate::ast_walk::LazyWalkReses<ExpandMacros>,
     ) -> Result<Value, Self::Err>
     {
         use crate::runtime::reify::Reifiable;
-        Ok(crate::ast::VariableReference(name).reify()) // Even variables are literal in macro expansion!
+        // Even variables are literal in macro expansion! (Keep the location, for errors.)
+        Ok(parts.this_ast.with_c(crate::ast::VariableReference(name)).reify())
     }
 }
 impl WalkMode for UnusedNegativeExpandMacros {
@@ -72,7 +73,7 @@ impl NegativeWalkMode for UnusedNegativeExpandMacros {
 }
 
 // I *think* the environment doesn't matter
-pub fn expand(ast: &Ast) -> Result<Ast, ()> {
+pub fn expand(ast: &Ast) -> Result<Ast, crate::runtime::eval::RuntimeError> {
     use crate::runtime::reify::Reifiable;
     Ok(Ast::reflect(&crate::ast_walk::walk::<ExpandMacros>(ast, &LazyWalkReses::new_empty())?))
 }
@@ -198,8 +199,8 @@ fn expand_basic_macros() {
             );
             x // let_pat
             five // let_val
-            (, Ast::ExtendEnv(Box::new(u!({apply : times [x ; eight]})),
-                              beta!(["let_pat" = "let_val"]))) // let_body
+            (, raw_ast!(ExtendEnv(Box::new(u!({apply : times [x ; eight]})),
+                              beta!(["let_pat" = "let_val"])))) // let_body
         })),
         Ok(u!({match : five [x {apply : times [x ; eight]}]}))
     );
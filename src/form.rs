@@ -59,23 +59,13 @@ impl<Mode: WalkMode> EitherPN<WalkRule<Mode>, WalkRule<Mode::Negated>> {
             Positive(_) => &WalkRule::NotWalked,
         }
     }
-    pub fn is_pos(&self) -> bool {
-        match *self {
-            Negative(_) => false,
-            _ => true,
-        }
-    }
-    pub fn is_neg(&self) -> bool {
-        match *self {
-            Positive(_) => false,
-            _ => true,
-        }
-    }
+    pub fn is_pos(&self) -> bool { !matches!(*self, Negative(_)) }
+    pub fn is_neg(&self) -> bool { !matches!(*self, Positive(_)) }
 }
 
 impl PartialEq for Form {
     /// pointer equality on the underlying structure!
-    fn eq(&self, other: &Form) -> bool { self as *const Form == other as *const Form }
+    fn eq(&self, other: &Form) -> bool { std::ptr::eq(self, other) }
 }
 
 impl Debug for Form {
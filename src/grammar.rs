@@ -1,7 +1,7 @@
 #![macro_use]
 
 use crate::{
-    ast::Ast::{self, *},
+    ast::{Ast, AstContents::*},
     beta::{Beta, ExportBeta},
     form::{simple_form, Form},
     name::*,
@@ -52,6 +52,10 @@ custom_derive! {
         Alt(Vec<Rc<FormPat>>),
         /// Matches the LHS pattern, or, failing that, the RHS pattern.
         Biased(Rc<FormPat>, Rc<FormPat>),
+        /// Matches an infix (or prefix, or postfix) form; its leftmost and rightmost `Call`s are
+        ///  operands. An operand can't be a `Prec` with a lower level (a looser binding),
+        ///  and the `Associativity` determines which operand can have the same level.
+        Prec(Rc<FormPat>, u8, Associativity),
 
         /// Lookup a nonterminal in the current syntactic environment.
         Call(Name),
@@ -80,6 +84,19 @@ custom_derive! {
     }
 }
 
+custom_derive! {
+    /// Which operand of a `Prec` may be another `Prec` at the same level.
+    #[derive(Debug, Clone, Copy, Reifiable, PartialEq, Eq)]
+    pub enum Associativity {
+        /// `a - b - c` is `(a - b) - c`
+        Left,
+        /// `a -> b -> c` is `a -> (b -> c)`
+        Right,
+        /// `a == b == c` doesn't parse
+        Non,
+    }
+}
+
 impl FormPat {
     // Finds all `Named` nodes, and how many layers of repetition they are underneath.
     pub fn binders(&self) -> Vec<(Name, u8)> {
@@ -104,6 +121,7 @@ impl FormPat {
             | QuoteDeepen(ref body, _)
             | QuoteEscape(ref body, _)
             | Common(ref body)
+            | Prec(ref body, _, _)
             | Reserved(ref body, _) => body.binders(),
             Biased(ref body_a, ref body_b) => {
                 body_a.binders().tap(|v| v.append(&mut body_b.binders()))
@@ -140,6 +158,7 @@ impl FormPat {
             | QuoteDeepen(ref body, _)
             | QuoteEscape(ref body, _)
             | Common(ref body)
+            | Prec(ref body, _, _)
             | Reserved(ref body, _) => body.find_named_call(n),
             Seq(ref bodies) | Alt(ref bodies) => {
                 for body in bodies {
@@ -159,13 +178,13 @@ impl FormPat {
 
 #[derive(Clone)]
 pub struct SyntaxExtension(
-    pub Rc<Box<(dyn Fn(crate::earley::ParseContext, Ast) -> crate::earley::ParseContext)>>,
+    pub Rc<Box<dyn Fn(crate::earley::ParseContext, Ast) -> crate::earley::ParseContext>>,
 );
 
 impl PartialEq for SyntaxExtension {
     /// pointer equality! (for testing)
     fn eq(&self, other: &SyntaxExtension) -> bool {
-        self as *const SyntaxExtension == other as *const SyntaxExtension
+        std::ptr::eq(self, other)
     }
 }
 
@@ -176,7 +195,21 @@ impl reify::Reifiable for SyntaxExtension {
 
     fn reify(&self) -> Value { reify::reify_2ary_function(self.0.clone()) }
 
-    fn reflect(v: &Value) -> Self { SyntaxExtension(reify::reflect_2ary_function(v.clone())) }
+    fn reflect(v: &Value) -> Self {
+        let f = reify::reflect_2ary_function(v.clone());
+        // Extensions are always Rust functions (reified, and now reflected back);
+        //  those can't fail, and there's no way to report it if they do.
+        SyntaxExtension(Rc::new(Box::new(move |pc, a| {
+            f(pc, a).unwrap_or_else(|e| icp!("syntax extension failed: {}", e))
+        })))
+    }
+
+    fn try_reflect(v: &Value) -> Option<Self> {
+        match *v {
+            Value::BuiltInFunction(_) | Value::Function(_) => Some(Self::reflect(v)),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Debug for SyntaxExtension {
@@ -204,6 +237,10 @@ impl reify::Reifiable for Scanner {
     fn reflect(v: &Value) -> Self {
         Scanner(regex::Regex::new(&<String as reify::Reifiable>::reflect(v)).unwrap())
     }
+
+    fn try_reflect(v: &Value) -> Option<Self> {
+        regex::Regex::new(&<String as reify::Reifiable>::try_reflect(v)?).ok().map(Scanner)
+    }
 }
 
 impl std::fmt::Debug for Scanner {
@@ -219,7 +256,7 @@ pub use crate::earley::parse;
 /// Parse `tt` with the grammar `f` in an empty syntactic environment.
 /// `Call` patterns are errors.
 pub fn parse_top(f: &FormPat, toks: &str) -> Result<Ast, crate::earley::ParseError> {
-    parse(f, &Assoc::new(), crate::earley::empty__code_envs(), toks)
+    parse(f, &Assoc::new(), crate::earley::empty__code_envs(), toks).map_err(|e| *e)
 }
 
 use self::FormPat::*;
@@ -370,7 +407,7 @@ fn advanced_parsing() {
                     Rc::new(Scope(simple_form("o", form_pat!((lit_aat "otherother"))),
                                 crate::beta::ExportBeta::Nothing))),
             crate::earley::empty__code_envs(),
-            &toks_a_b
+            toks_a_b
         )
         .unwrap(),
         ast!({pair_form ; ["rhs" => "b", "lhs" => "a"]})
@@ -421,32 +458,30 @@ fn extensible_parsing() {
         ast!({- "c" => ["O", "O", ("Extend" (() {- "c" => ["AA", "AA", ("Back" {- "c" => ["O"]} "#"), "AA"]}) "#"), "O"]})
     );
 
-    assert_eq!(
+    assert!(
         parse(
             &form_pat!((call "o")),
             &orig,
             crate::earley::empty__code_envs(),
             tokens_s!("O" "O" "Extend" "AA" "AA" "Back" "AA" "#" "AA" "#" "O")
         )
-        .is_err(),
-        true
+        .is_err()
     );
 
-    assert_eq!(
+    assert!(
         parse(
             &form_pat!((call "o")),
             &orig,
             crate::earley::empty__code_envs(),
             tokens_s!("O" "O" "Extend" "O" "#" "O")
         )
-        .is_err(),
-        true
+        .is_err()
     );
 
     let mt_syn_env = Rc::new(Assoc::new());
 
     fn counter_synex(_: ParseContext, a: Ast) -> ParseContext {
-        let count = match a {
+        let count = match a.c() {
             IncompleteNode(mbe) => mbe,
             _ => panic!(),
         }
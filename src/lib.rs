@@ -0,0 +1,79 @@
+//! Unseemly is a "core" typed language with (typed!) macros.
+//! You shouldn't write code in Unseemly.
+//! Instead, you should implement your programming language as Unseemly macros.
+//!
+//! This library lets a Rust program host Unseemly (and the languages built on it)
+//!  through an `Engine`, which owns a grammar and the types and values defined so far:
+//!
+//! ```
+//! let mut engine = unseemly::Engine::new();
+//! engine.define("double", ".[ x : Int . (plus x x) ].").unwrap();
+//! assert_eq!(format!("{}", engine.eval("(double five)").unwrap()), "10");
+//! assert_eq!(format!("{}", engine.typecheck("double").unwrap()), "[ Int -> Int ]");
+//! assert!(engine.eval("(double true)").is_err());
+//! ```
+
+#![allow(dead_code, unused_macros, non_snake_case, unused_imports, non_upper_case_globals)]
+// dead_code and unused_macros are hopefully temporary allowances
+// non_snake_case is stylistic, unused_imports is inaccurate for `cargo check`
+// non_upper_case_globals is stylistic; I like my thread_local!s lowercase.
+#![recursion_limit = "128"] // Yikes.
+
+// for testing; requires `cargo +nightly`
+// #![feature(log_syntax, trace_macros)]
+// trace_macros!(true);
+
+// TODO: turn these into `use` statements in the appropriate places
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate custom_derive;
+#[macro_use]
+extern crate quote;
+
+mod macros;
+
+mod name; // should maybe be moved to `util`; `mbe` needs it
+
+mod util;
+
+mod alpha;
+mod ast;
+mod beta;
+mod read;
+
+mod earley;
+mod grammar;
+mod unparse;
+
+mod form;
+
+mod ast_walk;
+mod exhaustiveness;
+mod expand;
+mod ty;
+mod ty_compare;
+mod type_classes;
+mod walk_mode;
+
+mod engine;
+mod program;
+mod runtime;
+
+mod core_forms;
+mod core_macro_forms;
+mod core_qq_forms;
+mod core_type_forms;
+
+pub use crate::{
+    ast::{Ast, AstContents},
+    engine::{Engine, Error},
+    name::Name,
+    program::Module,
+    runtime::{
+        eval::{RuntimeErr, RuntimeError, Value},
+        reify::{HostFunction, Reifiable},
+    },
+    ty::Ty,
+    util::diagnostics::{Diagnose, Diagnostic},
+};
@@ -10,7 +10,7 @@
 // It's not unsafe to use `u!` for runtime operations, but there's a runtime cost, so don't do it.
 
 use crate::{
-    ast::Ast::{self, *},
+    ast::{Ast, AstContents::*},
     grammar::FormPat,
     name::*,
     util::mbe::EnvMBE,
@@ -29,14 +29,14 @@ macro_rules! u_rep {
     };
     ([] [] {}) => {
         // Empty repeat
-        crate::ast::Shape(vec![crate::ast::Atom(n("REP"))])
+        raw_ast!(Shape(vec![raw_ast!(Atom(n("REP")))]))
     };
     ([]  [ $( $acc_cur:tt )* ] { $( [ $( $acc_rest:tt )* ] )* }) => {
-        crate::ast::Shape(vec![
-            crate::ast::Atom(n("REP")),
+        raw_ast!(Shape(vec![
+            raw_ast!(Atom(n("REP"))),
             $( u_shape_if_many!(  $($acc_rest)* ), )*
             u_shape_if_many!(  $($acc_cur)* )
-        ])
+        ]))
     };
 }
 
@@ -59,13 +59,13 @@ thread_local! {
 macro_rules! u {
     ($atom:ident) => {
         // Default to this, because `Call` will use whatever it's given, without a grammar:
-        crate::ast::VariableReference(n(stringify!($atom)))
+        raw_ast!(VariableReference(n(stringify!($atom))))
     };
     ( [ , $seq:expr ] ) => {
         {
             let mut contents: Vec<Ast> = $seq;
-            contents.insert(0, crate::ast::Atom(n("REP")));
-            crate::ast::Shape(contents)
+            contents.insert(0, raw_ast!(Atom(n("REP"))));
+            raw_ast!(Shape(contents))
         }
     };
     ( [ $( $ts:tt )*  ] ) => {
@@ -76,10 +76,10 @@ macro_rules! u {
             let f = crate::macros::flimsy_syntax::default_nt.with(|def_nt| {
                 crate::core_forms::find_core_form(&def_nt.borrow(), stringify!($form))
             });
-            crate::ast::Node(f.clone(),
+            raw_ast!(Node(f.clone(),
                 crate::macros::flimsy_syntax::parse_flimsy_mbe(&u!( (~ $($ts)* ) ), &f.grammar)
                     .unwrap_or_else(crate::util::mbe::EnvMBE::new),
-                crate::beta::ExportBeta::Nothing)
+                crate::beta::ExportBeta::Nothing))
         }
     };
     ( { $nt:ident $form:ident : $( $ts:tt )*} ) => {
@@ -92,10 +92,10 @@ macro_rules! u {
 
                 crate::core_forms::find_core_form(&nt, stringify!($form))
             });
-            let res = crate::ast::Node(f.clone(),
+            let res = raw_ast!(Node(f.clone(),
                 crate::macros::flimsy_syntax::parse_flimsy_mbe(&u!( (~ $($ts)* ) ), &f.grammar)
                     .unwrap_or_else(crate::util::mbe::EnvMBE::new),
-                crate::beta::ExportBeta::Nothing);
+                crate::beta::ExportBeta::Nothing));
             crate::macros::flimsy_syntax::default_nt.with(|def_nt| {
                 *def_nt.borrow_mut() = old_default_nt;
             });
@@ -109,10 +109,10 @@ macro_rules! u {
             let f = crate::macros::flimsy_syntax::default_nt.with(|def_nt| {
                 crate::core_forms::find_core_form(&def_nt.borrow(), stringify!($form))
             });
-            crate::ast::Node(f.clone(),
+            raw_ast!(Node(f.clone(),
                 crate::macros::flimsy_syntax::parse_flimsy_mbe(&u!( (~ $($ts)* ) ), &f.grammar)
                     .unwrap_or_else(crate::util::mbe::EnvMBE::new),
-                ebeta!($ebeta))
+                ebeta!($ebeta)))
         }
     };
     ( { $nt:ident $form:ident => $ebeta:tt : $( $ts:tt )*} ) => {
@@ -127,10 +127,10 @@ macro_rules! u {
                 crate::core_forms::find_core_form(&nt, stringify!($form))
             });
 
-            let res =crate::ast::Node(f.clone(),
+            let res =raw_ast!(Node(f.clone(),
                     crate::macros::flimsy_syntax::parse_flimsy_mbe(&u!( (~ $($ts)* ) ), &f.grammar)
                         .unwrap_or_else(crate::util::mbe::EnvMBE::new),
-                    ebeta!($ebeta));
+                    ebeta!($ebeta)));
             crate::macros::flimsy_syntax::default_nt.with(|def_nt| {
                 *def_nt.borrow_mut() = old_default_nt;
             });
@@ -140,10 +140,10 @@ macro_rules! u {
     ( { $form:expr ; $( $ts:tt )*} ) => {
         {
             let f = $form;
-            crate::ast::Node(f.clone(),
+            raw_ast!(Node(f.clone(),
                 crate::macros::flimsy_syntax::parse_flimsy_mbe(&u!( (~ $($ts)* ) ), &f.grammar)
                     .unwrap_or_else(crate::util::mbe::EnvMBE::new),
-                crate::beta::ExportBeta::Nothing)
+                crate::beta::ExportBeta::Nothing))
         }
     };
     ({ $( $anything:tt )* }) => {
@@ -151,13 +151,13 @@ macro_rules! u {
     };
     // Currently, nested `Seq`s need to correspond to nested `SEQ`s, so this creates one explicitly:
     ((~ $($ts:tt)*)) => {
-        crate::ast::Shape(vec![
-            crate::ast::Atom(n("SEQ")),
+        raw_ast!(Shape(vec![
+            raw_ast!(Atom(n("SEQ"))),
             $( u!( $ts ) ),*
-        ])
+        ]))
     };
     ((at $t:tt)) => {
-        crate::ast::Atom(n(stringify!($t)))
+        raw_ast!(Atom(n(stringify!($t))))
     };
     ((prim $t:tt)) => {
         crate::core_type_forms::get__primitive_type(n(stringify!($t))).concrete()
@@ -167,10 +167,10 @@ macro_rules! u {
     };
     // Two or more token trees (avoid infinite regress by not handling the one-element case)
     ( $t_first:tt $t_second:tt $( $t:tt )* ) => {
-        ::ast::Shape(vec![
-            ::ast::Atom(n("SEQ")),
+        raw_ast!(Shape(vec![
+            raw_ast!(Atom(n("SEQ"))),
             u!( $t_first ), u!( $t_second ), $( u!( $t ) ),*
-        ])
+        ]))
     };
 }
 
@@ -205,6 +205,15 @@ where I: Iterator<Item = &'a Ast> {
             }
             result
         }
+        // HACK: always pick the first branch, as `parse_flimsy_mbe` does,
+        //  but let a `Seq` there be spelled as part of the surrounding sequence:
+        Alt(ref subs)
+            if matches!(*subs[0], Seq(_))
+                && !matches!(flimsy_seq.peek().map(|f| f.c()),
+                             Some(Shape(ref f)) if f[0] == raw_ast!(Atom(n("SEQ")))) =>
+        {
+            parse_flimsy_seq(flimsy_seq, &subs[0])
+        }
         _ => {
             let flimsy = *match flimsy_seq.peek() {
                 None => return EnvMBE::new(), // Or is this an error?
@@ -215,10 +224,7 @@ where I: Iterator<Item = &'a Ast> {
                 Some(res) => {
                     // `Anyways`es shouldn't consume anything (and they'll always be `Named`):
                     let consuming = match grammar {
-                        Named(_, ref body) => match **body {
-                            Anyways(_) => false,
-                            _ => true,
-                        },
+                        Named(_, ref body) => !matches!(**body, Anyways(_)),
                         _ => true,
                     };
                     if consuming {
@@ -238,9 +244,9 @@ pub fn parse_flimsy_mbe(flimsy: &Ast, grammar: &FormPat) -> Option<EnvMBE<Ast>>
         Literal(_, _) => None,
         Call(_) => None,
         Scan(_) => None,
-        Seq(_) => match flimsy {
+        Seq(_) => match flimsy.c() {
             Shape(flimsy_parts) => {
-                if flimsy_parts[0] != Atom(n("SEQ")) {
+                if flimsy_parts[0] != raw_ast!(Atom(n("SEQ"))) {
                     panic!("Needed a SEQ, got {}", flimsy)
                 }
                 let mut fpi = flimsy_parts[1..].iter().peekable();
@@ -249,15 +255,15 @@ pub fn parse_flimsy_mbe(flimsy: &Ast, grammar: &FormPat) -> Option<EnvMBE<Ast>>
             }
             _ => panic!("Needed a SEQ shape, got {}", flimsy),
         },
-        Star(ref body) | Plus(ref body) => match flimsy {
+        Star(ref body) | Plus(ref body) => match flimsy.c() {
             Shape(flimsy_parts) => {
-                if flimsy_parts[0] != Atom(n("REP")) {
+                if flimsy_parts[0] != raw_ast!(Atom(n("REP"))) {
                     panic!("Need a REP, got {}", flimsy_parts[0])
                 }
 
                 let mut reps = vec![];
                 for flimsy_part in flimsy_parts[1..].iter() {
-                    reps.push(parse_flimsy_mbe(flimsy_part, &*body).unwrap());
+                    reps.push(parse_flimsy_mbe(flimsy_part, body).unwrap());
                 }
                 Some(EnvMBE::new_from_anon_repeat(reps))
             }
@@ -266,10 +272,10 @@ pub fn parse_flimsy_mbe(flimsy: &Ast, grammar: &FormPat) -> Option<EnvMBE<Ast>>
         Alt(ref subs) => {
             // HACK: always pick the first branch of the `Alt`
             // (mainly affects unquotation, where it skips the type annotation)
-            parse_flimsy_mbe(flimsy, &*subs[0])
+            parse_flimsy_mbe(flimsy, &subs[0])
         }
         Named(name, ref body) => Some(EnvMBE::new_from_leaves(
-            crate::util::assoc::Assoc::new().set(*name, parse_flimsy_ast(flimsy, &*body)),
+            crate::util::assoc::Assoc::new().set(*name, parse_flimsy_ast(flimsy, body)),
         )),
         SynImport(_, _, _) => panic!("`SynImport` can't work without a real parser"),
         NameImport(_, _) => panic!("`NameImport` should live underneath `Named`: {:?}", grammar),
@@ -284,23 +290,27 @@ fn parse_flimsy_ast(flimsy: &Ast, grammar: &FormPat) -> Ast {
         Anyways(ref a) => a.clone(),
         Impossible => unimplemented!(),
         Scan(_) => flimsy.clone(),
-        Literal(_, _) => Trivial,
-        VarRef(_) => match flimsy {
-            VariableReference(a) => VariableReference(*a),
-            non_atom => panic!("Needed an atom, got {}", non_atom),
+        Literal(_, _) => raw_ast!(Trivial),
+        VarRef(_) => match flimsy.c() {
+            VariableReference(a) => raw_ast!(VariableReference(*a)),
+            _ => panic!("Needed an atom, got {}", flimsy),
         },
         NameImport(body, beta) => {
-            ExtendEnv(Box::new(parse_flimsy_ast(flimsy, &*body)), beta.clone())
+            raw_ast!(ExtendEnv(Box::new(parse_flimsy_ast(flimsy, body)), beta.clone()))
+        }
+        QuoteDeepen(body, pos) => {
+            raw_ast!(QuoteMore(Box::new(parse_flimsy_ast(flimsy, body)), *pos))
+        }
+        QuoteEscape(body, depth) => {
+            raw_ast!(QuoteLess(Box::new(parse_flimsy_ast(flimsy, body)), *depth))
         }
-        QuoteDeepen(body, pos) => QuoteMore(Box::new(parse_flimsy_ast(flimsy, &*body)), *pos),
-        QuoteEscape(body, depth) => QuoteLess(Box::new(parse_flimsy_ast(flimsy, &*body)), *depth),
 
         Call(name) => {
             // HACK: don't descend into `Call(n("DefaultAtom"))
             if *name == n("DefaultAtom") {
-                match flimsy {
-                    VariableReference(a) => Atom(*a),
-                    non_atom => panic!("Needed an atom, got {}", non_atom),
+                match flimsy.c() {
+                    VariableReference(a) => raw_ast!(Atom(*a)),
+                    _ => panic!("Needed an atom, got {}", flimsy),
                 }
             } else {
                 flimsy.clone()
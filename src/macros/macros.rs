@@ -43,7 +43,7 @@ macro_rules! beta_connector {
     ($lhs:tt = $rhs:tt) => {
         crate::beta::SameAs(
             crate::name::n(expr_ify!($lhs)),
-            Box::new(crate::ast::VariableReference(crate::name::n(expr_ify!($rhs)))),
+            Box::new(raw_ast!(VariableReference(crate::name::n(expr_ify!($rhs))))),
         )
     };
     // TODO: this needs a better notation, somehow
@@ -148,22 +148,32 @@ macro_rules! t_elt {
 // Ast
 
 macro_rules! ast_shape {
-    ($($contents:tt)*) => { crate::ast::Shape(vec![ $(  ast!($contents) ),* ] )};
+    ($($contents:tt)*) => { raw_ast!(Shape(vec![ $(  ast!($contents) ),* ] ))};
+}
+
+/// Build an `Ast` with no location, e.g. `raw_ast!(Node(form, parts, export))`.
+macro_rules! raw_ast {
+    ($ctor:ident) => {
+        crate::ast::Ast::new(crate::ast::AstContents::$ctor)
+    };
+    ($ctor:ident ( $( $arg:expr ),* $(,)* ) ) => {
+        crate::ast::Ast::new(crate::ast::AstContents::$ctor( $( $arg ),* ))
+    };
 }
 
 macro_rules! ast {
-    ( (trivial) ) => { crate::ast::Trivial };
+    ( (trivial) ) => { raw_ast!(Trivial) };
     ( (++ $pos:tt $sub:tt) ) => {
-        crate::ast::QuoteMore(Box::new(ast!($sub)), $pos)
+        raw_ast!(QuoteMore(Box::new(ast!($sub)), $pos))
     };
     ( (-- $depth:tt $sub:tt ) ) => {
-        crate::ast::QuoteLess(Box::new(ast!($sub)), $depth)
+        raw_ast!(QuoteLess(Box::new(ast!($sub)), $depth))
     };
     ( (import $beta:tt $sub:tt) ) => {
-        crate::ast::ExtendEnv(Box::new(ast!($sub)), beta!($beta))
+        raw_ast!(ExtendEnv(Box::new(ast!($sub)), beta!($beta)))
     };
     ( (import_phaseless $beta:tt $sub:tt) ) => {
-        crate::ast::ExtendEnvPhaseless(Box::new(ast!($sub)), beta!($beta))
+        raw_ast!(ExtendEnvPhaseless(Box::new(ast!($sub)), beta!($beta)))
     };
     /* // not sure we'll need this
     ( (* $env:expr => $new_env:ident / $($n:expr),* ; $($sub_ar"gs:tt)*) ) => {
@@ -177,31 +187,31 @@ macro_rules! ast {
             Shape(res)
         }
     };*/
-    ( (vr $var:expr) ) => { crate::ast::VariableReference(crate::name::n($var)) };
+    ( (vr $var:expr) ) => { raw_ast!(VariableReference(crate::name::n($var))) };
     ( (, $interpolate:expr)) => { $interpolate };
     // TODO: maybe we should use commas for consistency:
     ( ( $( $list:tt )* ) ) => { ast_shape!($($list)*)};
     ( { - $($mbe_arg:tt)* } ) => {
-        crate::ast::IncompleteNode(mbe!( $($mbe_arg)* ))
+        raw_ast!(IncompleteNode(mbe!( $($mbe_arg)* )))
     };
     ( { $nt:tt $form:tt => $beta:tt : $($mbe_arg:tt)*} ) => {
-        crate::ast::Node(crate::core_forms::find($nt, $form), mbe!( $($mbe_arg)* ),
-                    ebeta!($beta))
+        raw_ast!(Node(crate::core_forms::find($nt, $form), mbe!( $($mbe_arg)* ),
+                    ebeta!($beta)))
     };
     ( { $form:expr => $beta:tt ; $($mbe_arg:tt)*} ) => {
-        crate::ast::Node($form, mbe!( $($mbe_arg)* ), ebeta!($beta))
+        raw_ast!(Node($form, mbe!( $($mbe_arg)* ), ebeta!($beta)))
     };
     ( { $form:expr; [ $($mbe_arg:tt)* ] }) => {
         ast!( { $form ; $($mbe_arg)* } )
     };
     ( { $form:expr; $($mbe_arg:tt)* }) => {
-        crate::ast::Node($form, mbe!( $($mbe_arg)* ), crate::beta::ExportBeta::Nothing)
+        raw_ast!(Node($form, mbe!( $($mbe_arg)* ), crate::beta::ExportBeta::Nothing))
     };
     ( { $nt:tt $form:tt : $($mbe_arg:tt)* }) => {
-        crate::ast::Node(crate::core_forms::find($nt, $form), mbe!( $($mbe_arg)* ),
-                    crate::beta::ExportBeta::Nothing)
+        raw_ast!(Node(crate::core_forms::find($nt, $form), mbe!( $($mbe_arg)* ),
+                    crate::beta::ExportBeta::Nothing))
     };
-    ($e:expr) => { crate::ast::Atom(crate::name::n($e))}
+    ($e:expr) => { raw_ast!(Atom(crate::name::n($e)))}
 }
 
 // Ty
@@ -222,7 +232,7 @@ macro_rules! ty_err_val {
 
 macro_rules! ty_err {
     ( $name:tt ( $($arg:expr),* ) at $loc:expr) => {
-        return Err(ty_err_val!( $name ( $($arg),* ) at $loc));
+        return Err(ty_err_val!( $name ( $($arg),* ) at $loc))
     }
 }
 
@@ -288,7 +298,7 @@ macro_rules! mbe_one_name {
             vec![ $( mbe_one_name!($k => $elt_pre) ),* ,
                   mbe_one_name!($k => $elt_rep) ,
                   $( mbe_one_name!($k => $elt_post) ),* ],
-              Some( vec![$( {$elt_pre; ()} ),*].len() ) // count the things in `$elt_pre`
+              Some( [$( stringify!($elt_pre) ),*].len() ) // count the things in `$elt_pre`
         )
     };
 
@@ -571,23 +581,37 @@ macro_rules! tyf {
 
 macro_rules! bind_patterns {
     ( $iter:expr; () => $body:expr ) => { $body };
-    ( $iter:expr; ($p_car:pat, $($p_cdr:pat,)* ) => $body:expr ) => {
-        match $iter.next() {
+    ( $iter:expr; ($p_car:pat, $($p_cdr:pat,)* ) => $body:expr ) => {{
+        // (A polymorphic function's patterns may be irrefutable.)
+        #[allow(unreachable_patterns)]
+        let res = match $iter.next() {
             Some($p_car) => {
                 bind_patterns!($iter; ($( $p_cdr, )*) => $body)
             }
-            None => { icp!("too few arguments"); }
-            Some(ref other) => { icp!("[type error] in argument: {:#?}", other); }
-        }
-    }
+            _ => icp!("arguments changed shape after being checked"),
+        };
+        res
+    }}
 }
 
 macro_rules! core_fn {
     ( $($p:pat),* => $body:expr ) => {
         BuiltInFunction(BIF(Rc::new(
-            move | args | {
+            move | args: Vec<Value> | {
+                let arity = <[&str]>::len(&[$( stringify!($p) ),*]);
+                if args.len() != arity {
+                    return Err(crate::runtime::eval::RuntimeError::new(
+                        crate::runtime::eval::RuntimeErr::ArgCountMismatch(arity, args)));
+                }
+                // Check the arguments without moving them, so that they can go in the error:
+                #[allow(unused_variables)]
+                let fits = matches!(&args[..], [$( $p ),*]);
+                if !fits {
+                    return Err(crate::runtime::eval::RuntimeError::new(
+                        crate::runtime::eval::RuntimeErr::BifArgMismatch(args)));
+                }
                 let mut argi = args.into_iter();
-                bind_patterns!(argi; ($( $p, )*) => $body )
+                bind_patterns!(argi; ($( $p, )*) => Ok($body) )
             }
         )))
     }
@@ -614,7 +638,7 @@ macro_rules! without_freshening {
 macro_rules! expect_node {
     ( ($node:expr ; $form:expr) $env:ident ; $body:expr ) => {
         // This is tied to the signature of `Custom`
-        if let Node(ref f, ref $env, _) = $node {
+        if let Node(ref f, ref $env, _) = $node.c() {
             if *f == $form {
                 $body
             } else {
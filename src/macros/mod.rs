@@ -1,5 +1,6 @@
 #![macro_use]
 
-pub mod flimsy_syntax;
+#[allow(clippy::module_inception)] // `macros::macros` is where the general-purpose ones live
 pub mod macros;
+pub mod flimsy_syntax;
 pub mod reification_macros;
@@ -96,7 +96,7 @@ macro_rules! Reifiable {
             fn ty() -> crate::ast::Ast {
                 type_defn_wrapper!($(<$($ty_param_ty),*>)* => { "Type" "struct" :
                    "component_name" => [@"c" $(
-                       (, crate::ast::Ast::Atom(crate::name::n(stringify!($field)))) ),* ],
+                       (, raw_ast!(Atom(crate::name::n(stringify!($field))))) ),* ],
                    "component" =>
                    // TODO: unless we specify arguments with the same name as parameters,
                    //  we get bogus results
@@ -134,6 +134,18 @@ macro_rules! Reifiable {
                                 env.find(&crate::name::n(stringify!($field))).unwrap())),*
                     })
             }
+
+            #[allow(unused_variables)]
+            fn try_reflect(v: &crate::runtime::eval::Value) -> Option<Self> {
+                match *v {
+                    crate::runtime::eval::Struct(ref env) => Some($name {
+                        $( $field :
+                            <$t as crate::runtime::reify::Reifiable>::try_reflect(
+                                env.find(&crate::name::n(stringify!($field)))?)?),*
+                    }),
+                    _ => None
+                }
+            }
         }
     };
     /* enum */
@@ -196,7 +208,7 @@ macro_rules! Reifiable {
             fn ty() -> crate::ast::Ast {
                 type_defn_wrapper!($(<$($ty_param_ty),*>)* => { "Type" "enum" :
                     "name" => [@"c" $(
-                        (, crate::ast::Ast::Atom(crate::name::n(stringify!($choice)))) ),* ],
+                        (, raw_ast!(Atom(crate::name::n(stringify!($choice))))) ),* ],
                     "component" => [@"c" $( [ $($(
                         (, <$part as crate::runtime::reify::Reifiable>::ty_invocation() )
                     ),*)*]),*]
@@ -236,6 +248,17 @@ macro_rules! Reifiable {
                         { $($choice $(( $($part),* ))*),* } )
                 })
             }
+
+            #[allow(unused_variables)]
+            fn try_reflect(v: &crate::runtime::eval::Value) -> Option<Self> {
+                match *v {
+                    crate::runtime::eval::Enum(ref choice, ref parts) => {
+                        make_enum_try_reflect!(choice; parts; $name$(<$($ty_param),*>)*/**/
+                            { $($choice $(( $($part),* ))*),* } )
+                    }
+                    _ => None
+                }
+            }
         }
     }
 }
@@ -286,6 +309,39 @@ macro_rules! make_enum_reflect {
     }
 }
 
+// Like `make_enum_reflect`, but `None` if anything is the wrong shape
+macro_rules! make_enum_try_reflect {
+    ($choice_name:ident; $parts_name:ident; $name:ident$(<$($ty_param:tt),*>)*/**/ {
+        $choice_car:ident $(( $($part_cars:ty),* ))*
+        $(, $choice_cdr:ident$(( $($part_cdr:ty),* ))*)*
+    }) => {
+        if $choice_name.is(stringify!($choice_car)) {
+            try_unpack_parts!( $(( $($part_cars),* ))* $parts_name; 0;
+                               $name::$choice_car$(::< $($ty_param),* >)*; ())
+        } else {
+            make_enum_try_reflect!($choice_name; $parts_name; $name$(<$($ty_param),*>)*/**/ {
+                $($choice_cdr $(( $($part_cdr),* ))* ),* })
+        }
+    };
+    ($choice_name:ident; $parts_name:ident; $name:ident$(<$($ty_param:tt),*>)*/**/ { } ) => {
+        None
+    }
+}
+
+macro_rules! try_unpack_parts {
+    ( ($t_car:ty $(, $t_cdr:ty)*) $v:expr; $idx:expr; $ctor:expr; ($($accum:expr),*)) => {
+        try_unpack_parts!( ( $($t_cdr),* ) $v; ($idx + 1); $ctor;
+            ($($accum, )*
+             <$t_car as crate::runtime::reify::Reifiable>::try_reflect($v.get($idx)?)?))
+    };
+    ( () $v:expr; $idx:expr; $ctor:expr; ($($accum:expr),*)) => {
+        Some($ctor($($accum),*))
+    };
+    ( $v:expr; $idx:expr; $ctor:expr; ()) => {
+        Some($ctor) // special case: a value, not a 0-arg constructor
+    }
+}
+
 macro_rules! unpack_parts {
     ( ($t_car:ty $(, $t_cdr:ty)*) $v:expr; $idx:expr; $ctor:expr; ($($accum:expr),*)) => {
         unpack_parts!( ( $($t_cdr),* ) $v; ($idx + 1); $ctor;
@@ -319,11 +375,11 @@ macro_rules! type_defn_wrapper {
         // All types will be μ. I think this is the way things work in most languages.
         ast!({"Type" "forall_type" :
             "param" => [ $($(
-                (, crate::ast::Ast::Atom(crate::name::n(stringify!($ty_param_ty))))
+                (, raw_ast!(Atom(crate::name::n(stringify!($ty_param_ty)))))
             ),*)*],
             "body" => (import [* [forall "param"]] {"Type" "mu_type" :
                  "param" => [(import [prot "param"]
-                              (, crate::ast::Ast::VariableReference(Self::ty_name())))],
+                              (, raw_ast!(VariableReference(Self::ty_name()))))],
                  "body" => (import [* [prot "param"]] $body)
              })
         })
@@ -333,11 +389,11 @@ macro_rules! type_defn_wrapper {
 macro_rules! refer_to_type {
     ($name:tt < $( $arg:ty ),* >) => {
         ast!({ "Type" "type_apply" :
-            "type_rator" => (, ::ast::Ast::VariableReference(::name::n(stringify!($name))) ),
+            "type_rator" => (, raw_ast!(VariableReference(::name::n(stringify!($name)))) ),
             "arg" => [ (, $( refer_to_type!($arg)),* )]
         })
     };
     ($name:tt) => {
-        ::ast::Ast::VariableReference(::name::n(stringify!($name)))
+        raw_ast!(VariableReference(::name::n(stringify!($name))))
     }
 }
@@ -1,72 +1,15 @@
 // Unseemly is a "core" typed language with (typed!) macros.
 // You shouldn't write code in Unseemly.
 // Instead, you should implement your programming language as Unseemly macros.
+// This is the REPL (and file runner); the language itself is in `lib.rs`.
 
-#![allow(dead_code, unused_macros, non_snake_case, unused_imports, non_upper_case_globals)]
-// dead_code and unused_macros are hopefully temporary allowances
-// non_snake_case is stylistic, unused_imports is inaccurate for `cargo check`
-// non_upper_case_globals is stylistic; I like my thread_local!s lowercase.
-#![recursion_limit = "128"] // Yikes.
+#![allow(non_upper_case_globals)] // I like my thread_local!s lowercase.
 
-// for testing; requires `cargo +nightly`
-// #![feature(log_syntax, trace_macros)]
-// trace_macros!(true);
-
-// TODO: turn these into `use` statements in the appropriate places
-#[macro_use]
-extern crate lazy_static;
-#[macro_use]
-extern crate custom_derive;
-#[macro_use]
-extern crate quote;
-
-use std::{fs::File, io::Read, path::Path};
-
-mod macros;
-
-mod name; // should maybe be moved to `util`; `mbe` needs it
-
-mod util;
-
-mod alpha;
-mod ast;
-mod beta;
-mod read;
-
-mod earley;
-mod grammar;
-mod unparse;
-
-mod form;
-
-mod ast_walk;
-mod expand;
-mod ty;
-mod ty_compare;
-mod walk_mode;
-
-mod runtime;
-
-mod core_forms;
-mod core_macro_forms;
-mod core_qq_forms;
-mod core_type_forms;
-
-use crate::{
-    ast::Ast,
-    name::{n, Name},
-    runtime::{
-        core_values,
-        eval::{eval, Value},
-    },
-    ty::Ty,
-    util::assoc::Assoc,
-};
-use std::{borrow::Cow, cell::RefCell, io::BufRead};
+use std::{borrow::Cow, cell::RefCell, path::Path};
+use unseemly::{Ast, Diagnose, Engine, Ty, Value};
 
 thread_local! {
-    pub static ty_env : RefCell<Assoc<Name, Ty>> = RefCell::new(core_values::core_types());
-    pub static val_env : RefCell<Assoc<Name, Value>> = RefCell::new(core_values::core_values());
+    pub static session : RefCell<Engine> = RefCell::new(Engine::new());
 }
 
 struct LineHelper {
@@ -91,9 +34,8 @@ impl rustyline::completion::Completer for LineHelper {
     {
         let mut res = vec![];
         let (start, word_so_far) = rustyline::completion::extract_word(line, pos, None, b"[({ })]");
-        val_env.with(|vals| {
-            let vals = vals.borrow();
-            for k in vals.iter_keys() {
+        session.with(|s| {
+            for k in s.borrow().value_names() {
                 if k.sp().starts_with(word_so_far) {
                     res.push(k.sp());
                 }
@@ -132,6 +74,101 @@ impl rustyline::highlight::Highlighter for LineHelper {
 
 impl rustyline::Helper for LineHelper {}
 
+/// Has `input` closed every delimiter it opened? (If it has closed too many, that's an error
+///  for the parser to report, not a reason to wait for more input.)
+/// `.[ ].`, `'[ ]'`, `*[ ]*`, `+[ ]+`, etc. are all just brackets with decoration,
+///  but `extend_syntax` and `let_type` are closed by `in`.
+/// String literals, regexes, `'`-quoted literals, and comments don't count.
+/// (Regexes only appear in grammars: right after `::=`, or inside `'{ }'`.)
+fn input_is_complete(input: &str) -> bool {
+    let mut brackets = 0;
+    let mut keywords = 0;
+    let mut chars = input.chars().peekable();
+    // For each open bracket, whether its contents are grammar:
+    let mut in_grammar = vec![false];
+    // Whether the next bracket opens a syntax quotation (`'{`) or something else (`'[`, `,{`):
+    let mut opening_grammar = None;
+    let mut after_rule_def = false;
+
+    // Skip to (and past) the next unescaped `close`:
+    fn skip_past(chars: &mut std::iter::Peekable<std::str::Chars>, close: char) {
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                chars.next();
+            } else if c == close {
+                return;
+            }
+        }
+    }
+
+    while let Some(c) = chars.next() {
+        let grammar_here = after_rule_def || in_grammar.last() == Some(&true);
+        let was_after_rule_def = after_rule_def;
+        if !c.is_whitespace() {
+            after_rule_def = false;
+        }
+        match c {
+            '(' | '[' | '{' => {
+                brackets += 1;
+                in_grammar.push(opening_grammar.take().unwrap_or(grammar_here));
+            }
+            ')' | ']' | '}' => {
+                brackets -= 1;
+                in_grammar.pop();
+                // The `'` in `]'` isn't the start of a literal:
+                if chars.peek() == Some(&'\'') {
+                    chars.next();
+                }
+            }
+            '\'' if chars.peek() == Some(&'[') => opening_grammar = Some(false),
+            '\'' if chars.peek() == Some(&'{') => opening_grammar = Some(true),
+            ',' if chars.peek() == Some(&'[') || chars.peek() == Some(&'{') => {
+                opening_grammar = Some(false)
+            }
+            '\'' | '"' => skip_past(&mut chars, c),
+            // Regexes have no escapes (they can't contain `/`):
+            '/' if grammar_here => {
+                let _ = chars.find(|&c| c == '/');
+            }
+            ':' if chars.clone().take(2).eq(":=".chars()) => {
+                chars.next();
+                chars.next();
+                after_rule_def = true;
+            }
+            '#' if chars.peek() == Some(&'|') => {
+                while let Some(c) = chars.next() {
+                    if c == '|' && chars.peek() == Some(&'#') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            '#' => skip_past(&mut chars, '\n'),
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !c.is_alphanumeric() && c != '_' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                match word.as_str() {
+                    "extend_syntax" | "let_type" => keywords += 1,
+                    "in" => keywords -= 1,
+                    "also" => after_rule_def = was_after_rule_def,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        if brackets < 0 || keywords < 0 {
+            return true;
+        }
+    }
+    brackets == 0 && keywords == 0
+}
+
 #[cfg_attr(tarpaulin, skip)]
 fn main() {
     let arguments: Vec<String> = std::env::args().collect();
@@ -142,18 +179,22 @@ fn main() {
         let mut rl = rustyline::Editor::<LineHelper>::new();
         rl.set_helper(Some(LineHelper::new()));
 
-        let just_parse = regex::Regex::new("^:p (.*)$").unwrap();
-        let just_parse_debug_print = regex::Regex::new("^:pd (.*)$").unwrap();
-
-        let just_type = regex::Regex::new("^:t (.*)$").unwrap();
-        let just_eval = regex::Regex::new("^:e (.*)$").unwrap();
-        let type_and_expand = regex::Regex::new("^:x (.*)$").unwrap();
-        let canon_type = regex::Regex::new("^:tt (.*)$").unwrap();
-        let assign_value = regex::Regex::new("^(\\w+)\\s*:=(.*)$").unwrap();
-        let save_value = regex::Regex::new("^:s +((\\w+)\\s*:=(.*))$").unwrap();
-        let assign_type = regex::Regex::new("^(\\w+)\\s*t=(.*)$").unwrap();
-        let save_type = regex::Regex::new("^:s +((\\w+)\\s*t=(.*))$").unwrap();
-        let comment = regex::Regex::new("^#").unwrap();
+        // `(?s)`, so that `.` matches the newlines in multi-line input.
+        let just_parse = regex::Regex::new("(?s)^:p (.*)$").unwrap();
+        let just_parse_debug_print = regex::Regex::new("(?s)^:pd (.*)$").unwrap();
+
+        let just_type = regex::Regex::new("(?s)^:t (.*)$").unwrap();
+        let just_eval = regex::Regex::new("(?s)^:e (.*)$").unwrap();
+        let type_and_expand = regex::Regex::new("(?s)^:x (.*)$").unwrap();
+        let canon_type = regex::Regex::new("(?s)^:tt (.*)$").unwrap();
+        let assign_value = regex::Regex::new("(?s)^(\\w+)\\s*:=(.*)$").unwrap();
+        let save_value = regex::Regex::new("(?s)^:s +((\\w+)\\s*:=(.*))$").unwrap();
+        let assign_type = regex::Regex::new("(?s)^(\\w+)\\s*t=(.*)$").unwrap();
+        let save_type = regex::Regex::new("(?s)^:s +((\\w+)\\s*t=(.*))$").unwrap();
+        let load = regex::Regex::new("(?s)^:load +(.*)$").unwrap();
+        let reload = regex::Regex::new("^:reload\\s*$").unwrap();
+        let extend_syntax = regex::Regex::new("(?s)^:syntax +(.*)$").unwrap();
+        let save_syntax = regex::Regex::new("(?s)^:s +:syntax +(.*)$").unwrap();
 
         println!();
         println!("                  \x1b[1;38mUnseemly\x1b[0m");
@@ -168,32 +209,38 @@ fn main() {
         println!("    `:s <name> t= <expr>` to save a type binding to the prelude.");
         println!("    `:p <expr>` to parse `<expr>` and pretty-print its AST output.");
         println!("    `:pd <expr>` to parse `<expr>` and debug-print its AST output.");
+        println!("    `:syntax <nt> ::=also <syntax> ;` to extend the grammar for this session.");
+        println!("    `:s :syntax <extension>` to save a syntax extension to the prelude.");
+        println!("    `:load <file>` to bind the definitions in <file> for this session.");
+        println!("    `:reload` to load all of the `:load`ed files again.");
+        println!("    Input continues onto the next line until all delimiters are closed.");
         println!("    Command history is saved over sessions.");
         println!("    Tab-completion works on variables, and lots of Bash-isms work.");
         println!();
 
-        if let Ok(prelude_file) = File::open(&Path::new(&prelude_filename)) {
-            let prelude = std::io::BufReader::new(prelude_file);
-            for line in prelude.lines() {
-                let line = line.unwrap();
-                if comment.captures(&line).is_some() {
-                    // comment
-                } else if let Some(caps) = assign_value.captures(&line) {
-                    if let Err(e) = assign_variable(&caps[1], &caps[2]) {
-                        println!("    Error in prelude line: {}\n    {}", line, e);
-                    }
-                } else if let Some(caps) = assign_type.captures(&line) {
-                    if let Err(e) = assign_t_var(&caps[1], &caps[2]) {
-                        println!("    Error in prelude line: {}\n    {}", line, e);
-                    }
-                }
+        if Path::new(&prelude_filename).is_file() {
+            match load_file(&prelude_filename) {
+                Ok(_) => println!("    [prelude loaded from {}]", prelude_filename),
+                Err(e) => println!("    Error in prelude:\n{}", e),
             }
-            println!("    [prelude loaded from {}]", prelude_filename);
         }
 
+        // Files `:load`ed this session, for `:reload`:
+        let mut loaded_files: Vec<String> = vec![];
+
         let _ = rl.load_history(&history_filename);
-        while let Ok(line) = rl.readline("\x1b[1;36m≫\x1b[0m ") {
-            // TODO: count delimiters, and allow line continuation!
+        'repl: while let Ok(mut line) = rl.readline("\x1b[1;36m≫\x1b[0m ") {
+            while !input_is_complete(&line) {
+                match rl.readline("\x1b[1;36m…\x1b[0m ") {
+                    Ok(more) => {
+                        line.push('\n');
+                        line.push_str(&more);
+                    }
+                    // Ctrl-C abandons the whole block:
+                    Err(rustyline::error::ReadlineError::Interrupted) => continue 'repl,
+                    Err(_) => break 'repl,
+                }
+            }
             rl.add_history_entry(line.clone());
 
             let result_display = if let Some(caps) = just_parse.captures(&line) {
@@ -208,6 +255,37 @@ fn main() {
                 type_and_expand_unseemly_program(&caps[1]).map(|x| format!("{}", x))
             } else if let Some(caps) = canon_type.captures(&line) {
                 canonicalize_type(&caps[1]).map(|x| format!("{}", x))
+            } else if let Some(caps) = extend_syntax.captures(&line) {
+                extend_session_syntax(&caps[1]).map(|()| "[syntax extended]".to_owned())
+            } else if let Some(caps) = save_syntax.captures(&line) {
+                match extend_session_syntax(&caps[1]) {
+                    Ok(_) => {
+                        use std::io::Write;
+                        let mut prel_file = std::fs::OpenOptions::new()
+                            .create(true)
+                            .append(true)
+                            .open(&prelude_filename)
+                            .unwrap();
+                        writeln!(prel_file, "extend_syntax {}", &caps[1]).unwrap();
+                        Ok(format!("[saved to {}]", &prelude_filename))
+                    }
+                    Err(e) => Err(e),
+                }
+            } else if let Some(caps) = load.captures(&line) {
+                let filename = caps[1].trim().to_owned();
+                let res = load_file(&filename);
+                if Path::new(&filename).is_file() && !loaded_files.contains(&filename) {
+                    loaded_files.push(filename);
+                }
+                res
+            } else if reload.is_match(&line) {
+                if loaded_files.is_empty() {
+                    Err("no files have been `:load`ed yet".to_owned())
+                } else {
+                    let results: Result<Vec<String>, String> =
+                        loaded_files.iter().map(|f| load_file(f)).collect();
+                    results.map(|msgs| msgs.join("\n"))
+                }
             } else if let Some(caps) = assign_value.captures(&line) {
                 assign_variable(&caps[1], &caps[2]).map(|x| format!("{}", x))
             } else if let Some(caps) = save_value.captures(&line) {
@@ -244,6 +322,7 @@ fn main() {
                 eval_unseemly_program(&line).map(|x| format!("{}", x))
             };
 
+            print_warnings();
             match result_display {
                 Ok(v) => println!("\x1b[1;32m≉\x1b[0m {}", v),
                 Err(s) => println!("\x1b[1;31m✘\x1b[0m {}", s),
@@ -253,80 +332,50 @@ fn main() {
     } else {
         let filename = &arguments[1];
 
-        let mut raw_input = String::new();
-        File::open(&Path::new(filename))
-            .expect("Error opening file")
-            .read_to_string(&mut raw_input)
-            .expect("Error reading file");
-
-        let result = eval_unseemly_program(&raw_input);
+        let result = session.with(|s| s.borrow_mut().run_file(Path::new(filename)));
 
+        print_warnings();
         match result {
-            Ok(v) => println!("{}", v),
-            Err(e) => println!("\x1b[1;31m✘\x1b[0m {:#?}", e),
+            Ok(Some(v)) => println!("{}", v),
+            Ok(None) => {}
+            Err(e) => println!("\x1b[1;31m✘\x1b[0m {}", e.render()),
         }
     }
 }
 
-fn assign_variable(name: &str, expr: &str) -> Result<Value, String> {
-    let res = eval_unseemly_program(expr);
-
-    if let Ok(ref v) = res {
-        let ty = type_unseemly_program(expr).unwrap();
-        ty_env.with(|tys| {
-            val_env.with(|vals| {
-                let new_tys = tys.borrow().set(n(name), ty).clone();
-                let new_vals = vals.borrow().set(n(name), v.clone());
-                *tys.borrow_mut() = new_tys;
-                *vals.borrow_mut() = new_vals;
-            })
-        })
+fn print_warnings() {
+    for warning in session.with(|s| s.borrow().take_warnings()) {
+        println!("{}", Diagnose::render(&warning));
     }
-    res
 }
 
-fn assign_t_var(name: &str, t: &str) -> Result<ty::Ty, String> {
-    let ast = grammar::parse(
-        &grammar::FormPat::Call(n("Type")),
-        &core_forms::get_core_forms(),
-        runtime::core_values::get_core_envs(),
-        t,
-    )
-    .map_err(|e| e.msg)?;
-
-    let res = ty_env
-        .with(|tys| ty::synth_type(&ast, tys.borrow().clone()).map_err(|e| format!("{:#?}", e)));
-
-    if let Ok(ref t) = res {
-        ty_env.with(|tys| {
-            let new_tys = tys.borrow().set(n(name), t.clone());
-            *tys.borrow_mut() = new_tys;
-        })
+/// Run the items in a file (see `program.rs`) in this session.
+fn load_file(filename: &str) -> Result<String, String> {
+    match session.with(|s| s.borrow_mut().run_file(Path::new(filename))).map_err(|e| e.render())? {
+        Some(v) => Ok(format!("{}", v)),
+        None => Ok(format!("[loaded {}]", filename)),
     }
+}
 
-    res
+fn assign_variable(name: &str, expr: &str) -> Result<Value, String> {
+    session.with(|s| s.borrow_mut().define(name, expr)).map_err(|e| e.render())
 }
 
-fn canonicalize_type(t: &str) -> Result<ty::Ty, String> {
-    let ast = grammar::parse(
-        &grammar::FormPat::Call(n("Type")),
-        &core_forms::get_core_forms(),
-        runtime::core_values::get_core_envs(),
-        t,
-    )
-    .map_err(|e| e.msg)?;
+fn assign_t_var(name: &str, t: &str) -> Result<Ty, String> {
+    session.with(|s| s.borrow_mut().define_type(name, t)).map_err(|e| e.render())
+}
 
-    ty_env.with(|tys| ty::synth_type(&ast, tys.borrow().clone()).map_err(|e| format!("{:#?}", e)))
+/// Extend the session's grammar with `clauses` (the part of an `extend_syntax` before `in`).
+fn extend_session_syntax(clauses: &str) -> Result<(), String> {
+    session.with(|s| s.borrow_mut().extend_syntax(clauses)).map_err(|e| e.render())
+}
+
+fn canonicalize_type(t: &str) -> Result<Ty, String> {
+    session.with(|s| s.borrow().canonicalize_type(t)).map_err(|e| e.render())
 }
 
 fn parse_unseemly_program(program: &str, pretty: bool) -> Result<String, String> {
-    let ast = grammar::parse(
-        &core_forms::outermost_form(),
-        &core_forms::get_core_forms(),
-        runtime::core_values::get_core_envs(),
-        program,
-    )
-    .map_err(|e| e.msg)?;
+    let ast = session.with(|s| s.borrow().parse(program)).map_err(|e| e.render())?;
 
     if pretty {
         Ok(format!("{}", ast))
@@ -335,464 +384,128 @@ fn parse_unseemly_program(program: &str, pretty: bool) -> Result<String, String>
     }
 }
 
-fn type_unseemly_program(program: &str) -> Result<ty::Ty, String> {
-    let ast = grammar::parse(
-        &core_forms::outermost_form(),
-        &core_forms::get_core_forms(),
-        runtime::core_values::get_core_envs(),
-        program,
-    )
-    .map_err(|e| e.msg)?;
-
-    ty_env.with(|tys| ty::synth_type(&ast, tys.borrow().clone()).map_err(|e| format!("{:#?}", e)))
+fn type_unseemly_program(program: &str) -> Result<Ty, String> {
+    session.with(|s| s.borrow().typecheck(program)).map_err(|e| e.render())
 }
 
 fn eval_unseemly_program_without_typechecking(program: &str) -> Result<Value, String> {
-    let ast: Ast = grammar::parse(
-        &core_forms::outermost_form(),
-        &core_forms::get_core_forms(),
-        runtime::core_values::get_core_envs(),
-        program,
-    )
-    .map_err(|e| e.msg)?;
-
-    let core_ast = crate::expand::expand(&ast).map_err(|e| format!("{:#?}", e))?;
-
-    val_env.with(|vals| eval(&core_ast, vals.borrow().clone()).map_err(|_| "???".to_string()))
+    session.with(|s| s.borrow().eval_without_typechecking(program)).map_err(|e| e.render())
 }
 
 fn eval_unseemly_program(program: &str) -> Result<Value, String> {
-    let ast: Ast = grammar::parse(
-        &core_forms::outermost_form(),
-        &core_forms::get_core_forms(),
-        runtime::core_values::get_core_envs(),
-        program,
-    )
-    .map_err(|e| e.msg)?;
-
-    let _type = ty_env
-        .with(|tys| ty::synth_type(&ast, tys.borrow().clone()).map_err(|e| format!("{:#?}", e)))?;
-
-    let core_ast = crate::expand::expand(&ast).map_err(|e| format!("{:#?}", e))?;
-
-    val_env.with(|vals| eval(&core_ast, vals.borrow().clone()).map_err(|_| "???".to_string()))
+    session.with(|s| s.borrow().eval(program)).map_err(|e| e.render())
 }
 
-fn type_and_expand_unseemly_program(program: &str) -> Result<ast::Ast, String> {
-    let ast: Ast = grammar::parse(
-        &core_forms::outermost_form(),
-        &core_forms::get_core_forms(),
-        runtime::core_values::get_core_envs(),
-        program,
-    )
-    .map_err(|e| e.msg)?;
-
-    let _type = ty_env
-        .with(|tys| ty::synth_type(&ast, tys.borrow().clone()).map_err(|e| format!("{:#?}", e)))?;
-
-    crate::expand::expand(&ast).map_err(|e| format!("{:#?}", e))
-}
-
-#[test]
-fn simple_end_to_end_eval() {
-    assert_eq!(eval_unseemly_program("(zero? zero)"), Ok(val!(b true)));
-
-    assert_eq!(eval_unseemly_program("(plus one one)"), Ok(val!(i 2)));
-
-    assert_eq!(eval_unseemly_program("(.[x : Int  y : Int . (plus x y)]. one one)"), Ok(val!(i 2)));
-
-    assert_eq!(
-        eval_unseemly_program(
-            "((fix .[ again : [ -> [ Int -> Int ]] .
-            .[ n : Int .
-                match (zero? n) {
-                    +[True]+ => one
-                    +[False]+ => (times n ((again) (minus n one))) } ]. ].) five)"
-        ),
-        Ok(val!(i 120))
-    );
+fn type_and_expand_unseemly_program(program: &str) -> Result<Ast, String> {
+    session.with(|s| s.borrow().expand(program)).map_err(|e| e.render())
 }
 
 #[test]
-fn end_to_end_int_list_tools() {
-    assert_m!(
-        assign_t_var("IntList", "mu_type IntList . enum { Nil () Cons (Int IntList) }"),
-        Ok(_)
-    );
-
-    assert_m!(assign_t_var("IntListUF", "enum { Nil () Cons (Int IntList) }"), Ok(_));
-
-    assert_m!(
-        assign_variable("mt_ilist", "fold +[Nil]+ : enum { Nil () Cons (Int IntList) } : IntList"),
-        Ok(_)
-    );
-
-    assert_m!(
-        assign_variable("ilist_3", "fold +[Cons three mt_ilist]+ : IntListUF : IntList"),
-        Ok(_)
-    );
-
-    assert_m!(
-        assign_variable("ilist_23", "fold +[Cons two ilist_3]+ : IntListUF : IntList"),
-        Ok(_)
-    );
-
-    assert_m!(
-        assign_variable("ilist_123", "fold +[Cons one ilist_23]+ : IntListUF : IntList"),
-        Ok(_)
-    );
-
-    assert_m!(
-        assign_variable(
-            "sum_int_list",
-            "(fix .[again : [-> [IntList -> Int]] .
-             .[ lst : IntList .
-                 match unfold lst {
-                     +[Nil]+ => zero +[Cons hd tl]+ => (plus hd ((again) tl))} ]. ]. )"
-        ),
-        Ok(_)
-    );
-
-    assert_eq!(eval_unseemly_program("(sum_int_list ilist_123)"), Ok(val!(i 6)));
-
-    assert_m!(
-        assign_variable(
-            "int_list_len",
-            "(fix .[again : [-> [IntList -> Int]] .
-             .[ lst : IntList .
-                 match unfold lst {
-                     +[Nil]+ => zero +[Cons hd tl]+ => (plus one ((again) tl))} ]. ].)"
-        ),
-        Ok(_)
-    );
-
-    assert_eq!(eval_unseemly_program("(int_list_len ilist_123)"), Ok(val!(i 3)));
+fn repl_input_completeness() {
+    assert!(input_is_complete("(plus one two)"));
+    assert!(!input_is_complete("(plus one"));
+    assert!(!input_is_complete(".[x : Int .\n  (plus x one)"));
+    assert!(input_is_complete(".[x : Int .\n  (plus x one)]."));
+    assert!(!input_is_complete("match x { +[Cons a b]+ => '[Expr | ,[a], ]'"));
+    assert!(input_is_complete("match x { +[Cons a b]+ => '[Expr | ,[a], ]' }"));
+    assert!(!input_is_complete("*[x : one"));
+    assert!(!input_is_complete("(plus one\n  \"not a paren: )\""));
+    assert!(!input_is_complete("(plus one #| ) |#"));
+    assert!(!input_is_complete("(plus one # )\n"));
+    // Too many closers is an error to report now, not to wait out:
+    assert!(input_is_complete("(plus one two))"));
+
+    // Keywords that take an `in`:
+    assert!(!input_is_complete("let_type pair = Int"));
+    assert!(input_is_complete("let_type pair = Int in five"));
+    let extension = "extend_syntax\n    DefaultSeparator ::= /((?s:\\s|%|#[^\\n|][^\\n]*)*)/ ;";
+    assert!(!input_is_complete(extension));
+    assert!(input_is_complete(&format!("{}\nin one", extension)));
+    assert!(!input_is_complete("extend_syntax Expr ::=also '{ [ lit ,{ DefaultToken }, = 'if'"));
+    assert!(input_is_complete("extend_syntax Expr ::=also '{ [ lit ,{ X }, = '(' ] }' ; in"));
+
+    // A `/` only starts a regex in a grammar:
+    assert!(input_is_complete("(a / b)"));
+    assert!(!input_is_complete("(a / b"));
+    assert!(input_is_complete("extend_syntax Expr ::= /(\\S)/ ; in one"));
+    assert!(input_is_complete("extend_syntax Expr ::=also '{ [ /([)/ ] }' ; in (a / b)"));
+    assert!(!input_is_complete("extend_syntax Expr ::=also '{ [ /([)/ ] }' ; in (a / b"));
 }
 
 #[test]
-fn end_to_end_list_tools() {
-    assert_m!(
-        assign_t_var("List", "forall T . mu_type List . enum { Nil () Cons (T List<T>) }"),
-        Ok(_)
-    );
-
-    assert_m!(assign_t_var("ListUF", "forall T . enum { Nil () Cons (T List<T>) }"), Ok(_));
-
-    assert_m!(
-        assign_variable(
-            "mt_list",
-            "fold +[Nil]+ : enum { Nil () Cons (Int List < Int > ) } : List < Int > "
-        ),
-        Ok(_)
-    );
-
-    assert_m!(
-        assign_variable("list_3", "fold +[Cons three mt_list]+ : ListUF<Int> : List<Int>"),
-        Ok(_)
-    );
-
-    assert_m!(
-        assign_variable("list_23", "fold +[Cons two list_3]+ : ListUF<Int> : List<Int>"),
-        Ok(_)
-    );
-
-    assert_m!(
-        assign_variable("list_123", "fold +[Cons one list_23]+ : ListUF<Int> : List<Int>"),
-        Ok(_)
-    );
-
-    assert_m!(
-        assign_variable(
-            "list_len",
-            "forall S . (fix .[again : [-> [List<S> -> Int]] .
-            .[ lst : List<S> .
-                match unfold lst {
-                    +[Nil]+ => zero
-                    +[Cons hd tl]+ => (plus one ((again) tl))} ]. ].)"
-        ),
-        Ok(_)
+fn load_files_into_session() {
+    let path = std::env::temp_dir().join(format!("load_test_{}.≉", std::process::id()));
+    let filename = path.to_str().unwrap();
+    std::fs::write(&path, "# A comment, then a blank line
+
+double := .[ x : Int .
+    (plus x x) ].
+IntToInt t= [Int -> Int]
+twice := .[ f : IntToInt  x : Int .
+    (f (f x)) ].
+").unwrap();
+    assert_eq!(load_file(filename), Ok(format!("[loaded {}]", filename)));
+    assert_eq!(
+        eval_unseemly_program("(twice double five)").map(|v| v.to_string()),
+        Ok("20".to_owned())
     );
 
-    assert_eq!(eval_unseemly_program("(list_len list_123)"), Ok(val!(i 3)));
-
-    assert_m!(
-        assign_variable(
-            "map",
-            "forall T S . (fix  .[again : [-> [List<T>  [T -> S] -> List<S> ]] .
-            .[ lst : List<T>   f : [T -> S] .
-                match unfold lst {
-                    +[Nil]+ => fold +[Nil]+ : ListUF<S> : List<S>
-                    +[Cons hd tl]+ =>
-                      fold +[Cons (f hd) ((again) tl f)]+ : ListUF<S> : List<S> } ]. ].)"
-        ),
-        Ok(_)
-    );
-    // TODO: what should even happen if you have `forall` not on the "outside"?
-    // It should probably be an error to have a value typed with an underdetermined type.
+    // A file can end with an expression; reloading picks up changes:
+    std::fs::write(&path, "double := .[ x : Int . (times x two) ].\n(double five)").unwrap();
+    assert_eq!(load_file(filename), Ok("10".to_owned()));
 
-    // TODO: it's way too much of a pain to define each different expected result list.
-    assert_m!(eval_unseemly_program("(map list_123 .[x : Int . (plus x one)]. )"), Ok(_));
+    std::fs::write(&path, "oops := (plus one true)").unwrap();
+    let err = load_file(filename).unwrap_err();
+    assert!(err.contains("Mismatch"), "{}", err);
+    assert!(err.contains(&format!("{}:1:19", filename)), "{}", err);
+    std::fs::remove_file(&path).unwrap();
 
-    assert_m!(eval_unseemly_program("(map list_123 .[x : Int . (equal? x two)]. )"), Ok(_));
+    assert!(load_file("/nonexistent.≉").unwrap_err().contains("couldn't read"));
 }
 
 #[test]
-fn end_to_end_quotation_basic() {
-    assert_m!(eval_unseemly_program("'[Expr | .[ x : Int . x ]. ]'"), Ok(_));
-
-    assert_m!(eval_unseemly_program("'[Expr | (plus five five) ]'"), Ok(_));
-
-    assert_m!(eval_unseemly_program("'[Expr | '[Expr | (plus five five) ]' ]'"), Ok(_));
-
-    //≫ .[s : Expr<Int> . '[Expr | ( ,[Expr | s], '[Expr | ,[Expr | s], ]')]' ].
-}
-#[test]
-fn subtyping_direction() {
-    // Let's check to make sure that "supertype" and "subtype" never got mixed up:
-
-    assert_m!(assign_variable("ident", "forall T . .[ a : T . a ]."), Ok(_));
-
-    assert_eq!(eval_unseemly_program("(ident five)"), Ok(val!(i 5)));
-
-    assert_m!(eval_unseemly_program("( .[ a : [Int -> Int] . a]. ident)"), Ok(_));
-
-    assert_m!(eval_unseemly_program("( .[ a : forall T . [T -> T] . a]. .[a : Int . a].)"), Err(_));
-
-    assert_m!(eval_unseemly_program(".[ a : struct {} . a]."), Ok(_));
-
-    assert_m!(
-        eval_unseemly_program("( .[ a : struct {normal : Int extra : Int} . a]. *[normal : one]*)"),
-        Err(_)
-    );
-
-    assert_m!(
-        eval_unseemly_program(
-            "( .[ a : struct {normal : Int} . a]. *[normal : one extra : five]*)"
-        ),
-        Ok(_)
-    );
-}
-
-#[test]
-fn end_to_end_quotation_advanced() {
+fn session_syntax_extension() {
+    let if_macro = "Expr ::=also forall T . '{
+            [
+                lit ,{ DefaultToken }, = 'if'
+                cond := ( ,{ Expr< Bool > }, )
+                lit ,{ DefaultToken }, = 'then'
+                then_e := ( ,{ Expr< T > }, )
+                lit ,{ DefaultToken }, = 'else'
+                else_e := ( ,{ Expr< T > }, )
+            ]
+        }' conditional -> .{
+            '[Expr | match ,[cond], {
+                      +[True]+ => ,[then_e],
+                      +[False]+ => ,[else_e], } ]' }. ;";
+    assert!(eval_unseemly_program("if (zero? five) then eight else two").is_err());
+    assert_eq!(extend_session_syntax(if_macro), Ok(()));
+
+    // The extension lasts beyond the line that defined it:
     assert_eq!(
-        eval_unseemly_program(
-            "(.[five_e : Expr < Int >.
-                '[Expr | (plus five ,[five_e],) ]' ].
-                '[Expr | five]')"
-        ),
-        eval_unseemly_program("'[Expr | (plus five five) ]'")
-    );
-
-    // Pass the wrong type (not really a test of quotation)
-    assert_m!(
-        type_unseemly_program(
-            "(.[five_e : Expr<Int> .
-                '[Expr | (plus five ,[five_e],) ]' ].
-                '[Expr | true]')"
-        ),
-        Err(_)
-    );
-
-    // Interpolate the wrong type
-    assert_m!(
-        type_unseemly_program(
-            "(.[five_e : Expr<Bool> .
-                '[Expr | (plus five ,[five_e],) ]' ].
-                '[Expr | true]')"
-        ),
-        Err(_)
-    );
-
-    // Interpolate the wrong type (no application needed to find the error)
-    assert_m!(
-        type_unseemly_program(".[five_e : Expr<Bool> . '[Expr | (plus five ,[five_e],) ]' ]."),
-        Err(_)
-    );
-
-    assert_m!(
-        eval_unseemly_program(
-            "forall T . .[type : Type<T>   rhs : Expr<T>
-                . '[Expr | (.[x : ,[Type<T> | type], . eight].  ,[rhs], )]' ]."
-        ),
-        Ok(_)
+        eval_unseemly_program("if (zero? five) then eight else two").map(|v| v.to_string()),
+        Ok("2".to_owned())
     );
-
-    assert_m!(eval_unseemly_program("'[Pat<Nat> | x]'"), Ok(_));
-
-    // Actually import a pattern of quoted syntax:
     assert_eq!(
-        eval_unseemly_program(
-            "match '[Expr | (plus one two) ]' {
-                 '[Expr<Int> | (plus ,[Expr<Int> | e], two) ]' => e }"
-        ),
-        Ok(val!(ast (vr "one")))
+        type_unseemly_program("if (zero? zero) then eight else two").map(|t| t.to_string()),
+        Ok("Int".to_owned())
     );
-
-    // In order to have "traditional", non-type-annotated `let`, we want to ... reify T, I guess?
-    // But the whole language has parametricity kinda baked in, and that seems to make it hard?
-    // I think the solution is to build `let` into the language;
-    //  if a macro wants to have non-annotated binding, it's probably expandable to `let` anyways.
-    assert_m!(
-        assign_variable(
-            "let",
-            "forall T S . .[binder : Pat<T>
-                        type : Type<T>
-                        rhs : Expr<T>
-                        body : Expr<S> .
-             '[ Expr | (.[x : ,[type],
-                     . match x { ,[Pat<T> | binder], => ,[body], } ].
-                 ,[rhs],)]' ]."
-        ),
-        Ok(_)
-    );
-
-    without_freshening! {
-        assert_eq!(
-            eval_unseemly_program(
-                "(let  '[Pat<Int> | y]'
-                       '[Type<Int> | Int]'
-                       '[Expr<Int> | eight]'
-                       '[Expr<Int> | five]')"),
-            eval_unseemly_program("'[Expr<Int> | (.[x : Int . match x {y => five}].  eight)]'"));
-    }
-
-    //  // We need tuple literals before we can test this:
-    //  assert_m!(assign_variable("let-multi",
-    //      "forall T . .[ binder : **[ :::[T >> Ident<T> ]::: ]**
-    //                     type : **[ :::[T >> Type<T> ]::: ]**
-    //                     rhs : **[ :::[T >> Expr<T> ]::: ]**
-    //                     body : Expr<S> .
-    //          '[Expr | (.[ ...[, binder , >> ,[Ident | binder],]...
-    //                       : ...[, type , >> ,[Type | type], ]... .
-    //                    ,[body], ].
-    //                      ...[, Expr , | ,[rhs], ]... ) ]'
-    //                       "),
-    //       Ok(_));
-
-    //  without_freshening! {
-    //      assert_eq!(
-    //          eval_unseemly_program(
-    //              "(let-multi  '[Ident<Int> | y]'
-    //                     '[Type<Int> | Int]'
-    //                     '[Expr<Int> | eight]'
-    //                     '[Expr<Int> | five]')"),
-    //          eval_unseemly_program("'[Expr<Int> | (.[x : Int . match x {y => five}].  eight)]'"));
-    //  }
-}
-
-#[test]
-fn language_building() {
+    assert!(type_unseemly_program("if five then eight else two").is_err());
     assert_eq!(
-        eval_unseemly_program(
-            r"extend_syntax
-                DefaultSeparator ::= /((?:\s|#[^\n]*)*)/ ;
-            in
-                # Now we have comments! (just not after the last token)
-            five"
-        ),
-        Ok(val!(i 5))
+        assign_variable("x", "if (zero? zero) then eight else two").map(|v| v.to_string()),
+        Ok("8".to_owned())
     );
-
-    let bound_wrong_prog = "extend_syntax
-            Expr ::=also forall T S . '{
-                [
-                    lit ,{ DefaultToken }, = 'let'
-                    [
-                        pat := ( ,{ Pat<S> }, )
-                        lit ,{ DefaultToken }, = '='
-                        value := ( ,{ Expr<S> }, )
-                        lit ,{ DefaultToken }, = ';'
-                    ] *
-                    lit ,{ DefaultToken }, = 'in'
-                    body := ( ,{ Expr<T> }, <-- ...[pat = value]... )
-                ]
-            }' let_macro -> .{
-                '[Expr |
-                    match ...[,value, >> ,[value], ]...
-                        { ...[,pat, >> ,[pat],]... => ,[body], } ]'
-            }. ;
-        in
-        let x = eight ;
-            y = times ;
-        in (plus x y)";
-    let bound_wrong_ast = grammar::parse(
-        &core_forms::outermost_form(),
-        &core_forms::get_core_forms(),
-        runtime::core_values::get_core_envs(),
-        bound_wrong_prog,
-    )
-    .unwrap();
-
-    assert_m!(
-        ty::synth_type(&bound_wrong_ast, crate::runtime::core_values::core_types()),
-        ty_err_p!(Mismatch(x, y)) => {
-            assert_eq!(x, uty!({Int :}));
-            assert_eq!(y, uty!({fn : [{Int :}; {Int :}] {Int :}}));
-        }
-    );
-
-    let inner_expr_wrong_prog = "extend_syntax
-            Expr ::=also forall T S . '{
-                [
-                    lit ,{ DefaultToken }, = 'let'
-                    [
-                        pat := ( ,{ Pat<S> }, )
-                        lit ,{ DefaultToken }, = '='
-                        value := ( ,{ Expr<S> }, )
-                        lit ,{ DefaultToken }, = ';'
-                    ] *
-                    lit ,{ DefaultToken }, = 'in'
-                    body := ( ,{ Expr< T > }, <-- ...[pat = value]... )
-                ]
-            }' let_macro -> .{
-                '[Expr |
-                    match ...[,value, >> ,[value], ]...
-                        { ...[,pat, >> ,[pat],]... => ,[body], } ]'
-            }. ;
-        in
-        let x = eight ;
-            y = four ;
-        in (plus x times)";
-    let inner_expr_wrong_ast = grammar::parse(
-        &core_forms::outermost_form(),
-        &core_forms::get_core_forms(),
-        runtime::core_values::get_core_envs(),
-        inner_expr_wrong_prog,
-    )
-    .unwrap();
-
-    assert_m!(
-        ty::synth_type(&inner_expr_wrong_ast, crate::runtime::core_values::core_types()),
-        ty_err_p!(Mismatch(x, times)) => {
-            assert_eq!(x, uty!({Int :}));
-            assert_eq!(times, uty!({fn : [{Int :}; {Int :}] {Int :}}));
-        }
-    );
-
-    // TODO: leaving out the `**[ ]**` results in an ICP; it should be a static error.
-
-    let let_macro_prog = "extend_syntax
-            Expr ::=also forall T S . '{
-                [
-                    lit ,{ DefaultToken }, = 'let'
-                    [
-                        pat := ( ,{ Pat<S> }, )
-                        lit ,{ DefaultToken }, = '='
-                        value := ( ,{ Expr<S> }, )
-                        lit ,{ DefaultToken }, = ';'
-                    ] *
-                    lit ,{ DefaultToken }, = 'in'
-                    body := ( ,{ Expr<T> }, <-- ...[pat = value]... )
-                ]
-            }' let_macro -> .{
-                '[Expr |
-                    match **[...[,value, >> ,[value], ]... ]**
-                        { **[...[,pat, >> ,[pat],]... ]** => ,[body], } ]'
-            }. ;
-        in
-        let x = eight ;
-            y = four ;
-        in (plus y (plus x y))";
-    assert_eq!(eval_unseemly_program(let_macro_prog), Ok(val!(i 16)));
+    assert_eq!(eval_unseemly_program("(plus x x)").map(|v| v.to_string()), Ok("16".to_owned()));
+
+    // ...and it can be part of a file:
+    let defns = "
+extend_syntax Expr ::=also forall . '{ [ lit ,{ DefaultToken }, = 'twice' e := ( ,{ Expr<Int> }, ) ]
+    }' twice -> .{ '[Expr | (plus ,[e], ,[e],) ]' }. ;
+y := twice six";
+    assert_eq!(session.with(|s| s.borrow_mut().run(defns)), Ok(None));
+    assert_eq!(eval_unseemly_program("(plus y x)").map(|v| v.to_string()), Ok("20".to_owned()));
+
+    let err = extend_session_syntax("Expr ::=also nonsense").unwrap_err();
+    assert!(err.contains("Could not parse"), "{}", err);
 }
+
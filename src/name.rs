@@ -30,32 +30,42 @@ thread_local! {
     // From `Spelling.unique` to `id`s:
     static id_map: RefCell<HashMap<String, usize>> = RefCell::new(HashMap::new());
     // From `id`s to `Spelling`s
-    static spellings: RefCell<Vec<Spelling>> = RefCell::new(vec![]);
+    static spellings: RefCell<Vec<Spelling>> = const { RefCell::new(vec![]) };
 
     static printables: RefCell<HashMap<usize, String>> = RefCell::new(HashMap::new());
     // The values of `printables`, for lookup purposes.
     static printables_used: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
 
     // Should we do "naive" freshening for testing purposes?
-    static fake_freshness: RefCell<bool> = RefCell::new(false);
+    static fake_freshness: RefCell<bool> = const { RefCell::new(false) };
 }
 
 impl crate::runtime::reify::Reifiable for Name {
     fn ty_name() -> Name { n("Name") }
 
     fn reify(&self) -> crate::runtime::eval::Value {
-        crate::runtime::eval::Value::AbstractSyntax(crate::ast::Ast::Atom(*self))
+        crate::runtime::eval::Value::AbstractSyntax(raw_ast!(Atom(*self)))
     }
 
     fn reflect(v: &crate::runtime::eval::Value) -> Name {
         extract!((v) crate::runtime::eval::Value::AbstractSyntax = (ref ast)
                           => crate::core_forms::ast_to_name(ast))
     }
+
+    fn try_reflect(v: &crate::runtime::eval::Value) -> Option<Name> {
+        match *v {
+            crate::runtime::eval::Value::AbstractSyntax(ref ast) => match *ast.c() {
+                crate::ast::Atom(n) => Some(n),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 impl std::cmp::PartialOrd for Name {
     fn partial_cmp(&self, other: &Name) -> Option<std::cmp::Ordering> {
-        Some(self.orig_sp().cmp(&other.orig_sp()))
+        Some(self.cmp(other))
     }
 }
 
@@ -148,7 +158,7 @@ impl Name {
                 *id_map_.borrow_mut().entry(unique_spelling.clone()).or_insert_with(claim_id)
             };
 
-            Name { id: id }
+            Name { id }
         })
     }
     pub fn is(self, s: &str) -> bool { self.sp() == s }
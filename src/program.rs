@@ -0,0 +1,879 @@
+//! Files of top-level items, rather than a single expression:
+//!
+//! ```text
+//! # Comments can go between items.
+//! IntToInt t= [Int -> Int]
+//! twice := .[ f : IntToInt  x : Int . (f (f x)) ].
+//! extend_syntax
+//!     Expr ::=also forall T . '{ ⋯ }' some_macro -> .{ ⋯ }. ;
+//! (twice .[ x : Int . (plus x x) ]. five)
+//! ```
+//!
+//! Each item starts on a new line, and scopes over the rest of the file
+//!  (syntax extensions included). The last one can be an expression for the file to evaluate to.
+//! Besides being what `unseemly <file>` runs, this is the format of the REPL's prelude,
+//!  and of the files that `:load` reads.
+//!
+//! A file can also be a module:
+//!
+//! ```text
+//! export twice IntToInt Expr
+//! ```
+//!
+//! ...makes `twice`, the type `IntToInt`, and the file's extensions to `Expr`
+//!  (along with the macros they define) available to any file that says
+//!
+//! ```text
+//! import "path/to/that/file.≉"
+//! ```
+//!
+//! Paths are relative to the importing file. Imported syntax applies to the rest of the file.
+//! An imported macro's expansion refers to what it would have in the file that defined it,
+//!  even if that isn't exported.
+//!
+//! `class` and `instance` items are described in `type_classes.rs`.
+
+use crate::{
+    ast::{Ast, AstContents::*},
+    ast_walk::{LazyWalkReses, WalkRule::*},
+    core_forms::{ast_to_name, STRING_LITERAL},
+    earley::{AmbiguityPolicy, CodeEnvs, ParseContext},
+    engine::Error,
+    form::{EitherPN::*, Form},
+    grammar::{FormPat, SynEnv},
+    name::*,
+    runtime::{
+        core_values,
+        eval::{eval, RuntimeErr, RuntimeError, Value, BIF},
+    },
+    ty::{synth_type, Ty},
+    type_classes::{self, Instance, Resolver},
+    util::{assoc::Assoc, diagnostics::without_warnings, mbe::EnvMBE, source::SourceFile},
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// The files run on behalf of a `TopLevel` (shared with the ones that run the modules it imports).
+#[derive(Default)]
+pub struct Modules {
+    /// The files being run right now, innermost last. For relative paths and cycle detection.
+    running: Vec<PathBuf>,
+    /// The parser and `run` both need each imported module, but it should only be run once.
+    loaded: HashMap<PathBuf, Result<Module, Error>>,
+}
+
+/// The rest of the line (which may have a comment), or the end of the file.
+const END_OF_ITEM: &str = r"([ \t]*(?:#[^\n]*)?(?:\n|$))";
+
+// These forms are never walked; `TopLevel::run` takes them apart itself.
+fn item_form(name: &str, grammar: FormPat) -> Rc<Form> {
+    Rc::new(Form {
+        name: n(name),
+        grammar: Rc::new(grammar),
+        type_compare: Both(NotWalked, NotWalked),
+        synth_type: Positive(NotWalked),
+        eval: Positive(NotWalked),
+        quasiquote: Both(LiteralLike, LiteralLike),
+    })
+}
+
+/// The grammar of the "Program" nonterminal: one item, and then (usually) the rest of the file.
+/// Imports are loaded (from `modules`) as they're parsed; the parser has no way to report
+///  a failed import, so it leaves the error in `import_errors`.
+fn program_grammar(
+    modules: &Rc<RefCell<Modules>>,
+    import_errors: &Rc<RefCell<Vec<Error>>>,
+    ambiguity: AmbiguityPolicy,
+) -> FormPat
+{
+    program_items(Some((modules.clone(), import_errors.clone(), ambiguity)))
+}
+
+/// Just one item, so that it can be run before the rest of the file is parsed.
+/// (Syntax extensions might refer to values defined by earlier items.)
+fn first_program_item() -> FormPat { program_items(None) }
+
+type Importing = (Rc<RefCell<Modules>>, Rc<RefCell<Vec<Error>>>, AmbiguityPolicy);
+
+fn program_items(whole_program: Option<Importing>) -> FormPat {
+    use crate::core_macro_forms::{perform_extension, syntax_extension_clauses};
+
+    let one_at_a_time = whole_program.is_none();
+    let rest = || {
+        if one_at_a_time {
+            form_pat!((scan END_OF_ITEM))
+        } else {
+            form_pat!([(scan END_OF_ITEM), (named "rest", (call "Program"))])
+        }
+    };
+    // The last item has to be last, even when it's parsed on its own:
+    let end_of_file = || if one_at_a_time { form_pat!((scan "$")) } else { form_pat!([]) };
+
+    let extend_for_rest = move |pc: ParseContext, clauses: Ast| -> ParseContext {
+        extract!((clauses.c()) IncompleteNode = (ref parts) => perform_extension(pc, parts))
+    };
+
+    // Each item has to start on a new line, or else (e.g.) `x := f y` would be ambiguous
+    //  if `f y` might be a macro invocation.
+    let (modules, import_errors, ambiguity) =
+        whole_program.unwrap_or((Default::default(), Default::default(), AmbiguityPolicy::Error));
+    let import_for_rest = move |pc: ParseContext, path_part: Ast| -> ParseContext {
+        let path = extract!((path_part.c()) IncompleteNode = (ref parts) =>
+            path_from_ast(parts.get_leaf_or_panic(&n("path"))));
+        match load_module(&modules, &path, ambiguity) {
+            Ok(module) => {
+                let grammar =
+                    module.syntax.iter().fold(pc.grammar.clone(), |g, clause| clause.extend(&g));
+                pc.with_grammar(grammar)
+            }
+            Err(e) => {
+                import_errors.borrow_mut().push(e);
+                pc
+            }
+        }
+    };
+
+    let define_value = item_form("define_value", form_pat!(
+        [(named "name", atom), (call "DefaultSeparator"), (scan r"(:=)"),
+         (named "value", (call "Expr")),
+         (, rest())]));
+    let define_type = item_form("define_type", form_pat!(
+        [(named "name", atom), (call "DefaultSeparator"), (scan r"(t=)"),
+         (named "type", (call "Type")),
+         (, rest())]));
+    // When items are parsed one at a time, running each item takes care of extending the syntax.
+    let extend_syntax = item_form("extend_syntax", if one_at_a_time {
+        form_pat!([(lit "extend_syntax"), (, syntax_extension_clauses()), (, rest())])
+    } else {
+        form_pat!([(lit "extend_syntax"),
+                   (extend (, syntax_extension_clauses()), (, rest()), extend_for_rest)])
+    });
+    let import = item_form("import", if one_at_a_time {
+        form_pat!([(lit "import"), (call "DefaultSeparator"),
+                   (named "path", (scan STRING_LITERAL)), (, rest())])
+    } else {
+        form_pat!([(lit "import"), (call "DefaultSeparator"),
+                   (extend (named "path", (scan STRING_LITERAL)), (, rest()), import_for_rest)])
+    });
+    // Only one line of names, so that the next item isn't mistaken for more of them:
+    let export = item_form("export", form_pat!(
+        [(lit "export"),
+         (star [(scan r"([ \t]*)"),
+                (named "export_name", (scan r"(\p{Letter}(?:\p{Letter}|\p{Number}|[_?])*)"))]),
+         (, rest())]));
+    let class = item_form("class", form_pat!(
+        [(lit "class"), (named "name", atom), (named "param", atom),
+         (delim "{", "{", (star [(named "method", atom), (lit ":"),
+                                 (named "method_ty", (call "Type"))])),
+         (, rest())]));
+    let instance = item_form("instance", form_pat!(
+        [(lit "instance"), (named "head", (call "Type")), (call "DefaultSeparator"),
+         (scan r"(:=)"), (named "value", (call "Expr")),
+         (, rest())]));
+    let main = item_form("main", form_pat!(
+        [(named "main", (call "Expr")), (call "TopLevelSeparator"), (, end_of_file())]));
+    let end = item_form("end", end_of_file());
+
+    form_pat!((pick [(call "TopLevelSeparator"),
+                     (named "item", (alt (scope define_value), (scope define_type),
+                                         (scope extend_syntax), (scope import), (scope export),
+                                         (scope class), (scope instance),
+                                         (scope main), (scope end)))],
+               "item"))
+}
+
+/// What a file makes available to the files that `import` it.
+#[derive(Clone, Default)]
+pub struct Module {
+    /// Replayed, in order, on the importer's grammar.
+    pub syntax: Vec<SyntaxClause>,
+    pub types: Assoc<Name, Ty>,
+    pub values: Assoc<Name, Value>,
+    /// Exported along with their (dictionary) types.
+    pub(crate) classes: Vec<Name>,
+    /// All of them, always: there's no way to name an instance in order to export it.
+    pub(crate) instances: Vec<Instance>,
+}
+
+/// One clause of a top-level `extend_syntax` (with its grammar already evaluated),
+///  so that it can be exported.
+#[derive(Clone)]
+pub struct SyntaxClause {
+    pub nt: Name,
+    /// `::=also`, rather than `::=`
+    pub also: bool,
+    pub rhs: Rc<FormPat>,
+    /// The types of the macros that `rhs` defines
+    pub macros: Assoc<Name, Ty>,
+    /// The bindings that the macros' expansions refer to, under private names
+    ///  (so that they mean the same thing wherever the macros are used).
+    pub captured_types: Assoc<Name, Ty>,
+    pub captured_values: Assoc<Name, Value>,
+}
+
+impl SyntaxClause {
+    pub fn extend(&self, syn_env: &SynEnv) -> SynEnv {
+        crate::core_macro_forms::extend_nt(syn_env, self.nt, self.also, (*self.rhs).clone())
+    }
+}
+
+/// Everything that top-level items can bind.
+#[derive(Clone)]
+pub struct TopLevel {
+    pub syn_env: SynEnv,
+    pub ty_env: Assoc<Name, Ty>,
+    pub val_env: Assoc<Name, Value>,
+    /// The syntax extensions made (or imported) here, so that they can be exported.
+    pub extensions: Vec<SyntaxClause>,
+    /// The type classes in scope (see `type_classes.rs`), and their instances.
+    pub classes: Vec<Name>,
+    pub(crate) instances: Vec<Instance>,
+    pub exports: Module,
+    /// What to do about code that can be parsed more than one way.
+    pub ambiguity: AmbiguityPolicy,
+    modules: Rc<RefCell<Modules>>,
+}
+
+impl Default for TopLevel {
+    /// Just the core language.
+    fn default() -> TopLevel {
+        TopLevel {
+            syn_env: crate::core_forms::get_core_forms(),
+            ty_env: core_values::core_types(),
+            val_env: core_values::core_values(),
+            extensions: vec![],
+            classes: vec![],
+            instances: vec![],
+            exports: Module::default(),
+            ambiguity: AmbiguityPolicy::Error,
+            modules: Rc::new(RefCell::new(Modules::default())),
+        }
+    }
+}
+
+impl TopLevel {
+    /// Syntax extensions are evaluated at parse time, so they get to use our bindings.
+    pub fn code_envs(&self) -> CodeEnvs {
+        (
+            LazyWalkReses::new_wrapper(self.ty_env.clone()),
+            LazyWalkReses::new_wrapper(self.val_env.clone()),
+        )
+    }
+
+    pub fn parse(&self, rule: &FormPat, program: &str) -> Result<Ast, Error> {
+        self.parse_file(rule, &SourceFile::new("<input>", program))
+    }
+
+    /// Like `parse`, but the `Ast`s remember that they came from `file`.
+    pub fn parse_file(&self, rule: &FormPat, file: &Rc<SourceFile>) -> Result<Ast, Error> {
+        let envs = self.code_envs();
+        Ok(crate::earley::parse_file_from(rule, &self.syn_env, envs, file, 0, self.ambiguity)?)
+    }
+
+    /// Synthesize the type of `expr`, generalizing any parameter types it left unconstrained.
+    pub fn typecheck(&self, expr: &Ast) -> Result<Ty, Error> { Ok(self.elaborate(expr)?.0) }
+
+    /// Typecheck and expand `expr`, and pass it the class instances it needs
+    ///  (see `type_classes.rs`).
+    /// Instances for types that get generalized become parameters of the result.
+    pub fn elaborate(&self, expr: &Ast) -> Result<(Ty, Ast), Error> {
+        // Typecheck what was written, so that errors are about that...
+        synth_type(expr, self.ty_env.clone())?;
+        // ...but macros can generate references that need instances, so elaborate the expansion.
+        // (Any warnings about it would just repeat the ones about `expr`, less helpfully.)
+        let expanded = type_classes::identify(&crate::expand::expand(expr)?);
+        type_classes::take_wanted();
+        let ty = without_warnings(|| synth_type(&expanded, self.ty_env.clone()))?;
+        let mut resolver = Resolver::new(&self.instances, self.ty_env.clone()).generalizing();
+        let dicts = resolver.resolve_all(type_classes::take_wanted())?;
+        check_identified(&dicts)?;
+        let (constraints, params): (Vec<_>, Vec<_>) = resolver.deferred().into_iter().unzip();
+        let ty = crate::ty_compare::generalize(&ty, &constraints, self.ty_env.clone())
+            .map_err(|e| Error::Type(crate::util::err::sp(e, expr.clone())))?;
+        Ok((ty, type_classes::abstract_over(&params, type_classes::elaborate(&expanded, &dicts))))
+    }
+
+    /// Typecheck, expand, and evaluate `expr`.
+    pub fn eval(&self, expr: &Ast) -> Result<(Ty, Value), Error> {
+        let (ty, core_ast) = self.elaborate(expr)?;
+        let val = eval(&core_ast, self.val_env.clone())?;
+        Ok((ty, val))
+    }
+
+    pub fn define_value(&mut self, name: Name, expr: &Ast) -> Result<Value, Error> {
+        let (ty, val) = self.eval(expr)?;
+        self.bind(name, ty, val.clone());
+        Ok(val)
+    }
+
+    pub fn bind(&mut self, name: Name, ty: Ty, val: Value) {
+        self.ty_env = self.ty_env.set(name, ty);
+        self.val_env = self.val_env.set(name, val);
+    }
+
+    pub fn define_type(&mut self, name: Name, t: &Ast) -> Result<Ty, Error> {
+        let ty = synth_type(t, self.ty_env.clone())?;
+        self.ty_env = self.ty_env.set(name, ty.clone());
+        Ok(ty)
+    }
+
+    /// `class name param { method : method_ty ⋯ }`.
+    /// The class is the type of its dictionaries, `forall param . *[ method : method_ty ⋯ ]*`,
+    ///  and each method is a function from a dictionary to the method.
+    pub fn define_class(
+        &mut self,
+        name: Name,
+        param: Name,
+        methods: &[(Name, &Ast)],
+    ) -> Result<Ty, Error>
+    {
+        let param_env = self.ty_env.set(param, Ty(raw_ast!(VariableReference(param))));
+        let mut method_tys = vec![];
+        for (_, method_ty) in methods {
+            method_tys.push(synth_type(method_ty, param_env.clone())?.concrete());
+        }
+        let forall = |body: Ast| {
+            ty!({"Type" "forall_type" :
+                "param" => [(, raw_ast!(Atom(param)))],
+                "body" => (import [* [forall "param"]] (, body))})
+        };
+        let dict_ty = forall(ast!({"Type" "struct" :
+            "component_name" =>
+                (@"c" ,seq methods.iter().map(|(m, _)| raw_ast!(Atom(*m))).collect::<Vec<_>>()),
+            "component" => (@"c" ,seq method_tys.clone())}));
+        self.ty_env = self.ty_env.set(name, dict_ty.clone());
+        self.classes.push(name);
+
+        let this_class = type_classes::constraint(name, &Ty(raw_ast!(VariableReference(param))));
+        for ((method, _), method_ty) in methods.iter().zip(method_tys) {
+            let constrained = ast!({"Type" "constrained_type" :
+                "constraint" => [(, this_class.clone())], "body" => (, method_ty)});
+            self.bind(*method, forall(constrained), type_classes::method_value(*method));
+        }
+        Ok(dict_ty)
+    }
+
+    /// `instance head := value`, where `head` is something like `Show<Int>`,
+    ///  or `forall T . { Show<T> => Show<List<T>> }` (which needs `Show<T>` to build `value`).
+    pub fn define_instance(&mut self, head: &Ast, value: &Ast) -> Result<(), Error> {
+        let (params, givens, class, arg) = type_classes::instance_head(head).ok_or_else(|| {
+            Error::Program(format!("expected an instance, like `Show<Int>`, not `{}`", head))
+        })?;
+        if !self.classes.contains(&class) {
+            return Err(Error::Program(format!("`{}` is not a class", class)));
+        }
+        let mut env = self.ty_env.clone();
+        for param in &params {
+            env = env.set(*param, Ty(raw_ast!(VariableReference(*param))));
+        }
+        let arg = synth_type(&arg.0, env.clone())?;
+        let mut given_tys = vec![];
+        for (g_class, g_ty) in givens {
+            given_tys.push((g_class, synth_type(&g_ty.0, env.clone())?));
+        }
+        if self.instances.iter().any(|inst| inst.class == class && inst.arg == arg) {
+            return Err(Error::Program(format!(
+                "there's already a `{}` instance for `{}`",
+                class, arg
+            )));
+        }
+
+        // The dictionary might refer to itself, so it's available before it's built.
+        let dict = Name::gensym(&format!("{}_instance", class.orig_sp()));
+        let dict_cell: Rc<RefCell<Option<Value>>> = Rc::new(RefCell::new(None));
+        let dict_cell_2 = dict_cell.clone();
+        let dict_fn = move |_: Vec<Value>| {
+            let dict_val = dict_cell_2.borrow().clone();
+            dict_val.ok_or_else(|| RuntimeError::new(RuntimeErr::UnboundVar(dict)))
+        };
+        let outer = (self.instances.len(), self.val_env.clone());
+        self.val_env = self.val_env.set(dict, Value::BuiltInFunction(BIF(Rc::new(dict_fn))));
+        self.instances.push(Instance {
+            params,
+            givens: given_tys.clone(),
+            class,
+            arg: arg.clone(),
+            dict,
+        });
+
+        let res = self.build_dictionary(value, &class, &arg, &given_tys, env);
+        match res {
+            Ok(val) => {
+                *dict_cell.borrow_mut() = Some(val);
+                Ok(())
+            }
+            Err(e) => {
+                self.instances.truncate(outer.0);
+                self.val_env = outer.1;
+                Err(e)
+            }
+        }
+    }
+
+    /// Check that `value` is a `class<arg>` (given instances for `givens`), and evaluate it.
+    fn build_dictionary(
+        &self,
+        value: &Ast,
+        class: &Name,
+        arg: &Ty,
+        givens: &[(Name, Ty)],
+        env: Assoc<Name, Ty>,
+    ) -> Result<Value, Error>
+    {
+        let expected = synth_type(&type_classes::constraint(*class, arg), env.clone())?;
+        let got = synth_type(value, env.clone())?;
+        crate::ty_compare::must_subtype(&got, &expected, env.clone())
+            .map_err(|e| crate::util::err::sp(e, value.clone()))?;
+
+        let expanded = type_classes::identify(&crate::expand::expand(value)?);
+        type_classes::take_wanted();
+        synth_type(&expanded, env.clone())?;
+        let mut resolver = Resolver::new(&self.instances, env);
+        let mut given_params = vec![];
+        for (g_class, g_ty) in givens {
+            let param = Name::gensym(&format!("{}_dict", g_class));
+            resolver = resolver.given(*g_class, g_ty.clone(), param);
+            given_params.push(param);
+        }
+        let dicts = resolver.resolve_all(type_classes::take_wanted())?;
+        check_identified(&dicts)?;
+        let core_ast =
+            type_classes::abstract_over(&given_params, type_classes::elaborate(&expanded, &dicts));
+        Ok(eval(&core_ast, self.val_env.clone())?)
+    }
+
+    /// `clauses` is what `syntax_extension_clauses` parses to.
+    pub fn extend_syntax(&mut self, clauses: &EnvMBE<Ast>) -> Result<(), Error> {
+        let (clauses, captured_types, captured_values) = self.capture_quoted_names(clauses);
+        self.ty_env = self.ty_env.set_assoc(&captured_types);
+        self.val_env = self.val_env.set_assoc(&captured_values);
+        let mut macros = vec![];
+        for rhs in clauses.get_rep_leaf_or_panic(n("rhs")) {
+            let macro_types = crate::core_macro_forms::macros_defined_by(rhs, &self.ty_env)?;
+            self.ty_env = self.ty_env.set_assoc(&macro_types);
+            macros.push(macro_types);
+        }
+        let pc = ParseContext::new(self.syn_env.clone(), self.code_envs());
+        let evaluated = crate::core_macro_forms::evaluate_extension(&pc, &clauses);
+        for ((nt, also, rhs), macros) in evaluated.into_iter().zip(macros) {
+            let clause = SyntaxClause {
+                nt,
+                also,
+                rhs: Rc::new(rhs),
+                macros,
+                captured_types: captured_types.clone(),
+                captured_values: captured_values.clone(),
+            };
+            self.syn_env = clause.extend(&self.syn_env);
+            self.extensions.push(clause);
+        }
+        Ok(())
+    }
+
+    /// A macro's expansion can refer to things defined here (not just in the core language),
+    ///  which might be unexported or shadowed where it's used.
+    /// So, rename those references (in the quoted syntax in `clauses`) to fresh names,
+    ///  and return the bindings for the fresh names, too.
+    fn capture_quoted_names(
+        &self,
+        clauses: &EnvMBE<Ast>,
+    ) -> (EnvMBE<Ast>, Assoc<Name, Ty>, Assoc<Name, Value>)
+    {
+        let core_types = core_values::core_types();
+        let (mut renaming, mut originals) = (Assoc::new(), Assoc::new());
+        for (name, ty) in self.ty_env.iter_pairs() {
+            if core_types.find(name) != Some(ty) {
+                let fresh = name.freshen();
+                renaming = renaming.set(*name, raw_ast!(VariableReference(fresh)));
+                originals = originals.set(fresh, *name);
+            }
+        }
+        let clauses = clauses.map(&mut |a| crate::alpha::substitute_quoted(a, &renaming));
+
+        let (mut types, mut values) = (Assoc::new(), Assoc::new());
+        let mut capture = |var: &Ast| {
+            if let VariableReference(fresh) = *var.c() {
+                if let Some(original) = originals.find(&fresh) {
+                    types = types.set(fresh, self.ty_env.find_or_panic(original).clone());
+                    if let Some(val) = self.val_env.find(original) {
+                        values = values.set(fresh, val.clone());
+                    }
+                }
+            }
+            var.clone()
+        };
+        for rhs in clauses.get_rep_leaf_or_panic(n("rhs")) {
+            type_classes::map_vars(rhs, &mut capture);
+        }
+        (clauses, types, values)
+    }
+
+    pub fn import(&mut self, module: &Module) {
+        for clause in &module.syntax {
+            // (Two imports might re-export the same extension.)
+            if self.extensions.iter().any(|c| Rc::ptr_eq(&c.rhs, &clause.rhs)) {
+                continue;
+            }
+            self.syn_env = clause.extend(&self.syn_env);
+            self.ty_env = self.ty_env.set_assoc(&clause.macros).set_assoc(&clause.captured_types);
+            self.val_env = self.val_env.set_assoc(&clause.captured_values);
+            self.extensions.push(clause.clone());
+        }
+        self.ty_env = self.ty_env.set_assoc(&module.types);
+        self.val_env = self.val_env.set_assoc(&module.values);
+        self.classes.extend(module.classes.iter().cloned());
+        self.instances.extend(module.instances.iter().cloned());
+        self.exports.instances.extend(module.instances.iter().cloned());
+    }
+
+    /// Export whatever is called `name`: a value, a type, or a nonterminal.
+    pub fn export(&mut self, name: Name) -> Result<(), Error> {
+        let mut found = false;
+        if let Some(ty) = self.ty_env.find(&name) {
+            self.exports.types = self.exports.types.set(name, ty.clone());
+            if self.classes.contains(&name) {
+                self.exports.classes.push(name);
+            }
+            found = true;
+        }
+        if let Some(val) = self.val_env.find(&name) {
+            self.exports.values = self.exports.values.set(name, val.clone());
+            found = true;
+        }
+        if self.syn_env.find(&name).is_some() {
+            // Export the extensions, not the whole nonterminal,
+            //  so that the importer's (and other imports') extensions to it survive:
+            if !self.exports.syntax.iter().any(|c| c.nt == name) {
+                let clauses = self.extensions.iter().filter(|c| c.nt == name).cloned();
+                self.exports.syntax.extend(clauses);
+            }
+            found = true;
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(Error::Program(format!("can't export `{}`: there's nothing by that name", name)))
+        }
+    }
+
+    /// Bind all of the items in `program`, in order, returning the value of the final expression
+    ///  (if there is one). Stops at the first error, but keeps the bindings made before it.
+    pub fn run(&mut self, program: &str) -> Result<Option<Value>, Error> {
+        self.forget_modules();
+        self.run_items(&SourceFile::new("<input>", program))
+    }
+
+    /// Like `run`, but relative `import`s are relative to `path`.
+    pub fn run_file(&mut self, path: &Path) -> Result<Option<Value>, Error> {
+        self.forget_modules();
+        let (path, file) = read_source(path)?;
+        self.modules.borrow_mut().running.push(path);
+        let res = self.run_items(&file);
+        self.modules.borrow_mut().running.pop();
+        res
+    }
+
+    /// Modules might have changed since the last time that something was run (from the outside).
+    fn forget_modules(&self) {
+        let mut modules = self.modules.borrow_mut();
+        if modules.running.is_empty() {
+            modules.loaded.clear();
+        }
+    }
+
+    /// Each item is run before the next one is parsed,
+    ///  so that syntax extensions can use the values defined before them.
+    fn run_items(&mut self, file: &Rc<SourceFile>) -> Result<Option<Value>, Error> {
+        let mut start = 0;
+        loop {
+            let (item, end) = self.parse_item_at(file, start)?;
+            let parts = extract!((item.c()) Node = (_, ref parts, _) => parts.clone());
+            let form_name = extract!((item.c()) Node = (ref form, _, _) => form.name);
+            let name = || ast_to_name(parts.get_leaf_or_panic(&n("name")));
+            match form_name.sp().as_str() {
+                "define_value" => {
+                    self.define_value(name(), parts.get_leaf_or_panic(&n("value")))?;
+                }
+                "define_type" => {
+                    self.define_type(name(), parts.get_leaf_or_panic(&n("type")))?;
+                }
+                "extend_syntax" => self.extend_syntax(&parts)?,
+                "import" => {
+                    let path = path_from_ast(parts.get_leaf_or_panic(&n("path")));
+                    let module = load_module(&self.modules, &path, self.ambiguity)?;
+                    self.import(&module);
+                }
+                "export" => {
+                    for export_name in parts.get_rep_leaf_or_panic(n("export_name")) {
+                        self.export(ast_to_name(export_name))?;
+                    }
+                }
+                "class" => {
+                    let method_tys = parts.get_rep_leaf_or_panic(n("method_ty"));
+                    let methods: Vec<_> = parts
+                        .get_rep_leaf_or_panic(n("method"))
+                        .into_iter()
+                        .map(ast_to_name)
+                        .zip(method_tys)
+                        .collect();
+                    let param = ast_to_name(parts.get_leaf_or_panic(&n("param")));
+                    self.define_class(name(), param, &methods)?;
+                }
+                "instance" => self.define_instance(
+                    parts.get_leaf_or_panic(&n("head")),
+                    parts.get_leaf_or_panic(&n("value")),
+                )?,
+                "main" => return Ok(Some(self.eval(parts.get_leaf_or_panic(&n("main")))?.1)),
+                _ => return Ok(None), // "end"
+            }
+            start = end;
+        }
+    }
+
+    /// Parse the item at `start`, and find where the next one begins.
+    fn parse_item_at(&self, file: &Rc<SourceFile>, start: usize) -> Result<(Ast, usize), Error> {
+        let item = crate::earley::parse_file_prefix(
+            &first_program_item(), &self.syn_env, self.code_envs(), file, start, self.ambiguity);
+        match item {
+            Ok(item) => Ok(item),
+            // Parse the whole rest of the file, to report all of its errors, not just the first:
+            Err(item_err) => {
+                let import_errors = Rc::new(RefCell::new(vec![]));
+                let program = program_grammar(&self.modules, &import_errors, self.ambiguity);
+                let rest = crate::earley::parse_file_from(
+                    &FormPat::Call(n("Program")),
+                    &self.syn_env.set(n("Program"), Rc::new(program)),
+                    self.code_envs(),
+                    file,
+                    start,
+                    self.ambiguity,
+                );
+                let import_error = import_errors.borrow_mut().drain(..).next();
+                // A failed import probably explains any parse error after it:
+                match (rest, import_error) {
+                    (Err(_), Some(import_err)) => Err(import_err),
+                    (Err(rest_err), None) => Err(rest_err.into()),
+                    (Ok(_), _) => Err(item_err.into()),
+                }
+            }
+        }
+    }
+}
+
+/// Only references that `type_classes::identify` saw can be elaborated.
+fn check_identified(dicts: &type_classes::Dictionaries) -> Result<(), Error> {
+    match dicts.unidentified.first() {
+        Some(var) => Err(Error::Program(format!(
+            "can't pass class instances to `{}`, because it can't be found again to rewrite",
+            var
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn path_from_ast(path: &Ast) -> String {
+    crate::core_forms::unescape_string_literal(&ast_to_name(path).orig_sp())
+}
+
+/// Read a source file (named, for error messages, as `path`). Also returns its canonical path.
+fn read_source(path: &Path) -> Result<(PathBuf, Rc<SourceFile>), Error> {
+    let couldnt_read = |e| Error::Program(format!("couldn't read {}: {}", path.display(), e));
+    let canonical = path.canonicalize().map_err(couldnt_read)?;
+    let source = std::fs::read_to_string(&canonical).map_err(couldnt_read)?;
+    Ok((canonical, SourceFile::new(&path.display().to_string(), &source)))
+}
+
+/// Run the file at `path` (relative to the file currently running), and get its exports.
+/// Its ambiguities are treated according to `ambiguity`, like the importer's.
+fn load_module(
+    modules: &Rc<RefCell<Modules>>,
+    path: &str,
+    ambiguity: AmbiguityPolicy,
+) -> Result<Module, Error>
+{
+    let full_path = match modules.borrow().running.last() {
+        Some(importer) => importer.parent().unwrap_or_else(|| Path::new("")).join(path),
+        None => PathBuf::from(path),
+    };
+    let canonical = full_path
+        .canonicalize()
+        .map_err(|e| Error::Program(format!("couldn't import \"{}\": {}", path, e)))?;
+
+    if let Some(res) = modules.borrow().loaded.get(&canonical) {
+        return res.clone();
+    }
+    let cycle: Option<Vec<String>> = {
+        let running = &modules.borrow().running;
+        running
+            .iter()
+            .position(|f| *f == canonical)
+            .map(|start| running[start..].iter().map(|f| f.display().to_string()).collect())
+    };
+    if let Some(cycle) = cycle {
+        return Err(Error::Program(format!(
+            "import cycle: {} imports {} again",
+            cycle.join(" imports "),
+            canonical.display()
+        )));
+    }
+
+    let mut module_top = TopLevel { modules: modules.clone(), ambiguity, ..TopLevel::default() };
+    let res = module_top.run_file(&full_path).map(|_| module_top.exports);
+    modules.borrow_mut().loaded.insert(canonical, res.clone());
+    res
+}
+
+#[test]
+fn run_top_level_items() {
+    let mut top = TopLevel::default();
+    let prog = "# Comments are allowed between items.
+IntToInt t= [Int -> Int]  # ...and after them, on the same line.
+twice := .[ f : IntToInt  x : Int .
+    (f (f x)) ].
+
+# An extension scopes over the rest of the file:
+extend_syntax
+    Expr ::=also forall . '{ [
+        lit ,{ DefaultToken }, = 'double'
+        e := ( ,{ Expr<Int> }, ) ] }' double -> .{ '[Expr | (plus ,[e], ,[e],) ]' }. ;
+four := double two
+(twice .[ x : Int . double x ]. four)
+";
+    assert_eq!(top.run(prog), Ok(Some(val!(i 16))));
+    assert_eq!(top.val_env.find(&n("four")), Some(&val!(i 4)));
+    // The bindings, including the extension, survive:
+    assert_eq!(top.run("double four"), Ok(Some(val!(i 8))));
+
+    assert_eq!(top.run("three := (plus one two)\n  # No main expression\n"), Ok(None));
+    assert_eq!(top.val_env.find(&n("three")), Some(&val!(i 3)));
+
+    // Bindings made before an error stay made:
+    let err = top.run("seven := (plus three four)\n oops := (plus one true)").unwrap_err();
+    let err = err.to_string();
+    assert!(err.contains("Mismatch"), "{}", err);
+    assert_eq!(top.val_env.find(&n("seven")), Some(&val!(i 7)));
+
+    // Each item runs before the next is parsed, so a macro can use an earlier value:
+    let prog = "tripled := .[ e : Expr<Int> . '[Expr | (plus ,[e], (plus ,[e], ,[e],)) ]' ].
+extend_syntax
+    Expr ::=also forall . '{ [
+        lit ,{ DefaultToken }, = 'thrice'
+        e := ( ,{ Expr<Int> }, ) ] }' thrice -> .{ (tripled e) }. ;
+thrice four
+";
+    assert_eq!(top.run(prog), Ok(Some(val!(i 12))));
+}
+
+#[test]
+fn default_prelude() {
+    let mut top = TopLevel::default();
+    assert_eq!(top.run(include_str!("examples/.unseemly_prelude")), Ok(None));
+    assert_eq!(top.run("(list_len list_123)"), Ok(Some(val!(i 3))));
+}
+
+#[test]
+fn modules() {
+    let dir = std::env::temp_dir().join(format!("unseemly_modules_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    let write = |file: &str, contents: &str| std::fs::write(dir.join(file), contents).unwrap();
+
+    write("lib/arith.≉", "IntToInt t= [Int -> Int]
+double := .[ x : Int . (plus x x) ].
+not_exported := one
+extend_syntax
+    Expr ::=also forall . '{ [
+        lit ,{ DefaultToken }, = 'quadruple'
+        e := ( ,{ Expr<Int> }, ) ] }' quadruple -> .{ '[Expr | (double (double ,[e],)) ]' }. ;
+export IntToInt double Expr
+");
+    write("main.≉", "import \"lib/arith.≉\"
+apply_twice := .[ f : IntToInt  x : Int . (f (f x)) ].
+quadruple (apply_twice double two)");
+    let mut top = TopLevel::default();
+    assert_eq!(top.run_file(&dir.join("main.≉")), Ok(Some(val!(i 32))));
+    assert!(top.val_env.find(&n("not_exported")).is_none());
+
+    // Imports are typechecked against the exported types:
+    write("bad_use.≉", "import \"lib/arith.≉\"\n(double true)");
+    let err = TopLevel::default().run_file(&dir.join("bad_use.≉")).unwrap_err().to_string();
+    assert!(err.contains("Mismatch"), "{}", err);
+    assert!(err.contains("bad_use.≉:2:9"), "{}", err);
+
+    write("bad_export.≉", "export nonexistent");
+    write("imports_bad_export.≉", "import \"bad_export.≉\"\none");
+    let err = TopLevel::default().run_file(&dir.join("imports_bad_export.≉")).unwrap_err();
+    let err = err.to_string();
+    assert!(err.contains("can't export `nonexistent`"), "{}", err);
+
+    let err = TopLevel::default().run("import \"nonexistent.≉\"").unwrap_err().to_string();
+    assert!(err.contains("couldn't import \"nonexistent.≉\""), "{}", err);
+
+    // Extensions to the same nonterminal from different places all survive:
+    write("lib/square.≉", "extend_syntax
+    Expr ::=also forall . '{ [
+        lit ,{ DefaultToken }, = 'square'
+        e := ( ,{ Expr<Int> }, ) ] }' square -> .{ '[Expr | (times ,[e], ,[e],) ]' }. ;
+export Expr
+");
+    write("both.≉", "import \"lib/arith.≉\"
+import \"lib/square.≉\"
+extend_syntax
+    Expr ::=also forall . '{ [
+        lit ,{ DefaultToken }, = 'halve'
+        e := ( ,{ Expr<Int> }, ) ] }' halve -> .{ '[Expr | (div ,[e], two) ]' }. ;
+a := quadruple one
+b := square three
+c := halve four
+(plus a (plus b c))");
+    assert_eq!(TopLevel::default().run_file(&dir.join("both.≉")), Ok(Some(val!(i 15))));
+    // The extension comes with the types of its macros, and nothing else:
+    let mut top = TopLevel::default();
+    top.run_file(&dir.join("lib/square.≉")).unwrap();
+    assert_eq!(top.exports.syntax.len(), 1);
+    assert!(top.exports.syntax[0].macros.find(&n("square")).is_some());
+    assert!(top.exports.values.empty());
+
+    // A macro's expansion means what it meant where the macro was defined,
+    //  even if that isn't exported, or is shadowed by the importer:
+    write("lib/helped.≉", "helper := .[ x : Int . (plus x x) ].
+extend_syntax
+    Expr ::=also forall . '{ [
+        lit ,{ DefaultToken }, = 'helped'
+        e := ( ,{ Expr<Int> }, ) ] }' helped -> .{ '[Expr | (helper ,[e],) ]' }. ;
+ten_ish := helped five
+export Expr ten_ish
+");
+    write("helped.≉", "import \"lib/helped.≉\"\nx := helped five\n(plus ten_ish x)");
+    assert_eq!(TopLevel::default().run_file(&dir.join("helped.≉")), Ok(Some(val!(i 20))));
+    write("shadowed.≉", "import \"lib/helped.≉\"
+helper := .[ x : Int . (times x five) ].
+x := helped (helper one)
+(plus x (helper one))");
+    assert_eq!(TopLevel::default().run_file(&dir.join("shadowed.≉")), Ok(Some(val!(i 15))));
+
+    // Modules are parsed with the importer's ambiguity policy:
+    write("lib/ambiguous.≉", "extend_syntax
+    Expr ::=also forall . '{ [
+        lhs := ( ,{ Expr<Int> }, ) lit ,{ DefaultToken }, = '+' rhs := ( ,{ Expr<Int> }, )
+    ] }' add -> .{ '[Expr | (plus ,[lhs], ,[rhs],)]' }. ;
+six := one + two + three
+export six
+");
+    write("ambiguous.≉", "import \"lib/ambiguous.≉\"\nsix");
+    let err = TopLevel::default().run_file(&dir.join("ambiguous.≉")).unwrap_err().to_string();
+    assert!(err.contains("Ambiguous syntax"), "{}", err);
+    let mut top = TopLevel { ambiguity: AmbiguityPolicy::Warn, ..TopLevel::default() };
+    assert_eq!(top.run_file(&dir.join("ambiguous.≉")), Ok(Some(val!(i 6))));
+
+    write("a.≉", "import \"b.≉\"\nexport one");
+    write("b.≉", "import \"a.≉\"\nexport two");
+    let err = TopLevel::default().run_file(&dir.join("a.≉")).unwrap_err().to_string();
+    assert!(err.contains("import cycle"), "{}", err);
+    assert!(err.contains("a.≉ imports "), "{}", err);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -2,16 +2,16 @@ use crate::{
     ast::Ast,
     name::*,
     runtime::eval::{
-        eval,
+        eval, RuntimeErr, RuntimeError,
         Value::{self, *},
         BIF,
     },
     ty::Ty,
     util::assoc::Assoc,
 };
-use std::rc::Rc;
+use std::{cmp::Ordering, rc::Rc};
 
-use num::BigInt;
+use num::{BigInt, Integer, Signed, ToPrimitive, Zero};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypedValue {
@@ -32,7 +32,6 @@ pub fn core_typed_values() -> Assoc<Name, TypedValue> {
                     "param" => [{"Type" "fn" : "param" => [], "ret" => (vr "F") }],
                     "ret" => (vr "F")} ],
                 "ret" => (vr "F") })},
-            // TODO: built-in functions, even though none of them make sense here, shouldn't crash
             ( Function(cl) ) => {
                 let new_env = cl.env.set(cl.params[0],
                     // reconstruct the invocation that caused this:
@@ -44,25 +43,153 @@ pub fn core_typed_values() -> Assoc<Name, TypedValue> {
                         env: assoc_n!("orig_arg" => Function(cl.clone()),
                                       // TODO: `core_values` does the `map` every time...
                                       "fix" => core_values().find_or_panic(&n("fix")).clone())})));
-                eval(&cl.body, new_env).unwrap() // TODO: should be able to return `Result`
+                eval(&cl.body, new_env)?
             }
         ),
         "plus" =>
         tf!([( "Int", "Int" ) -> "Int"],
-             ( Int(a), Int(b) ) => { Int( a.clone() + b ) }),
+             ( Int(a), Int(b) ) => Int( a.clone() + b ) ),
         "minus" =>
         tf!([( "Int", "Int" ) -> "Int"],
-             ( Int(a), Int(b) ) => { Int( a.clone() - b ) }),
+             ( Int(a), Int(b) ) => Int( a.clone() - b ) ),
         "times" =>
         tf!([( "Int", "Int" ) -> "Int"],
-             ( Int(a), Int(b) ) => { Int( a.clone() * b ) }),
+             ( Int(a), Int(b) ) => Int( a.clone() * b ) ),
         "zero?" =>
         tyf!( {"Type" "fn" : "param" => [ {"Type" "Int" :}], "ret" => (vr "Bool") },
-              ( Int(a) ) => { val!(b   a == BigInt::from(0))}),
+              ( Int(a) ) => val!(b   a == BigInt::from(0)) ),
         "equal?" =>
         tyf!( {"Type" "fn" : "param" => [ {"Type" "Int" :}, {"Type" "Int" :} ],
                              "ret" => (vr "Bool")},
-              ( Int(a), Int(b) ) => { val!(b a == b)} ),
+              ( Int(a), Int(b) ) => val!(b a == b)  ),
+        // `div` rounds down (not toward zero), and `mod` has the sign of the divisor,
+        //  so that `(plus (times (div a b) b) (mod a b))` is always `a`.
+        "div" =>
+        tf!([( "Int", "Int" ) -> "Int"],
+             ( Int(a), Int(b) ) => Int( a.div_floor(&nonzero(b)?) ) ),
+        "mod" =>
+        tf!([( "Int", "Int" ) -> "Int"],
+             ( Int(a), Int(b) ) => Int( a.mod_floor(&nonzero(b)?) ) ),
+        "negate" =>
+        tf!([( "Int" ) -> "Int"],
+             ( Int(a) ) => Int( -a ) ),
+        "abs" =>
+        tf!([( "Int" ) -> "Int"],
+             ( Int(a) ) => Int( a.abs() ) ),
+        "pow" =>
+        tf!([( "Int", "Int" ) -> "Int"],
+             ( Int(a), Int(b) ) => {
+                 // Only bother limiting the exponent if the result would be huge:
+                 let max = match a.bits() {
+                     0 | 1 => usize::MAX,
+                     bits => MAX_RESULT_BITS / (bits - 1),
+                 };
+                 Int( num::pow::pow(a, up_to(b, max)?) )
+             }),
+        "less?" =>
+        tyf!( {"Type" "fn" : "param" => [ {"Type" "Int" :}, {"Type" "Int" :} ],
+                             "ret" => (vr "Bool")},
+              ( Int(a), Int(b) ) => val!(b a < b) ),
+        "greater?" =>
+        tyf!( {"Type" "fn" : "param" => [ {"Type" "Int" :}, {"Type" "Int" :} ],
+                             "ret" => (vr "Bool")},
+              ( Int(a), Int(b) ) => val!(b a > b) ),
+        "less_or_equal?" =>
+        tyf!( {"Type" "fn" : "param" => [ {"Type" "Int" :}, {"Type" "Int" :} ],
+                             "ret" => (vr "Bool")},
+              ( Int(a), Int(b) ) => val!(b a <= b) ),
+        "greater_or_equal?" =>
+        tyf!( {"Type" "fn" : "param" => [ {"Type" "Int" :}, {"Type" "Int" :} ],
+                             "ret" => (vr "Bool")},
+              ( Int(a), Int(b) ) => val!(b a >= b) ),
+        // Bitwise operations act as if on infinite two's-complement representations.
+        "bit_and" =>
+        tf!([( "Int", "Int" ) -> "Int"],
+             ( Int(a), Int(b) ) => Int( a & b ) ),
+        "bit_or" =>
+        tf!([( "Int", "Int" ) -> "Int"],
+             ( Int(a), Int(b) ) => Int( a | b ) ),
+        "bit_xor" =>
+        tf!([( "Int", "Int" ) -> "Int"],
+             ( Int(a), Int(b) ) => Int( a ^ b ) ),
+        "shift_left" =>
+        tf!([( "Int", "Int" ) -> "Int"],
+             ( Int(a), Int(b) ) => Int( a << up_to(b, MAX_RESULT_BITS)? ) ),
+        "shift_right" =>
+        tf!([( "Int", "Int" ) -> "Int"],
+             ( Int(a), Int(b) ) => Int( a >> up_to(b, usize::MAX)? ) ),
+        "string_concat" =>
+        tf!([( "String", "String" ) -> "String"],
+             ( Text(a), Text(b) ) => Text( a + &b ) ),
+        "string_length" =>
+        tf!([( "String" ) -> "Int"],
+             ( Text(a) ) => val!(i a.chars().count()) ),
+        // Indices count `char`s, not bytes. The range is half-open.
+        "substring" =>
+        tf!([( "String", "Int", "Int" ) -> "String"],
+             ( Text(a), Int(begin), Int(end) ) => {
+                 let chars: Vec<char> = a.chars().collect();
+                 let begin = char_index(&a, &chars, &begin)?;
+                 let end = char_index(&a, &chars, &end)?;
+                 if begin > end {
+                     return Err(RuntimeError::new(
+                         RuntimeErr::IndexOutOfBounds(Text(a), val!(i begin))));
+                 }
+                 Text(chars[begin..end].iter().collect())
+             }),
+        "char_code_at" =>
+        tf!([( "String", "Int" ) -> "Int"],
+             ( Text(a), Int(idx) ) => {
+                 let chars: Vec<char> = a.chars().collect();
+                 match chars.get(char_index(&a, &chars, &idx)?) {
+                     Some(c) => val!(i *c as u32),
+                     None => return Err(RuntimeError::new(
+                         RuntimeErr::IndexOutOfBounds(Text(a), Int(idx)))),
+                 }
+             }),
+        "from_char_code" =>
+        tf!([( "Int" ) -> "String"],
+             ( Int(code) ) => {
+                 match code.to_u32().and_then(std::char::from_u32) {
+                     Some(c) => Text(c.to_string()),
+                     None => return Err(RuntimeError::new(
+                         RuntimeErr::BifArgMismatch(vec![Int(code)]))),
+                 }
+             }),
+        "string_equal?" =>
+        tyf!( {"Type" "fn" : "param" => [ {"Type" "String" :}, {"Type" "String" :} ],
+                             "ret" => (vr "Bool")},
+              ( Text(a), Text(b) ) => val!(b a == b) ),
+        "string_less?" =>
+        tyf!( {"Type" "fn" : "param" => [ {"Type" "String" :}, {"Type" "String" :} ],
+                             "ret" => (vr "Bool")},
+              ( Text(a), Text(b) ) => val!(b a < b) ),
+        "int_to_string" =>
+        tf!([( "Int" ) -> "String"],
+             ( Int(a) ) => Text(a.to_string()) ),
+        "string_to_int" =>
+        tf!([( "String" ) -> "Int"],
+             ( Text(a) ) => {
+                 match a.trim().parse::<BigInt>() {
+                     Ok(i) => Int(i),
+                     Err(_) => return Err(RuntimeError::new(
+                         RuntimeErr::BifArgMismatch(vec![Text(a)]))),
+                 }
+             }),
+        // Structural equality and ordering, for anything but functions:
+        "eq?" =>
+        tyf!( { "Type" "forall_type" :
+            "param" => ["T"],
+            "body" => (import [* [forall "param"]] { "Type" "fn" :
+                "param" => [(vr "T"), (vr "T")], "ret" => (vr "Bool") })},
+            ( a, b ) => val!(b compare_values(&a, &b)? == Ordering::Equal) ),
+        // Negative, zero, or positive, as the first argument is less, equal, or greater:
+        "compare" =>
+        tyf!( { "Type" "forall_type" :
+            "param" => ["T"],
+            "body" => (import [* [forall "param"]] { "Type" "fn" :
+                "param" => [(vr "T"), (vr "T")], "ret" => {"Type" "Int" :} })},
+            ( a, b ) => val!(i compare_values(&a, &b)? as i32) ),
         "zero" => tf!( "Int", val!(i 0) ),
         "one" => tf!( "Int", val!(i 1) ),
         "two" => tf!( "Int", val!(i 2) ),
@@ -77,6 +204,157 @@ pub fn core_typed_values() -> Assoc<Name, TypedValue> {
         "false" => TypedValue { ty: ast!((vr "Bool")), val: val!(b false)},
         "true" => TypedValue { ty: ast!((vr "Bool")), val: val!(b true)}
     )
+    .set_assoc(&crate::runtime::numbers::numeric_typed_values())
+}
+
+/// Errors if either is (or contains) a function.
+/// Syntax is compared up to alpha-equivalence: it's ordered by how it prints
+///  once its binders have been given canonical names.
+/// Enum values are ordered by the names of their choices.
+fn compare_values(lhs: &Value, rhs: &Value) -> Result<Ordering, RuntimeError> {
+    fn check_comparable(v: &Value) -> Result<(), RuntimeError> {
+        match *v {
+            Function(_) | BuiltInFunction(_) => {
+                Err(RuntimeError::new(RuntimeErr::Incomparable(v.clone())))
+            }
+            Sequence(ref elts) => elts.iter().try_for_each(|elt| check_comparable(elt)),
+            Struct(ref components) => components.iter_values().try_for_each(check_comparable),
+            Enum(_, ref args) => args.iter().try_for_each(check_comparable),
+            Int(_) | Float(_) | Signed(_, _) | Unsigned(_, _) | Text(_) | AbstractSyntax(_) => {
+                Ok(())
+            }
+        }
+    }
+    fn cmp_all<'a>(
+        lhs: impl Iterator<Item = &'a Value>,
+        rhs: impl Iterator<Item = &'a Value>,
+    ) -> Ordering {
+        let mut rhs = rhs;
+        for l in lhs {
+            match rhs.next() {
+                None => return Ordering::Greater,
+                Some(r) => match cmp(l, r) {
+                    Ordering::Equal => {}
+                    unequal => return unequal,
+                },
+            }
+        }
+        if rhs.next().is_some() {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        }
+    }
+    fn sorted(components: &Assoc<Name, Value>) -> Vec<(String, &Value)> {
+        let mut res: Vec<_> = components.iter_pairs().map(|(k, v)| (k.orig_sp(), v)).collect();
+        res.sort_by(|a, b| a.0.cmp(&b.0));
+        res
+    }
+    // Like `Debug`, but it's the same for `==` syntax (and doesn't decorate names for printing).
+    fn syntax_key(a: &Ast) -> String {
+        use crate::ast::AstContents::*;
+        match *a.c() {
+            Trivial => "⨉".to_owned(),
+            Atom(name) => format!("∘{}∘", name.sp()),
+            VariableReference(name) => name.sp(),
+            Shape(ref v) => format!("({})", v.iter().map(syntax_key).collect::<Vec<_>>().join(" ")),
+            Node(ref form, ref parts, ref export) => format!(
+                "{{({}); {} ⇑{:?}}}",
+                form.name.sp(),
+                parts.canonical_description(&syntax_key),
+                export
+            ),
+            IncompleteNode(ref parts) => {
+                format!("{{INCOMPLETE; {}}}", parts.canonical_description(&syntax_key))
+            }
+            QuoteMore(ref body, pos) => format!("``{} {}``", pos, syntax_key(body)),
+            QuoteLess(ref body, depth) => format!(",,({}){},,", depth, syntax_key(body)),
+            ExtendEnv(ref body, ref beta) => format!("{}↓{:?}", syntax_key(body), beta),
+            ExtendEnvPhaseless(ref body, ref beta) => format!("{}±↓{:?}", syntax_key(body), beta),
+        }
+    }
+    fn cmp(lhs: &Value, rhs: &Value) -> Ordering {
+        match (lhs, rhs) {
+            (Int(l), Int(r)) => l.cmp(r),
+            (Signed(_, l), Signed(_, r)) => l.cmp(r),
+            (Unsigned(_, l), Unsigned(_, r)) => l.cmp(r),
+            // NaNs are all equal, and greater than everything else.
+            (Float(l), Float(r)) => {
+                l.partial_cmp(r).unwrap_or_else(|| l.is_nan().cmp(&r.is_nan()))
+            }
+            (Text(l), Text(r)) => l.cmp(r),
+            (Sequence(l), Sequence(r)) => cmp_all(l.iter().map(|v| &**v), r.iter().map(|v| &**v)),
+            (Enum(l_choice, l_args), Enum(r_choice, r_args)) => {
+                l_choice.orig_sp().cmp(&r_choice.orig_sp()).then_with(|| {
+                    cmp_all(l_args.iter(), r_args.iter())
+                })
+            }
+            (Struct(l), Struct(r)) => {
+                let (l, r) = (sorted(l), sorted(r));
+                l.iter()
+                    .map(|c| &c.0)
+                    .cmp(r.iter().map(|c| &c.0))
+                    .then_with(|| cmp_all(l.iter().map(|c| c.1), r.iter().map(|c| c.1)))
+            }
+            (AbstractSyntax(l), AbstractSyntax(r)) => {
+                let l = crate::alpha::canonical_binders(l);
+                let r = crate::alpha::canonical_binders(r);
+                format!("{}", l)
+                    .cmp(&format!("{}", r))
+                    .then_with(|| syntax_key(&l).cmp(&syntax_key(&r)))
+            }
+            // Only possible if typechecking was skipped:
+            _ => rank(lhs).cmp(&rank(rhs)),
+        }
+    }
+    fn rank(v: &Value) -> usize {
+        match *v {
+            Int(_) => 0,
+            Signed(_, _) => 1,
+            Unsigned(_, _) => 2,
+            Float(_) => 3,
+            Text(_) => 4,
+            Sequence(_) => 5,
+            Enum(_, _) => 6,
+            Struct(_) => 7,
+            AbstractSyntax(_) => 8,
+            Function(_) | BuiltInFunction(_) => 9,
+        }
+    }
+
+    check_comparable(lhs)?;
+    check_comparable(rhs)?;
+    Ok(cmp(lhs, rhs))
+}
+
+pub(crate) fn nonzero<N: Zero>(i: N) -> Result<N, RuntimeError> {
+    if i.is_zero() {
+        Err(RuntimeError::new(RuntimeErr::DivisionByZero(())))
+    } else {
+        Ok(i)
+    }
+}
+
+/// `pow` and `shift_left` won't produce integers bigger than this (instead of running out of memory).
+const MAX_RESULT_BITS: usize = 1 << 24;
+
+/// For exponents and shift amounts, which must be from 0 to `max`.
+fn up_to(i: BigInt, max: usize) -> Result<usize, RuntimeError> {
+    match i.to_usize() {
+        Some(i) if i <= max => Ok(i),
+        _ => Err(RuntimeError::new(RuntimeErr::ArgOutOfRange(Int(i), max))),
+    }
+}
+
+/// Check that `idx` is a valid position (possibly the end) in `chars`, which came from `s`.
+fn char_index(s: &str, chars: &[char], idx: &BigInt) -> Result<usize, RuntimeError> {
+    match idx.to_usize() {
+        Some(i) if i <= chars.len() => Ok(i),
+        _ => Err(RuntimeError::new(RuntimeErr::IndexOutOfBounds(
+            Text(s.to_owned()),
+            Int(idx.clone()),
+        ))),
+    }
 }
 
 pub fn core_values() -> Assoc<Name, Value> { core_typed_values().map(&erase_type) }
@@ -95,10 +373,7 @@ pub fn core_types() -> Assoc<Name, Ty> {
     };
     core_typed_values()
         .map(&erase_value)
-        .set(
-            n("Bool"),
-            ty!({"Type" "enum" : "name" => [@"c" "True", "False"], "component" => [@"c" [], []]}),
-        )
+        .set(n("Bool"), Ty(bool::ty()))
         // These need to be in the environment, not just atomic types
         //  because we sometimes look them up internally in the compiler
         //   in the environment,
@@ -113,6 +388,7 @@ pub fn core_types() -> Assoc<Name, Ty> {
             crate::util::mbe::EnvMBE<Irr>,
             Name, crate::ast::Ast, crate::beta::Beta, crate::beta::ExportBeta,
             crate::grammar::FormPat, crate::grammar::SyntaxExtension, crate::grammar::Scanner,
+            crate::grammar::Associativity,
             crate::form::Form, crate::form::EitherPN<Irr, Irr>, crate::ast_walk::WalkRule<Irr>,
             crate::runtime::eval::QQuote, crate::runtime::eval::QQuoteDestr,
             crate::runtime::eval::Eval, crate::runtime::eval::Destructure,
@@ -5,6 +5,7 @@ use crate::{
     ast_walk::{walk, LazyWalkReses, WalkRule},
     form::Form,
     name::*,
+    runtime::numbers::IntWidth,
     util::assoc::Assoc,
     walk_mode::{NegativeWalkMode, WalkMode},
 };
@@ -16,6 +17,11 @@ use std::{self, rc::Rc};
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Int(BigInt),
+    Float(f64),
+    // Fixed-width integers (always in range for the width):
+    Signed(IntWidth, i64),
+    Unsigned(IntWidth, u64),
+    Text(String),
     Sequence(Vec<Rc<Value>>), // TODO: switch to a different core sequence type
     Function(Rc<Closure>),    // TODO: unsure if this Rc is needed
     BuiltInFunction(BIF),
@@ -34,10 +40,11 @@ pub struct Closure {
 }
 
 // Built-in function
-pub struct BIF(pub Rc<(dyn Fn(Vec<Value>) -> Value)>);
+#[allow(clippy::upper_case_acronyms)] // (it's pronounced "biff")
+pub struct BIF(pub Rc<dyn Fn(Vec<Value>) -> Result<Value, RuntimeError>>);
 
 impl PartialEq for BIF {
-    fn eq(&self, other: &BIF) -> bool { self as *const BIF == other as *const BIF }
+    fn eq(&self, other: &BIF) -> bool { std::ptr::eq(self, other) }
 }
 
 impl Clone for BIF {
@@ -48,9 +55,13 @@ impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         match *self {
             Int(ref bi) => write!(f, "{}", bi),
+            Float(fl) => write!(f, "{:?}", fl),
+            Signed(w, i) => write!(f, "{}{}", i, w.suffix()),
+            Unsigned(w, i) => write!(f, "{}{}", i, w.suffix()),
+            Text(ref s) => write!(f, "{:?}", s),
             Sequence(ref seq) => {
                 for elt in seq {
-                    write!(f, "{}", &*elt)?;
+                    write!(f, "{}", elt)?;
                 }
                 Ok(())
             }
@@ -75,6 +86,159 @@ impl std::fmt::Display for Value {
     }
 }
 
+custom_derive! {
+    #[derive(Reifiable, Clone, PartialEq)]
+    pub enum RuntimeErr {
+        /// No arm of a `match` accepted the scrutinee.
+        MatchFailure(Value),
+        /// A pattern didn't fit a value. (`match` catches this to try the next arm.)
+        PatternMismatch(Value),
+        UnboundVar(Name),
+        /// A built-in function was passed arguments it doesn't handle.
+        BifArgMismatch(Vec<Value>),
+        /// A function that takes some number of arguments was passed these instead.
+        ArgCountMismatch(usize, Vec<Value>),
+        /// Something other than a function was invoked.
+        NotAFunction(Value),
+        /// An index (the second `Value`) was out of range for the first `Value`.
+        IndexOutOfBounds(Value, Value),
+        /// Functions can't be compared (by `eq?` or `compare`).
+        Incomparable(Value),
+        /// The (exact) result of fixed-width arithmetic didn't fit in the type named.
+        Overflow(Value, Name),
+        /// An argument had to be from 0 to the number given (inclusive).
+        ArgOutOfRange(Value, usize),
+        // TODO: the reification macros can't handle empty `enum` cases. Fix that!
+        DivisionByZero(())
+    }
+}
+
+impl RuntimeErr {
+    /// The name of the variant; diagnostics use it as an error code.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            RuntimeErr::MatchFailure(_) => "MatchFailure",
+            RuntimeErr::PatternMismatch(_) => "PatternMismatch",
+            RuntimeErr::UnboundVar(_) => "UnboundVar",
+            RuntimeErr::BifArgMismatch(_) => "BifArgMismatch",
+            RuntimeErr::ArgCountMismatch(_, _) => "ArgCountMismatch",
+            RuntimeErr::NotAFunction(_) => "NotAFunction",
+            RuntimeErr::IndexOutOfBounds(_, _) => "IndexOutOfBounds",
+            RuntimeErr::Incomparable(_) => "Incomparable",
+            RuntimeErr::Overflow(_, _) => "Overflow",
+            RuntimeErr::ArgOutOfRange(_, _) => "ArgOutOfRange",
+            RuntimeErr::DivisionByZero(()) => "DivisionByZero",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match *self {
+            RuntimeErr::MatchFailure(ref v) => format!("no arm matched `{}`", v),
+            RuntimeErr::PatternMismatch(ref v) => format!("pattern doesn't match `{}`", v),
+            RuntimeErr::UnboundVar(name) => format!("`{}` is not defined", name),
+            RuntimeErr::BifArgMismatch(ref args) => format!(
+                "built-in function can't handle the arguments ({})",
+                args.iter().map(|a| format!("{}", a)).collect::<Vec<_>>().join(" ")
+            ),
+            RuntimeErr::ArgCountMismatch(expected, ref args) => format!(
+                "expected {} arguments, but got {} ({})",
+                expected,
+                args.len(),
+                args.iter().map(|a| format!("{}", a)).collect::<Vec<_>>().join(" ")
+            ),
+            RuntimeErr::NotAFunction(ref v) => format!("`{}` is not a function", v),
+            RuntimeErr::IndexOutOfBounds(ref v, ref idx) => {
+                format!("index {} is out of bounds for `{}`", idx, v)
+            }
+            RuntimeErr::Incomparable(ref v) => {
+                format!("`{}` is a function; it can't be compared", v)
+            }
+            RuntimeErr::Overflow(ref v, ty) => format!("`{}` doesn't fit in `{}`", v, ty),
+            RuntimeErr::ArgOutOfRange(ref v, max) => {
+                format!("`{}` is out of range (it must be from 0 to {})", v, max)
+            }
+            RuntimeErr::DivisionByZero(()) => "division by zero".to_owned(),
+        }
+    }
+}
+
+impl std::fmt::Display for RuntimeErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "[{}] {}", self.code(), self.message())
+    }
+}
+
+custom_derive! {
+    /// A `RuntimeErr`, where it happened (if known yet; built-in functions don't know),
+    ///  and the Unseemly call sites it propagated through, innermost first.
+    #[derive(Reifiable, Clone, PartialEq)]
+    pub struct RuntimeError {
+        pub body: RuntimeErr,
+        pub loc: Option<Ast>,
+        pub stack: Vec<Ast>
+    }
+}
+
+impl RuntimeError {
+    pub fn new(body: RuntimeErr) -> RuntimeError { RuntimeError { body, loc: None, stack: vec![] } }
+
+    pub fn at(body: RuntimeErr, loc: &Ast) -> RuntimeError {
+        RuntimeError { body, loc: Some(loc.clone()), stack: vec![] }
+    }
+
+    /// Note that the error has propagated out of a call at `call_site`.
+    /// (Errors that don't have a location yet come from built-ins, and happened right there.)
+    pub fn called_from(mut self, call_site: &Ast) -> RuntimeError {
+        if self.loc.is_none() {
+            self.loc = Some(call_site.clone())
+        } else {
+            self.stack.push(call_site.clone())
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.body)?;
+        if let Some(ref loc) = self.loc {
+            write!(f, " at {}", loc.describe_location())?;
+        }
+        for call_site in &self.stack {
+            write!(f, "\n  called from {}", call_site.describe_location())?;
+        }
+        Ok(())
+    }
+}
+
+// Force pretty version
+impl std::fmt::Debug for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self)
+    }
+}
+
+impl crate::util::diagnostics::Diagnose for RuntimeError {
+    fn diagnostic(&self) -> crate::util::diagnostics::Diagnostic {
+        // Deep recursion makes for long stacks; the innermost frames are the interesting ones.
+        const MAX_FRAMES: usize = 8;
+
+        let span = self.loc.as_ref().and_then(Ast::some_loc);
+        let mut res = crate::util::diagnostics::Diagnostic::new(self.body.message(), span)
+            .code(self.body.code());
+        for call_site in self.stack.iter().take(MAX_FRAMES) {
+            res = match call_site.some_loc() {
+                Some(span) => res.note("called from here".to_owned(), Some(span)),
+                None => res.note(format!("called from `{}`", call_site), None),
+            }
+        }
+        if self.stack.len() > MAX_FRAMES {
+            res = res.note(format!("... and {} more calls", self.stack.len() - MAX_FRAMES), None);
+        }
+        res
+    }
+}
+
 impl std::fmt::Debug for BIF {
     fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         formatter.write_str("[built-in function]")
@@ -107,7 +271,7 @@ impl WalkMode for Eval {
     type Negated = Destructure;
     type AsPositive = Eval;
     type AsNegative = Destructure;
-    type Err = ();
+    type Err = RuntimeError;
     type D = crate::walk_mode::Positive<Eval>;
     type ExtraInfo = ();
 
@@ -120,10 +284,10 @@ impl WalkMode for Eval {
     }
     fn automatically_extend_env() -> bool { true }
 
-    fn walk_var(n: Name, cnc: &LazyWalkReses<Eval>) -> Result<Value, ()> {
+    fn walk_var(n: Name, cnc: &LazyWalkReses<Eval>) -> Result<Value, RuntimeError> {
         match cnc.env.find(&n) {
             Some(v) => Ok(v.clone()),
-            None => panic!("Undefined var `{}` in {}", n, cnc.env),
+            None => Err(RuntimeError::at(RuntimeErr::UnboundVar(n), &cnc.this_ast)),
         }
     }
 
@@ -138,7 +302,7 @@ impl WalkMode for Destructure {
     type Negated = Eval;
     type AsPositive = Eval;
     type AsNegative = Destructure;
-    type Err = ();
+    type Err = RuntimeError;
     type D = crate::walk_mode::Negative<Destructure>;
     type ExtraInfo = ();
 
@@ -150,6 +314,10 @@ impl WalkMode for Destructure {
 }
 
 impl NegativeWalkMode for Destructure {
+    fn qlit_mismatch_error(got: Value, _expd: Value) -> RuntimeError {
+        RuntimeError::new(RuntimeErr::PatternMismatch(got))
+    }
+
     fn needs_pre_match() -> bool { false } // Values don't have binding (in this mode!)
 }
 
@@ -158,13 +326,17 @@ impl crate::walk_mode::WalkElt for Ast {
     fn to_ast(&self) -> Ast { self.clone() }
 }
 
-pub fn eval_top(expr: &Ast) -> Result<Value, ()> { eval(expr, Assoc::new()) }
+pub fn eval_top(expr: &Ast) -> Result<Value, RuntimeError> { eval(expr, Assoc::new()) }
 
-pub fn eval(expr: &Ast, env: Assoc<Name, Value>) -> Result<Value, ()> {
+pub fn eval(expr: &Ast, env: Assoc<Name, Value>) -> Result<Value, RuntimeError> {
     walk::<Eval>(expr, &LazyWalkReses::new_wrapper(env))
 }
 
-pub fn neg_eval(pat: &Ast, env: Assoc<Name, Value>) -> Result<Assoc<Name, Value>, ()> {
+pub fn neg_eval(
+    pat: &Ast,
+    env: Assoc<Name, Value>,
+) -> Result<Assoc<Name, Value>, RuntimeError>
+{
     walk::<Destructure>(pat, &LazyWalkReses::new_wrapper(env))
 }
 
@@ -185,15 +357,15 @@ impl WalkMode for QQuote {
     type Negated = QQuoteDestr;
     type AsPositive = QQuote;
     type AsNegative = QQuoteDestr;
-    type Err = ();
+    type Err = RuntimeError;
     type D = crate::walk_mode::Positive<QQuote>;
     type ExtraInfo = ();
 
-    fn walk_var(n: Name, _: &LazyWalkReses<Self>) -> Result<Value, ()> {
-        Ok(val!(ast (, Ast::VariableReference(n))))
+    fn walk_var(n: Name, cnc: &LazyWalkReses<Self>) -> Result<Value, RuntimeError> {
+        Ok(val!(ast (, cnc.this_ast.with_c(crate::ast::VariableReference(n)))))
     }
-    fn walk_atom(n: Name, _: &LazyWalkReses<Self>) -> Result<Value, ()> {
-        Ok(val!(ast (, Ast::Atom(n))))
+    fn walk_atom(n: Name, _: &LazyWalkReses<Self>) -> Result<Value, RuntimeError> {
+        Ok(val!(ast (, raw_ast!(Atom(n)))))
     }
     // TODO #26: Just special-case "unquote" and "dotdotdot"
     fn get_walk_rule(f: &Form) -> WalkRule<QQuote> { f.quasiquote.pos().clone() }
@@ -207,20 +379,20 @@ impl WalkMode for QQuoteDestr {
     type Negated = QQuote;
     type AsPositive = QQuote;
     type AsNegative = QQuoteDestr;
-    type Err = ();
+    type Err = RuntimeError;
     type D = crate::walk_mode::Negative<QQuoteDestr>;
     type ExtraInfo = ();
 
-    fn walk_var(n: Name, cnc: &LazyWalkReses<Self>) -> Result<Assoc<Name, Value>, ()> {
-        let val = val!(ast (, Ast::VariableReference(n)));
+    fn walk_var(n: Name, cnc: &LazyWalkReses<Self>) -> Result<Assoc<Name, Value>, RuntimeError> {
+        let val = val!(ast (, raw_ast!(VariableReference(n))));
         if cnc.context_elt() == &val {
             Ok(Assoc::<Name, Value>::new())
         } else {
             Err(Self::qlit_mismatch_error(val, cnc.context_elt().clone()))
         }
     }
-    fn walk_atom(n: Name, cnc: &LazyWalkReses<Self>) -> Result<Assoc<Name, Value>, ()> {
-        let val = val!(ast (, Ast::Atom(n)));
+    fn walk_atom(n: Name, cnc: &LazyWalkReses<Self>) -> Result<Assoc<Name, Value>, RuntimeError> {
+        let val = val!(ast (, raw_ast!(Atom(n))));
         if cnc.context_elt() == &val {
             Ok(Assoc::<Name, Value>::new())
         } else {
@@ -233,11 +405,15 @@ impl WalkMode for QQuoteDestr {
 }
 
 impl NegativeWalkMode for QQuoteDestr {
+    fn qlit_mismatch_error(got: Value, _expd: Value) -> RuntimeError {
+        RuntimeError::new(RuntimeErr::PatternMismatch(got))
+    }
+
     fn needs_pre_match() -> bool { true } // Quoted syntax does have binding!
 }
 
 // `env` is a trap! We want a shifted `LazyWalkReses`!
-// pub fn qquote(expr: &Ast, env: Assoc<Name, Value>) -> Result<Value, ()> {
+// pub fn qquote(expr: &Ast, env: Assoc<Name, Value>) -> Result<Value, RuntimeError> {
 //     walk::<QQuote>(expr, &LazyWalkReses::new_wrapper(env))
 // }
 //
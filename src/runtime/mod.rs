@@ -1,3 +1,4 @@
 pub mod core_values;
 pub mod eval;
+pub mod numbers;
 pub mod reify;
@@ -0,0 +1,271 @@
+// Fixed-width integers and floats, for code that wants machine arithmetic instead of `Int`'s.
+//
+// Fixed-width integers are native `i64`s and `u64`s (narrower widths are range-checked),
+//  and their arithmetic is checked: a result that doesn't fit is a runtime error.
+// (`wrap_to_i32` and friends are there for anyone who wants modular arithmetic.)
+// There are no implicit conversions; `int_to_i32`, `i32_to_int`, `int_to_float`, etc. are explicit.
+
+use crate::{
+    ast::Ast,
+    core_forms::find_core_form,
+    name::*,
+    runtime::{
+        core_values::{nonzero, TypedValue},
+        eval::{
+            RuntimeErr, RuntimeError,
+            Value::{self, *},
+            BIF,
+        },
+    },
+    util::assoc::Assoc,
+};
+use num::{BigInt, FromPrimitive, Integer, One, ToPrimitive};
+use std::rc::Rc;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IntWidth {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntWidth {
+    pub const ALL: [IntWidth; 8] = [
+        IntWidth::I8,
+        IntWidth::I16,
+        IntWidth::I32,
+        IntWidth::I64,
+        IntWidth::U8,
+        IntWidth::U16,
+        IntWidth::U32,
+        IntWidth::U64,
+    ];
+
+    /// The name of the type, e.g. `I32`.
+    pub fn type_name(self) -> &'static str {
+        match self {
+            IntWidth::I8 => "I8",
+            IntWidth::I16 => "I16",
+            IntWidth::I32 => "I32",
+            IntWidth::I64 => "I64",
+            IntWidth::U8 => "U8",
+            IntWidth::U16 => "U16",
+            IntWidth::U32 => "U32",
+            IntWidth::U64 => "U64",
+        }
+    }
+
+    /// The literal suffix (and built-in function prefix), e.g. `i32`.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            IntWidth::I8 => "i8",
+            IntWidth::I16 => "i16",
+            IntWidth::I32 => "i32",
+            IntWidth::I64 => "i64",
+            IntWidth::U8 => "u8",
+            IntWidth::U16 => "u16",
+            IntWidth::U32 => "u32",
+            IntWidth::U64 => "u64",
+        }
+    }
+
+    pub fn from_suffix(suffix: &str) -> Option<IntWidth> {
+        IntWidth::ALL.iter().cloned().find(|w| w.suffix() == suffix)
+    }
+
+    pub fn bits(self) -> u32 {
+        match self {
+            IntWidth::I8 | IntWidth::U8 => 8,
+            IntWidth::I16 | IntWidth::U16 => 16,
+            IntWidth::I32 | IntWidth::U32 => 32,
+            IntWidth::I64 | IntWidth::U64 => 64,
+        }
+    }
+
+    pub fn signed(self) -> bool {
+        matches!(self, IntWidth::I8 | IntWidth::I16 | IntWidth::I32 | IntWidth::I64)
+    }
+
+    pub fn min(self) -> i128 {
+        if self.signed() {
+            -(1 << (self.bits() - 1))
+        } else {
+            0
+        }
+    }
+
+    pub fn max(self) -> i128 {
+        if self.signed() {
+            (1 << (self.bits() - 1)) - 1
+        } else {
+            (1 << self.bits()) - 1
+        }
+    }
+
+    pub fn fits(self, i: &BigInt) -> bool {
+        BigInt::from(self.min()) <= *i && *i <= BigInt::from(self.max())
+    }
+
+    /// `i` as a value of this width, if it fits.
+    pub fn value(self, i: &BigInt) -> Option<Value> {
+        i.to_i128().and_then(|i| self.native_value(i))
+    }
+
+    fn native_value(self, i: i128) -> Option<Value> {
+        if i < self.min() || i > self.max() {
+            None
+        } else if self.signed() {
+            Some(Signed(self, i as i64))
+        } else {
+            Some(Unsigned(self, i as u64))
+        }
+    }
+
+    /// `i`, modulo 2^bits, as two's complement (if signed).
+    pub fn wrap(self, i: &BigInt) -> BigInt {
+        let modulus = BigInt::one() << self.bits() as usize;
+        let res = i.mod_floor(&modulus);
+        if res > BigInt::from(self.max()) {
+            res - modulus
+        } else {
+            res
+        }
+    }
+
+    fn ty(self) -> Ast { ast!({ find_core_form("Type", self.type_name()) ; }) }
+}
+
+fn overflow(w: IntWidth, exact: BigInt) -> RuntimeError {
+    RuntimeError::new(RuntimeErr::Overflow(Int(exact), n(w.type_name())))
+}
+
+/// Checked construction of a fixed-width value.
+fn fixed(w: IntWidth, exact: BigInt) -> Result<Value, RuntimeError> {
+    w.value(&exact).ok_or_else(|| overflow(w, exact))
+}
+
+/// The result of native arithmetic: `res`, unless it overflowed (`None`) or doesn't fit in `w`.
+/// `exact` (only computed for the error) is what the result should have been.
+fn checked<N: Into<i128>>(
+    w: IntWidth,
+    res: Option<N>,
+    exact: impl FnOnce() -> BigInt,
+) -> Result<Value, RuntimeError>
+{
+    res.and_then(|res| w.native_value(res.into())).ok_or_else(|| overflow(w, exact()))
+}
+
+/// The operations on one of the native representations of fixed-width integers.
+/// (`$v` is `Signed` or `Unsigned`.)
+macro_rules! fixed_ops {
+    ($w:expr, $v:ident, $binary:expr, $predicate:expr, $to_int:expr) => {{
+        let w = $w;
+        let big = |i| BigInt::from(i);
+        vec![
+            ("plus", $binary, core_fn!($v(_, a), $v(_, b) =>
+                checked(w, a.checked_add(b), || big(a) + big(b))?)),
+            ("minus", $binary, core_fn!($v(_, a), $v(_, b) =>
+                checked(w, a.checked_sub(b), || big(a) - big(b))?)),
+            ("times", $binary, core_fn!($v(_, a), $v(_, b) =>
+                checked(w, a.checked_mul(b), || big(a) * big(b))?)),
+            // Like `div` and `mod` on `Int`s, these round down.
+            ("div", $binary, core_fn!($v(_, a), $v(_, b) =>
+                checked(w, a.checked_div(nonzero(b)?).map(|_| Integer::div_floor(&a, &b)),
+                        || big(a).div_floor(&big(b)))?)),
+            // (The only way for `checked_rem` to overflow is `MIN % -1`, which is 0.)
+            ("mod", $binary, core_fn!($v(_, a), $v(_, b) => {
+                let rem = a.checked_rem(nonzero(b)?).map_or(0, |_| Integer::mod_floor(&a, &b));
+                checked(w, Some(rem), || big(a).mod_floor(&big(b)))?
+            })),
+            ("less?", $predicate, core_fn!($v(_, a), $v(_, b) => val!(b a < b))),
+            ("equal?", $predicate, core_fn!($v(_, a), $v(_, b) => val!(b a == b))),
+            ("to_int", $to_int, core_fn!($v(_, a) => Int(big(a)))),
+        ]
+    }};
+}
+
+fn fn_type(params: Vec<Ast>, ret: Ast) -> Ast {
+    ast!({ find_core_form("Type", "fn") ; "param" => (,seq params), "ret" => (, ret) })
+}
+
+fn bool_type() -> Ast { ast!((vr "Bool")) }
+
+fn int_type() -> Ast { ast!({ "Type" "Int" : }) }
+
+/// The arithmetic, comparison, and conversion functions for fixed-width integers and floats.
+pub fn numeric_typed_values() -> Assoc<Name, TypedValue> {
+    let mut res = Assoc::new();
+
+    for &w in IntWidth::ALL.iter() {
+        let binary = || fn_type(vec![w.ty(), w.ty()], w.ty());
+        let predicate = || fn_type(vec![w.ty(), w.ty()], bool_type());
+        let to_int = || fn_type(vec![w.ty()], int_type());
+        let ops: Vec<(&str, Ast, Value)> = if w.signed() {
+            fixed_ops!(w, Signed, binary(), predicate(), to_int())
+        } else {
+            fixed_ops!(w, Unsigned, binary(), predicate(), to_int())
+        };
+        for (op, ty, val) in ops {
+            res = res.set(n(&format!("{}_{}", w.suffix(), op)), TypedValue { ty, val });
+        }
+        res = res.set(
+            n(&format!("int_to_{}", w.suffix())),
+            TypedValue {
+                ty: fn_type(vec![int_type()], w.ty()),
+                val: core_fn!(Int(a) => fixed(w, a)?),
+            },
+        );
+        res = res.set(
+            n(&format!("wrap_to_{}", w.suffix())),
+            TypedValue {
+                ty: fn_type(vec![int_type()], w.ty()),
+                val: core_fn!(Int(a) => fixed(w, w.wrap(&a))?),
+            },
+        );
+    }
+
+    res.set_assoc(&assoc_n!(
+        "float_plus" =>
+        tf!([( "Float", "Float" ) -> "Float"],
+             ( Float(a), Float(b) ) => Float(a + b) ),
+        "float_minus" =>
+        tf!([( "Float", "Float" ) -> "Float"],
+             ( Float(a), Float(b) ) => Float(a - b) ),
+        "float_times" =>
+        tf!([( "Float", "Float" ) -> "Float"],
+             ( Float(a), Float(b) ) => Float(a * b) ),
+        // IEEE 754 semantics: dividing by zero produces an infinity or NaN, not an error.
+        "float_div" =>
+        tf!([( "Float", "Float" ) -> "Float"],
+             ( Float(a), Float(b) ) => Float(a / b) ),
+        "float_less?" =>
+        tyf!( {"Type" "fn" : "param" => [ {"Type" "Float" :}, {"Type" "Float" :} ],
+                             "ret" => (vr "Bool")},
+              ( Float(a), Float(b) ) => val!(b a < b) ),
+        "float_equal?" =>
+        tyf!( {"Type" "fn" : "param" => [ {"Type" "Float" :}, {"Type" "Float" :} ],
+                             "ret" => (vr "Bool")},
+              ( Float(a), Float(b) ) => val!(b a == b) ),
+        "int_to_float" =>
+        tf!([( "Int" ) -> "Float"],
+             ( Int(a) ) => Float(a.to_f64().unwrap_or(f64::NAN)) ),
+        // Rounds toward zero. NaN and the infinities don't fit in an `Int`.
+        "float_to_int" =>
+        tf!([( "Float" ) -> "Int"],
+             ( Float(a) ) => {
+                 match BigInt::from_f64(a.trunc()) {
+                     Some(i) => Int(i),
+                     None => return Err(RuntimeError::new(
+                         RuntimeErr::Overflow(Float(a), n("Int")))),
+                 }
+             }),
+        "float_to_string" =>
+        tf!([( "Float" ) -> "String"],
+             ( Float(a) ) => Text(format!("{:?}", a)) )
+    ))
+}
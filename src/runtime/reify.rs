@@ -1,5 +1,9 @@
 // Designed for `use reify::*`
-pub use crate::{ast::Ast, name::*, runtime::eval::Value};
+pub use crate::{
+    ast::{Ast, AstContents::*},
+    name::*,
+    runtime::eval::Value,
+};
 
 use crate::{runtime::eval, util::assoc::Assoc};
 
@@ -20,7 +24,6 @@ use std::rc::Rc;
 /// Then, we can use `T::<SomeActualArg, OtherActualArg>::ty_invocation()` in that environment.
 ///
 /// This is also where ICPs can happen, so make sure that ::ty() is consistent with ::reify().
-
 pub trait Reifiable {
     /// The Unseemly type that corresponds to to the `Reifiable` type.
     /// This leaves abstract the type parameters of `Self`; invoke like `Self::<Irr,Irr>::ty()`.
@@ -44,7 +47,7 @@ pub trait Reifiable {
     /// (Types using this type will use this, rather than `ty`)
     /// Don't override this.
     fn ty_invocation() -> Ast {
-        let name_ref = Ast::VariableReference(Self::ty_name());
+        let name_ref = raw_ast!(VariableReference(Self::ty_name()));
         match Self::concrete_arguments() {
             None => name_ref,
             Some(args) => ast!({ "Type" "type_apply" :
@@ -62,6 +65,13 @@ pub trait Reifiable {
 
     /// Get a value from an Unseemly value
     fn reflect(_: &Value) -> Self;
+
+    /// Like `reflect`, but `None` (instead of an ICP) if `v` is the wrong kind of value.
+    /// Derived `impl`s check; hand-written ones that don't override this trust the typechecker.
+    fn try_reflect(v: &Value) -> Option<Self>
+    where Self: Sized {
+        Some(Self::reflect(v))
+    }
 }
 
 // Core values
@@ -69,6 +79,8 @@ pub trait Reifiable {
 macro_rules! basic_reifiability {
     ( $underlying_type:ty, $ty_name:tt, $value_name:ident ) => {
         impl Reifiable for $underlying_type {
+            fn ty() -> Ast { ast!({ "Type" $ty_name : }) }
+
             fn ty_name() -> Name { n($ty_name) }
 
             // TODO: can we remove these clones? are they even bad?
@@ -78,6 +90,13 @@ macro_rules! basic_reifiability {
             fn reflect(v: &Value) -> Self {
                 extract!((v) Value::$value_name = (ref i) => i.clone())
             }
+
+            fn try_reflect(v: &Value) -> Option<Self> {
+                match *v {
+                    Value::$value_name(ref i) => Some(i.clone()),
+                    _ => None,
+                }
+            }
         }
     }
 }
@@ -151,8 +170,14 @@ impl crate::walk_mode::NegativeWalkMode for NegIrr {
 }
 
 basic_reifiability!(BigInt, "Int", Int);
+basic_reifiability!(f64, "Float", Float);
+basic_reifiability!(String, "String", Text);
 
 impl Reifiable for bool {
+    fn ty() -> Ast {
+        ast!({"Type" "enum" : "name" => [@"c" "True", "False"], "component" => [@"c" [], []]})
+    }
+
     fn ty_name() -> Name { n("Bool") }
 
     fn reify(&self) -> Value { eval::Value::Enum(n(if *self { "True" } else { "False" }), vec![]) }
@@ -160,11 +185,21 @@ impl Reifiable for bool {
     fn reflect(v: &Value) -> Self {
         extract!((v) Value::Enum = (ref name, _) => name == &n("True"))
     }
+
+    fn try_reflect(v: &Value) -> Option<Self> {
+        match *v {
+            Value::Enum(name, _) if name == n("True") => Some(true),
+            Value::Enum(name, _) if name == n("False") => Some(false),
+            _ => None,
+        }
+    }
 }
 
-// note: operations for these shouldn't have BigInt semantics!
+// Unseemly sees these as `Int`s; `try_reflect` rejects the ones that don't fit.
 impl Reifiable for usize {
-    fn ty_name() -> Name { n("Rust_usize") }
+    fn ty() -> Ast { ast!({ "Type" "Int" : }) }
+
+    fn ty_name() -> Name { n("Int") }
 
     fn reify(&self) -> Value { Value::Int(BigInt::from(*self)) }
 
@@ -172,9 +207,19 @@ impl Reifiable for usize {
         use num::ToPrimitive;
         extract!((v) Value::Int = (ref i) => i.to_usize().unwrap())
     }
+
+    fn try_reflect(v: &Value) -> Option<Self> {
+        use num::ToPrimitive;
+        match *v {
+            Value::Int(ref i) => i.to_usize(),
+            _ => None,
+        }
+    }
 }
 impl Reifiable for i32 {
-    fn ty_name() -> Name { n("Rust_i32") }
+    fn ty() -> Ast { ast!({ "Type" "Int" : }) }
+
+    fn ty_name() -> Name { n("Int") }
 
     fn reify(&self) -> Value { Value::Int(BigInt::from(*self)) }
 
@@ -182,9 +227,19 @@ impl Reifiable for i32 {
         use num::ToPrimitive;
         extract!((v) Value::Int = (ref i) => i.to_i32().unwrap())
     }
+
+    fn try_reflect(v: &Value) -> Option<Self> {
+        use num::ToPrimitive;
+        match *v {
+            Value::Int(ref i) => i.to_i32(),
+            _ => None,
+        }
+    }
 }
 impl Reifiable for u8 {
-    fn ty_name() -> Name { n("Rust_u8") }
+    fn ty() -> Ast { ast!({ "Type" "Int" : }) }
+
+    fn ty_name() -> Name { n("Int") }
 
     fn reify(&self) -> Value { Value::Int(BigInt::from(*self)) }
 
@@ -192,6 +247,14 @@ impl Reifiable for u8 {
         use num::ToPrimitive;
         extract!((v) Value::Int = (ref i) => i.to_u8().unwrap())
     }
+
+    fn try_reflect(v: &Value) -> Option<Self> {
+        use num::ToPrimitive;
+        match *v {
+            Value::Int(ref i) => i.to_u8(),
+            _ => None,
+        }
+    }
 }
 impl Reifiable for () {
     fn ty_name() -> Name { n("Unit") }
@@ -213,19 +276,15 @@ impl<T0: Reifiable, T1: Reifiable> Reifiable for (T0, T1) {
     }
 
     fn reflect(v: &Value) -> Self {
-        extract!((v) Value::Sequence = (ref s) => (T0::reflect(&*s[0]), T1::reflect(&*s[1])))
+        extract!((v) Value::Sequence = (ref s) => (T0::reflect(&s[0]), T1::reflect(&s[1])))
     }
-}
-
-impl Reifiable for String {
-    fn ty_name() -> Name { n("Rust_str") }
 
-    fn reify(&self) -> Value { Value::AbstractSyntax(Ast::Atom(n(self))) }
-
-    fn reflect(v: &Value) -> Self {
-        match v {
-            eval::AbstractSyntax(Ast::Atom(name)) => name.orig_sp(),
-            _ => icp!(),
+    fn try_reflect(v: &Value) -> Option<Self> {
+        match *v {
+            Value::Sequence(ref s) if s.len() == 2 => {
+                Some((T0::try_reflect(&s[0])?, T1::try_reflect(&s[1])?))
+            }
+            _ => None,
         }
     }
 }
@@ -242,22 +301,26 @@ impl Reifiable for Value {
 // TODO: when returning traits works, just make functions `Reifiable`
 // TOUNDERSTAND: 'x also allows things to be owned instead?!?
 pub fn reify_1ary_function<A: Reifiable + 'static, R: Reifiable + 'static>(
-    f: Rc<Box<(dyn Fn(A) -> R)>>,
+    f: Rc<Box<dyn Fn(A) -> R>>,
 ) -> Value {
     Value::BuiltInFunction(eval::BIF(Rc::new(move |args: Vec<Value>| {
-        ((*f)(A::reflect(&args[0]))).reify()
+        Ok(((*f)(A::reflect(&args[0]))).reify())
     })))
 }
 
+/// A reflected Unseemly function. Calling it can fail, since the Unseemly function can.
+pub type ReflectedFn1<A, R> = Rc<Box<dyn Fn(A) -> Result<R, eval::RuntimeError>>>;
+pub type ReflectedFn2<A, B, R> = Rc<Box<dyn Fn(A, B) -> Result<R, eval::RuntimeError>>>;
+
 pub fn reflect_1ary_function<A: Reifiable + 'static, R: Reifiable + 'static>(
     f_v: Value,
-) -> Rc<Box<(dyn Fn(A) -> R)>> {
+) -> ReflectedFn1<A, R> {
     Rc::new(Box::new(move |a: A| {
         extract!((&f_v)
-        Value::BuiltInFunction = (ref bif) => R::reflect(&(*bif.0)(vec![a.reify()]));
+        Value::BuiltInFunction = (ref bif) => Ok(R::reflect(&(*bif.0)(vec![a.reify()])?));
         Value::Function = (ref closure) => {
-            R::reflect(&eval::eval(&closure.body,
-                closure.env.clone().set(closure.params[0], a.reify())).unwrap())
+            Ok(R::reflect(&eval::eval(&closure.body,
+                closure.env.clone().set(closure.params[0], a.reify()))?))
         })
     }))
 }
@@ -268,10 +331,10 @@ pub fn reify_2ary_function<
     B: Reifiable + 'static,
     R: Reifiable + 'static,
 >(
-    f: Rc<Box<(dyn Fn(A, B) -> R)>>,
+    f: Rc<Box<dyn Fn(A, B) -> R>>,
 ) -> Value {
     Value::BuiltInFunction(eval::BIF(Rc::new(move |args: Vec<Value>| {
-        ((*f)(A::reflect(&args[0]), B::reflect(&args[1]))).reify()
+        Ok(((*f)(A::reflect(&args[0]), B::reflect(&args[1]))).reify())
     })))
 }
 
@@ -281,23 +344,81 @@ pub fn reflect_2ary_function<
     R: Reifiable + 'static,
 >(
     f_v: Value,
-) -> Rc<Box<(dyn Fn(A, B) -> R)>> {
+) -> ReflectedFn2<A, B, R> {
     Rc::new(Box::new(move |a: A, b: B| {
         extract!((&f_v)
         Value::BuiltInFunction = (ref bif) =>
-            R::reflect(&(*bif.0)(vec![a.reify(), b.reify()]));
+            Ok(R::reflect(&(*bif.0)(vec![a.reify(), b.reify()])?));
         Value::Function = (ref closure) => {
-            R::reflect(&eval::eval(&closure.body,
+            Ok(R::reflect(&eval::eval(&closure.body,
                 closure.env.clone().set(closure.params[0], a.reify())
-                                   .set(closure.params[1], b.reify())).unwrap())
+                                   .set(closure.params[1], b.reify()))?))
         })
     }))
 }
 
-pub fn ty_of_1ary_function<A: Reifiable + 'static, R: Reifiable + 'static>() -> Ast {
-    ast!("TODO: generate type")
+/// The Unseemly type of `T`, for the signature of a Rust function being made available.
+/// Non-generic types are spelled out, but generic ones are referred to by name,
+///  so the name had better be bound where the function is used (`Sequence` is, for example).
+pub fn host_ty<T: Reifiable>() -> Ast {
+    match T::concrete_arguments() {
+        None => T::ty(),
+        Some(_) => T::ty_invocation(),
+    }
+}
+
+/// Rust functions that can be called from Unseemly.
+/// Arguments are converted with `try_reflect`, and the result with `reify`.
+/// (`Args` is the tuple of the parameter types; it's just there to tell the `impl`s apart.)
+pub trait HostFunction<Args> {
+    fn ty() -> Ast;
+
+    fn reify_function(self) -> Value;
 }
 
+macro_rules! host_function {
+    ( $( $arg:ident ),* ) => {
+        impl<F, $( $arg: Reifiable + 'static, )* R: Reifiable + 'static>
+            HostFunction<( $( $arg, )* )> for F
+        where F: Fn($( $arg ),*) -> R + 'static
+        {
+            fn ty() -> Ast {
+                ast!({ "Type" "fn" :
+                    "param" => (,seq vec![ $( host_ty::<$arg>() ),* ]),
+                    "ret" => (, host_ty::<R>())
+                })
+            }
+
+            fn reify_function(self) -> Value {
+                Value::BuiltInFunction(eval::BIF(Rc::new(move |args: Vec<Value>| {
+                    let arity = <[&str]>::len(&[$( stringify!($arg) ),*]);
+                    if args.len() != arity {
+                        return Err(eval::RuntimeError::new(
+                            eval::RuntimeErr::ArgCountMismatch(arity, args)));
+                    }
+                    // (These go unused by nullary functions.)
+                    #[allow(unused_variables)]
+                    let mismatch = || eval::RuntimeError::new(
+                        eval::RuntimeErr::BifArgMismatch(args.clone()));
+                    #[allow(unused_variables, unused_mut)]
+                    let mut args_iter = args.iter();
+                    $(
+                        let $arg =
+                            args_iter.next().and_then($arg::try_reflect).ok_or_else(mismatch)?;
+                    )*
+                    Ok(self($( $arg ),*).reify())
+                })))
+            }
+        }
+    }
+}
+
+host_function!();
+host_function!(A);
+host_function!(A, B);
+host_function!(A, B, C);
+host_function!(A, B, C, D);
+
 macro_rules! reify_types {
     ( $($t:ty),* ) => {{
         let mut res = Assoc::new();
@@ -340,6 +461,8 @@ impl<T: Reifiable> Reifiable for Rc<T> {
     fn reify(&self) -> Value { (**self).reify() }
 
     fn reflect(v: &Value) -> Self { Rc::new(T::reflect(v)) }
+
+    fn try_reflect(v: &Value) -> Option<Self> { T::try_reflect(v).map(Rc::new) }
 }
 
 // for when we have a `Ty`, rather than a Rust type.
@@ -369,9 +492,16 @@ impl<T: Reifiable> Reifiable for Vec<T> {
 
     fn reflect(v: &Value) -> Self {
         extract!((v) Value::Sequence = (ref s) =>
-            s.iter().map(|elt| T::reflect(&elt)).collect()
+            s.iter().map(|elt| T::reflect(elt)).collect()
         )
     }
+
+    fn try_reflect(v: &Value) -> Option<Self> {
+        match *v {
+            Value::Sequence(ref s) => s.iter().map(|elt| T::try_reflect(elt)).collect(),
+            _ => None,
+        }
+    }
 }
 
 impl<T: Reifiable> Reifiable for std::boxed::Box<T> {
@@ -384,6 +514,8 @@ impl<T: Reifiable> Reifiable for std::boxed::Box<T> {
     fn reify(&self) -> Value { (**self).reify() }
 
     fn reflect(v: &Value) -> Self { std::boxed::Box::new(T::reflect(v)) }
+
+    fn try_reflect(v: &Value) -> Option<Self> { T::try_reflect(v).map(std::boxed::Box::new) }
 }
 
 // The roundtrip will de-alias the cell, sadly.
@@ -451,6 +583,8 @@ impl<'t> Reifiable for OldName<'t> {
     fn reify(&self) -> Value { self.actual.reify() }
 
     fn reflect(v: &Value) -> Self { new_oldname(Name::reflect(v)) }
+
+    fn try_reflect(v: &Value) -> Option<Self> { Name::try_reflect(v).map(new_oldname) }
 }
 
 custom_derive! {
@@ -536,7 +670,27 @@ fn function_r_and_r_roundtrip() {
 
     let f2 = reflect_1ary_function::<BigInt, BigInt>(reify_1ary_function(Rc::new(Box::new(f))));
 
-    assert_eq!((*f2)(BigInt::from(1776)), BigInt::from(1777));
+    assert_eq!((*f2)(BigInt::from(1776)), Ok(BigInt::from(1777)));
+}
+
+#[test]
+fn try_reflect_rejects_wrong_shapes() {
+    let bev = BasicEnum::Jefferson(BigInt::from(17), BigInt::from(1781));
+    assert_eq!(BasicEnum::try_reflect(&bev.reify()), Some(bev));
+    assert_eq!(BasicEnum::try_reflect(&val!(enum "Jefferson", (i 1801))), None);
+    assert_eq!(BasicEnum::try_reflect(&val!(enum "Burr", (b false))), None);
+    assert_eq!(BasicEnum::try_reflect(&val!(enum "Madison", (i 1812))), None);
+    assert_eq!(BasicEnum::try_reflect(&val!(i 1781)), None);
+
+    let bsv = BasicStruct { a: BigInt::from(4), b: BigInt::from(5) };
+    assert_eq!(BasicStruct::try_reflect(&bsv.reify()), Some(bsv));
+    assert_eq!(BasicStruct::try_reflect(&val!(struct "a" => (i 4))), None);
+
+    assert_eq!(Name::try_reflect(&val!(ast (vr "x"))), None);
+    assert_eq!(Ast::try_reflect(&val!(i 4)), None);
+    let fp = crate::grammar::FormPat::Call(n("Expr"));
+    assert_eq!(crate::grammar::FormPat::try_reflect(&fp.reify()), Some(fp));
+    assert_eq!(crate::grammar::FormPat::try_reflect(&val!(enum "Call", (i 4))), None);
 }
 
 struct T {}
@@ -546,7 +700,7 @@ fake_reifiability!(S);
 
 #[test]
 fn reified_types() {
-    //"ParameterizedLifetimeStruct<Option<Rust_usize> integer>"
+    //"ParameterizedLifetimeStruct<Option<Int> integer>"
     assert_eq!(
         ParameterizedLifetimeStruct::<'static, Option<usize>, BigInt>::ty_invocation(),
         ast!({"Type" "type_apply" :
@@ -554,7 +708,7 @@ fn reified_types() {
             "arg" => [
                 {"Type" "type_apply" :
                     "type_rator" => (vr "Option"),
-                    "arg" => [ (vr "Rust_usize") ]
+                    "arg" => [ (vr "Int") ]
                 },
                 (vr "Int")]
         })
@@ -45,6 +45,36 @@ impl Ty {
             .destructure(expd_form.clone())
             .ok_or(ty_err_val!(UnableToDestructure(self.clone(), expd_form.name) at loc /*TODO*/))
     }
+
+    /// For error messages: if `env` has a name for this type, write that instead of spelling it out.
+    /// (Types and values share an environment; by convention, only type names are capitalized.)
+    pub fn abbreviated(&self, env: &Assoc<Name, Ty>) -> Ty {
+        // Primitive types (like `Int`) are already just names:
+        if let Node(_, ref parts, _) = *self.0.c() {
+            if *parts == crate::util::mbe::EnvMBE::new() {
+                return self.clone();
+            }
+        }
+        let name = env
+            .iter_pairs()
+            .filter(|&(k, v)| v == self && k.orig_sp().starts_with(char::is_uppercase))
+            .map(|(k, _)| *k)
+            .min_by_key(|k| k.orig_sp());
+        match name {
+            Some(name) => Ty(raw_ast!(VariableReference(name)).with_loc(self.0.loc())),
+            None => self.clone(),
+        }
+    }
+}
+
+impl TyErr {
+    /// Use `env`'s names for the types involved (see `Ty::abbreviated`).
+    pub fn abbreviated(self, env: &Assoc<Name, Ty>) -> TyErr {
+        match self {
+            TyErr::Mismatch(got, exp) => TyErr::Mismatch(got.abbreviated(env), exp.abbreviated(env)),
+            e => e,
+        }
+    }
 }
 
 // this kinda belongs in core_forms.rs
@@ -60,6 +90,10 @@ impl crate::runtime::reify::Reifiable for Ty {
     fn reify(&self) -> crate::runtime::eval::Value { self.0.reify() }
 
     fn reflect(v: &crate::runtime::eval::Value) -> Self { Ty::new(Ast::reflect(v)) }
+
+    fn try_reflect(v: &crate::runtime::eval::Value) -> Option<Self> {
+        Ast::try_reflect(v).map(Ty::new)
+    }
 }
 
 impl crate::walk_mode::WalkElt for Ty {
@@ -97,13 +131,24 @@ impl WalkMode for SynthTy {
         match parts.env.find(&name) {
             None => Err(crate::util::err::sp(TyErr::UnboundName(name), parts.this_ast.clone())),
             // If name is protected, stop:
-            Some(ty) if &Ty(VariableReference(name)) == ty => Ok(ty.clone()),
-            Some(ty) => synth_type(&ty.concrete(), parts.env.clone()),
+            Some(ty) if &Ty(raw_ast!(VariableReference(name))) == ty => Ok(ty.clone()),
+            Some(ty) => {
+                // (Keep the enclosing phases, in case `ty` mentions their type variables.)
+                let ty_parts = LazyWalkReses {
+                    less_quoted_env: parts.less_quoted_env.clone(),
+                    ..LazyWalkReses::new_wrapper(parts.env.clone())
+                };
+                crate::type_classes::instantiate(
+                    walk::<SynthTy>(&ty.concrete(), &ty_parts)?,
+                    &parts.this_ast,
+                    &parts.env,
+                )
+            }
         }
     }
 
     // Simply protect the name; don't try to unify it.
-    fn underspecified(name: Name) -> Ty { Ty(VariableReference(name)) }
+    fn underspecified(name: Name) -> Ty { Ty(raw_ast!(VariableReference(name))) }
 }
 
 impl WalkMode for UnpackTy {
@@ -119,7 +164,7 @@ impl WalkMode for UnpackTy {
     fn get_walk_rule(f: &Form) -> WalkRule<UnpackTy> { f.synth_type.neg().clone() }
     fn automatically_extend_env() -> bool { true }
 
-    fn underspecified(name: Name) -> Ty { Ty(VariableReference(name)) }
+    fn underspecified(name: Name) -> Ty { Ty(raw_ast!(VariableReference(name))) }
 }
 
 impl crate::walk_mode::NegativeWalkMode for UnpackTy {
@@ -146,61 +191,130 @@ custom_derive! {
         NtInterpMismatch(Name, Name),
         NonexistentEnumArm(Name, Ty),
         NonexistentStructField(Name, Ty),
-        NonExhaustiveMatch(Ty),
+        NonExhaustiveMatch(Ty, String), // scrutinee type, an unmatched value (as a pattern)
         UnableToDestructure(Ty, Name),
         UnboundName(Name),
+        KindMismatch(Ty, Ast, Ast), // type, its kind, expected kind
+        NoInstance(Name, Ty), // class, type
+        AmbiguousInstance(Name, Ty), // class, (not-yet-known) type
+        LiteralOutOfRange(String, Ty),
         // TODO: the reification macros can't handle empty `enum` cases. Fix that!
         AnnotationRequired(()),
-        NeedsDriver(()),
-        // TODO: replace all uses of `Other` with more specific errors:
-        Other(String)
+        NeedsDriver(())
     }
 }
 
-impl fmt::Display for TyErr {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl TyErr {
+    /// The name of the variant; diagnostics use it as an error code.
+    pub fn code(&self) -> &'static str {
+        use self::TyErr::*;
+        match *self {
+            Mismatch(_, _) => "Mismatch",
+            LengthMismatch(_, _) => "LengthMismatch",
+            NtInterpMismatch(_, _) => "NtInterpMismatch",
+            NonexistentEnumArm(_, _) => "NonexistentEnumArm",
+            NonexistentStructField(_, _) => "NonexistentStructField",
+            NonExhaustiveMatch(_, _) => "NonExhaustiveMatch",
+            UnableToDestructure(_, _) => "UnableToDestructure",
+            UnboundName(_) => "UnboundName",
+            KindMismatch(_, _, _) => "KindMismatch",
+            NoInstance(_, _) => "NoInstance",
+            AmbiguousInstance(_, _) => "AmbiguousInstance",
+            LiteralOutOfRange(_, _) => "LiteralOutOfRange",
+            AnnotationRequired(()) => "AnnotationRequired",
+            NeedsDriver(()) => "NeedsDriver",
+        }
+    }
+
+    /// A one-line description of the problem.
+    pub fn message(&self) -> String {
         use self::TyErr::*;
         match *self {
-            Mismatch(ref got, ref exp) => {
-                write!(f, "[Mismatch] got:\n  `{}`\n   expected:\n  `{}`\n", got, exp)
+            Mismatch(ref got, ref exp) => format!("expected `{}`, got `{}`", exp, got),
+            LengthMismatch(ref got, exp_len) => format!(
+                "expected {} arguments, got {}: {}",
+                exp_len,
+                got.len(),
+                got.iter().map(|g| format!("`{}`", g)).collect::<Vec<_>>().join(", ")
+            ),
+            NtInterpMismatch(got, exp) => {
+                format!("expected the nonterminal `{}`, but `{}` was interpolated", exp, got)
             }
-            LengthMismatch(ref got, exp_len) => {
-                write!(f, "[LengthMismatch] got:\n  ")?;
-                for g in got {
-                    write!(f, "{}, ", g)?;
-                }
-                write!(f, "\n  expected {} arguments.\n", exp_len)
+            NonexistentEnumArm(got_name, ref ty) => {
+                format!("the enum `{}` doesn't have an arm named `{}`", ty, got_name)
             }
-            NtInterpMismatch(got, exp) => write!(
-                f,
-                "[NtInterpMismatch] expected the nonterminal `{}`, but `{}` was interpolated",
-                exp, got
-            ),
-            NonexistentEnumArm(got_name, ref ty) => write!(
-                f,
-                "[NonexistentEnumArm] the enum `{}` doesn't have an arm named `{}`",
-                ty, got_name
-            ),
-            NonexistentStructField(got_name, ref ty) => write!(
-                f,
-                "[NonexistentStructField] the struct `{}` doesn't have a field named `{}`",
-                ty, got_name
-            ),
-            NonExhaustiveMatch(ref ty) => {
-                write!(f, "[NonExhaustiveMatch] non-exhaustive match of `{}`", ty)
+            NonexistentStructField(got_name, ref ty) => {
+                format!("the struct `{}` doesn't have a field named `{}`", ty, got_name)
+            }
+            NonExhaustiveMatch(ref ty, ref witness) => {
+                format!("non-exhaustive match of `{}`: `{}` is not matched", ty, witness)
             }
             UnableToDestructure(ref ty, expected_name) => {
-                write!(f, "[UnableToDestructure] expected a `{}` type, got `{}`", expected_name, ty)
+                format!("expected a `{}` type, got `{}`", expected_name, ty)
             }
-            UnboundName(name) => write!(f, "[UnboundName] `{}` is not defined", name),
-            AnnotationRequired(()) => write!(
-                f,
-                "[AnnotationRequired] Negative syntax (e.g. a pattern) inside positive syntax \
-                 (e.g. an expression) requires a type annotation."
+            UnboundName(name) => format!("`{}` is not defined", name),
+            KindMismatch(ref ty, ref got, ref exp) => {
+                format!("expected a type of kind `{}`, but `{}` has kind `{}`", exp, ty, got)
+            }
+            NoInstance(class, ref ty) => format!("there's no `{}` instance for `{}`", class, ty),
+            AmbiguousInstance(class, ref ty) => format!(
+                "can't tell which `{}` instance to use: the type `{}` is unknown",
+                class, ty
             ),
-            NeedsDriver(()) => write!(f, "[NeedsDriver] Repetition needs a driver"),
-            Other(ref s) => write!(f, "[Other] {}", s),
+            LiteralOutOfRange(ref lit, ref ty) => format!("`{}` doesn't fit in `{}`", lit, ty),
+            AnnotationRequired(()) => "Negative syntax (e.g. a pattern) inside positive syntax \
+                                       (e.g. an expression) requires a type annotation."
+                .to_owned(),
+            NeedsDriver(()) => "Repetition needs a driver".to_owned(),
+        }
+    }
+
+    /// What to say next to the offending syntax.
+    fn label(&self) -> String {
+        use self::TyErr::*;
+        match *self {
+            Mismatch(ref got, _) => format!("this has type `{}`", got),
+            LengthMismatch(ref got, _) => format!("{} supplied", got.len()),
+            NtInterpMismatch(got, _) => format!("this is a `{}`", got),
+            NonexistentEnumArm(_, _) => "no such arm".to_owned(),
+            NonexistentStructField(_, _) => "no such field".to_owned(),
+            NonExhaustiveMatch(_, ref witness) => format!("`{}` not matched by any arm", witness),
+            UnableToDestructure(ref ty, _) => format!("this has type `{}`", ty),
+            UnboundName(_) => "not found in this scope".to_owned(),
+            KindMismatch(_, ref got, _) => format!("this has kind `{}`", got),
+            NoInstance(class, _) | AmbiguousInstance(class, _) => {
+                format!("this needs a `{}` instance", class)
+            }
+            LiteralOutOfRange(_, _) => "out of range".to_owned(),
+            AnnotationRequired(()) => "add a type annotation here".to_owned(),
+            NeedsDriver(()) => "nothing here determines how many times to repeat".to_owned(),
+        }
+    }
+
+    /// Point at the places where the types involved were written down, if we know them.
+    fn notes(&self) -> Vec<crate::util::diagnostics::Note> {
+        use self::TyErr::*;
+        let written_at = |msg: String, ty: &Ty| {
+            ty.0.loc().map(|span| crate::util::diagnostics::Note { msg, span: Some(span) })
+        };
+        match *self {
+            Mismatch(_, ref exp) => written_at(format!("`{}` was declared here", exp), exp),
+            NonexistentEnumArm(_, ref ty) | NonexistentStructField(_, ref ty) => {
+                written_at(format!("`{}` was defined here", ty), ty)
+            }
+            NonExhaustiveMatch(ref ty, _) | UnableToDestructure(ref ty, _) => {
+                written_at(format!("`{}` comes from here", ty), ty)
+            }
+            _ => None,
         }
+        .into_iter()
+        .collect()
+    }
+}
+
+impl fmt::Display for TyErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {}", self.code(), self.message())
     }
 }
 
@@ -218,13 +332,28 @@ impl fmt::Debug for TyErr {
 
 pub type TypeError = crate::util::err::Spanned<TyErr>;
 
+impl crate::util::diagnostics::Diagnose for TypeError {
+    fn diagnostic(&self) -> crate::util::diagnostics::Diagnostic {
+        let mut res = crate::util::diagnostics::Diagnostic::new(self.body.message(), self.span())
+            .code(self.body.code())
+            .label(self.body.label());
+        if self.span().is_none() {
+            res = res.note(format!("while checking `{}`", self.loc), None);
+        }
+        for note in self.body.notes() {
+            res = res.note(note.msg, note.span);
+        }
+        res
+    }
+}
+
 pub type TypeResult = Result<Ty, TypeError>;
 
 pub fn expect_type(expected: &Ty, got: &Ty, loc: &Ast) -> Result<(), TypeError> {
     if got != expected {
         Err(crate::util::err::Spanned {
             loc: loc.clone(),
-            body: TyErr::Mismatch(expected.clone(), got.clone()),
+            body: TyErr::Mismatch(got.clone(), expected.clone()),
         })
     } else {
         Ok(())
@@ -296,7 +425,7 @@ fn basic_type_synth() {
 fn type_specialization() {
     let nat_ty = ty!( { "Type" "Nat" : });
 
-    fn tbn(nm: &'static str) -> Ty { Ty(crate::ast::Ast::VariableReference(n(nm))) }
+    fn tbn(nm: &'static str) -> Ty { Ty(raw_ast!(VariableReference(n(nm)))) }
 
     let _para_ty_env = assoc_n!(
         "some_int" => ty!( { "Type" "Int" : }),
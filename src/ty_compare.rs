@@ -109,16 +109,16 @@ use std::{cell::RefCell, collections::HashMap, rc::Rc};
 pub fn resolve(Clo { it: t, env }: Clo<Ty>, unif: &HashMap<Name, Clo<Ty>>) -> Clo<Ty> {
     let u_f = underdetermined_form.with(|u_f| u_f.clone());
 
-    let resolved = match t {
-        Ty(VariableReference(vr)) => {
+    let resolved = match *t.0.c() {
+        VariableReference(vr) => {
             match env.find(&vr).cloned() {
                 // HACK: leave mu-protected variables alone, instead of recurring forever
-                Some(Ty(VariableReference(new_vr))) if vr == new_vr => None,
+                Some(Ty(ref new_vr)) if new_vr.c() == &VariableReference(vr) => None,
                 Some(different) => Some(Clo { it: different, env: env.clone() }),
                 None => None,
             }
         }
-        Ty(Node(ref form, ref parts, _)) if form == &find_core_form("Type", "type_apply") => {
+        Node(ref form, ref parts, _) if form == &find_core_form("Type", "type_apply") => {
             // Expand defined type applications.
             // This is sorta similar to the type synthesis for "type_apply",
             //  but it does not recursively process the arguments (which may be underdetermined!).
@@ -130,7 +130,8 @@ pub fn resolve(Clo { it: t, env }: Clo<Ty>, unif: &HashMap<Name, Clo<Ty>>) -> Cl
             );
 
             match resolved {
-                Clo { it: Ty(VariableReference(rator_vr)), env } => {
+                Clo { it: Ty(ref rator), ref env } if matches!(rator.c(), VariableReference(_)) => {
+                    let rator_vr = crate::core_forms::vr_to_name(rator);
                     // e.g. `X<int, Y>` underneath `mu X. ...`
 
                     // Rebuild a type_apply, but evaulate its arguments
@@ -140,7 +141,7 @@ pub fn resolve(Clo { it: t, env }: Clo<Ty>, unif: &HashMap<Name, Clo<Ty>>) -> Cl
                     use crate::util::mbe::EnvMBE;
 
                     let mut new__tapp_parts = EnvMBE::new_from_leaves(
-                        assoc_n!("type_rator" => VariableReference(rator_vr)),
+                        assoc_n!("type_rator" => raw_ast!(VariableReference(rator_vr))),
                     );
 
                     let mut args = vec![];
@@ -151,11 +152,11 @@ pub fn resolve(Clo { it: t, env }: Clo<Ty>, unif: &HashMap<Name, Clo<Ty>>) -> Cl
                     }
                     new__tapp_parts.add_anon_repeat(args, None);
 
-                    let res = Ty::new(Node(
+                    let res = Ty::new(raw_ast!(Node(
                         find_core_form("Type", "type_apply"),
                         new__tapp_parts,
                         crate::beta::ExportBeta::Nothing,
-                    ));
+                    )));
 
                     if res != t {
                         Some(Clo { it: res, env: env.clone() })
@@ -169,11 +170,8 @@ pub fn resolve(Clo { it: t, env }: Clo<Ty>, unif: &HashMap<Name, Clo<Ty>>) -> Cl
                         Ok(ref got_forall) => {
                             let params = got_forall.get_rep_leaf_or_panic(n("param"));
                             if params.len() != arg_terms.len() {
-                                panic!(
-                                    "Kind error: wrong number of arguments: {} vs {}",
-                                    params.len(),
-                                    arg_terms.len()
-                                );
+                                // A kind error; `type_apply`'s type synthesis reports it
+                                return Clo { it: t, env: env.clone() };
                             }
                             let mut actual_params = Assoc::new();
                             for (name, arg_term) in params.iter().zip(arg_terms) {
@@ -198,14 +196,146 @@ pub fn resolve(Clo { it: t, env }: Clo<Ty>, unif: &HashMap<Name, Clo<Ty>>) -> Cl
         // TODO: This needs to be implemented (unless issue #28 obviates it)
         // Ty(Node(ref form, ref parts, _)) if form == &find_core_form("Type", "dotdotdot") => {
         // }
-        Ty(Node(ref form, ref parts, _)) if form == &u_f => {
+        Node(ref form, ref parts, _) if form == &u_f => {
             // underdetermined
             unif.get(&ast_to_name(parts.get_leaf_or_panic(&n("id")))).cloned()
         }
         _ => None,
     };
 
-    resolved.map(|clo| resolve(clo, unif)).unwrap_or(Clo { it: t, env: env })
+    resolved.map(|clo| resolve(clo, unif)).unwrap_or(Clo { it: t, env })
+}
+
+// Collects the unsolved underdetermined types in `a` (looking through the solved ones),
+//  and every name mentioned along the way.
+fn unsolved_in(
+    a: &Ast,
+    u_f: &Rc<Form>,
+    unif: &HashMap<Name, Clo<Ty>>,
+    ids: &mut Vec<Name>,
+    names: &mut Vec<Name>,
+) {
+    match *a.c() {
+        Node(ref form, ref parts, _) if form == u_f => {
+            let id = ast_to_name(parts.get_leaf_or_panic(&n("id")));
+            match unif.get(&id) {
+                Some(clo) => unsolved_in(&clo.it.0, u_f, unif, ids, names),
+                None if !ids.contains(&id) => ids.push(id),
+                None => {}
+            }
+        }
+        Atom(nm) | VariableReference(nm) => names.push(nm),
+        Node(_, ref parts, _) => {
+            parts.map(&mut |part| unsolved_in(part, u_f, unif, ids, names));
+        }
+        ExtendEnv(ref body, _) | ExtendEnvPhaseless(ref body, _) => {
+            unsolved_in(body, u_f, unif, ids, names)
+        }
+        QuoteMore(ref body, _) | QuoteLess(ref body, _) => unsolved_in(body, u_f, unif, ids, names),
+        _ => {}
+    }
+}
+
+/// `canonicalize`, but with each unsolved underdetermined type (`id`) standing for a type variable.
+fn canonicalize_solving(t: &Ty, env: Assoc<Name, Ty>, vars: &[(Name, Name)]) -> Result<Ty, TyErr> {
+    unification.with(|unif| {
+        for &(id, var) in vars {
+            let var_ty = Ty(raw_ast!(VariableReference(var)));
+            unif.borrow_mut().insert(id, Clo { it: var_ty, env: Assoc::new() });
+        }
+    });
+    let res = canonicalize(t, env);
+    unification.with(|unif| {
+        for (id, _) in vars {
+            unif.borrow_mut().remove(id);
+        }
+    });
+    res
+}
+
+/// Like `canonicalize`, but underdetermined types that haven't been determined yet stay that way
+///  (rather than being an error). For inference, which determines them as it goes.
+pub fn canonicalize_partially(t: &Ty, env: Assoc<Name, Ty>) -> Result<Ty, TyErr> {
+    let u_f = underdetermined_form.with(|u_f| u_f.clone());
+    let mut ids = vec![];
+    unification.with(|unif| unsolved_in(&t.0, &u_f, &unif.borrow(), &mut ids, &mut vec![]));
+    if ids.is_empty() {
+        return canonicalize(t, env);
+    }
+    let vars: Vec<(Name, Name)> = ids.iter().map(|id| (*id, Name::gensym("⚁"))).collect();
+    let res = canonicalize_solving(t, env, &vars)?;
+    let undetermined = vars.iter().fold(Assoc::new(), |a, &(id, var)| {
+        a.set(var, ast!({u_f.clone() ; "id" => (, raw_ast!(Atom(id)))}))
+    });
+    Ok(Ty(crate::alpha::substitute(&res.0, &undetermined)))
+}
+
+/// Turn the underdetermined types in `t` that nothing has constrained into `forall` parameters
+///  (so `.[x . x].` gets the type `forall T . [T -> T]`).
+/// Only sound where nothing else can constrain them later, i.e. at a top-level definition.
+/// `constraints` are class instances needed for underdetermined types (class, type id);
+///  they become a `{ Show<T> ⋯ => ⋯ }` in the same order.
+pub fn generalize(
+    t: &Ty,
+    constraints: &[(Name, Name)],
+    env: Assoc<Name, Ty>,
+) -> Result<Ty, TyErr>
+{
+    let u_f = underdetermined_form.with(|u_f| u_f.clone());
+    let (mut ids, mut taken) = (vec![], env.iter_keys().cloned().collect::<Vec<_>>());
+    unification.with(|unif| unsolved_in(&t.0, &u_f, &unif.borrow(), &mut ids, &mut taken));
+    if ids.is_empty() && constraints.is_empty() {
+        return Ok(t.clone()); // Nothing to do (and no need to canonicalize)
+    }
+
+    let mut params: Vec<(Name, Name)> = vec![];
+    for id in ids {
+        let param = (0..)
+            .map(|i| if i == 0 { n("T") } else { n(&format!("T{}", i)) })
+            .find(|p| !taken.contains(p) && params.iter().all(|(_, q)| q != p))
+            .unwrap();
+        params.push((id, param));
+    }
+
+    // (Some underdetermined types have been solved by now; the rest become the parameters.)
+    let mut body = canonicalize_solving(t, env, &params)?.0;
+
+    if !constraints.is_empty() {
+        let mut constraint_asts = vec![];
+        for &(class, id) in constraints {
+            // Look through any underdetermined types that were merged:
+            let id = unification.with(|unif| {
+                let unif = unif.borrow();
+                let mut id = id;
+                while let Some(clo) = unif.get(&id) {
+                    match clo.it.0.destructure(u_f.clone()) {
+                        Some(parts) => id = ast_to_name(parts.get_leaf_or_panic(&n("id"))),
+                        None => break,
+                    }
+                }
+                id
+            });
+            match params.iter().find(|(p_id, _)| *p_id == id) {
+                Some(&(_, param)) => constraint_asts.push(crate::type_classes::constraint(
+                    class,
+                    &Ty(raw_ast!(VariableReference(param))),
+                )),
+                // Nothing outside can ever determine the type, so we'll never find the instance:
+                None => {
+                    let u_f_ast = ast!({u_f.clone() ; "id" => (, raw_ast!(Atom(id)))});
+                    return Err(TyErr::AmbiguousInstance(class, Ty(u_f_ast)));
+                }
+            }
+        }
+        body = ast!({"Type" "constrained_type" :
+            "constraint" => (,seq constraint_asts), "body" => (, body)});
+    }
+    if params.is_empty() {
+        return Ok(Ty(body));
+    }
+    Ok(ty!({ "Type" "forall_type" :
+        "param" => (,seq params.iter().map(|(_, p)| raw_ast!(Atom(*p))).collect::<Vec<_>>()),
+        "body" => (import [* [forall "param"]] (, body))}))
 }
 
 thread_local! {
@@ -227,7 +357,8 @@ thread_local! {
                 })
             }),
             NotWalked),
-        synth_type:   Both(NotWalked, NotWalked),
+        // Variable references re-synthesize their types, which might be (or contain) these:
+        synth_type:   Both(LiteralLike, NotWalked),
         eval:         Both(NotWalked, NotWalked),
         quasiquote:   Both(NotWalked, NotWalked)
     })
@@ -262,14 +393,24 @@ impl WalkMode for Canonicalize {
     fn walk_var(n: Name, cnc: &LazyWalkReses<Canonicalize>) -> Result<Ty, TyErr> {
         match cnc.env.find(&n) {
             // If it's protected, stop:
-            Some(t) if &Ty(VariableReference(n)) == t => Ok(t.clone()),
+            Some(t) if t.0.c() == &VariableReference(n) => Ok(t.clone()),
             Some(t) => canonicalize(t, cnc.env.clone()),
-            None => Ok(Ty(VariableReference(n))), // TODO why can this happen?
+            None => Ok(Ty(raw_ast!(VariableReference(n)))), // TODO why can this happen?
         }
     }
 
     // Simply protect the name; don't try to unify it.
-    fn underspecified(name: Name) -> Ty { Ty(VariableReference(name)) }
+    fn underspecified(name: Name) -> Ty { Ty(raw_ast!(VariableReference(name))) }
+}
+
+/// Is `n` (which isn't bound in this phase) a type variable that an enclosing phase
+///  keeps abstract? E.g. a macro's `forall`ed `T`, used as a `:::[ ]:::` driver in its quotation.
+pub fn abstract_in_outer_phase<Mode: WalkMode<Elt = Ty>>(
+    n: Name,
+    cnc: &LazyWalkReses<Mode>,
+) -> bool {
+    let protected = Ty(raw_ast!(VariableReference(n)));
+    cnc.less_quoted_env.iter().any(|env| env.find(&n) == Some(&protected))
 }
 
 impl WalkMode for Subtype {
@@ -290,18 +431,24 @@ impl WalkMode for Subtype {
         underdetermined_form.with(|u_f| {
             let new_name = Name::gensym(&format!("{}⚁", name));
 
-            ty!({ u_f.clone() ; "id" => (, Atom(new_name))})
+            ty!({ u_f.clone() ; "id" => (, raw_ast!(Atom(new_name)))})
         })
     }
 
     /// Look up the reference and keep going.
     fn walk_var(n: Name, cnc: &LazyWalkReses<Subtype>) -> Result<Assoc<Name, Ty>, TyErr> {
-        let lhs: &Ty = cnc.env.find_or_panic(&n);
-        if lhs == &Ty(VariableReference(n)) {
+        let protected = Ty(raw_ast!(VariableReference(n)));
+        let lhs: &Ty = match cnc.env.find(&n) {
+            Some(lhs) => lhs,
+            // Just as opaque as a mu-protected name:
+            None if abstract_in_outer_phase(n, cnc) => &protected,
+            None => return Err(TyErr::UnboundName(n)),
+        };
+        if lhs == &protected {
             // mu-protected!
             return match cnc.context_elt() {
                 // mu-protected type variables have to exactly match by name:
-                &Ty(VariableReference(other_n)) if other_n == n => Ok(Assoc::new()),
+                Ty(other) if other.c() == &VariableReference(n) => Ok(Assoc::new()),
                 different => Err(TyErr::Mismatch(different.clone(), lhs.clone())),
             };
         }
@@ -326,28 +473,28 @@ impl crate::walk_mode::NegativeWalkMode for Subtype {
             let rhs: Clo<Ty> =
                 resolve(Clo { it: rhs_ty, env: env.clone() }, &unif.borrow()).clone();
 
-            let lhs_name = lhs.it.destructure(u_f.clone(), &Trivial).map(
+            let lhs_name = lhs.it.destructure(u_f.clone(), &raw_ast!(Trivial)).map(
                 // errors get swallowed ↓
                 |p| ast_to_name(p.get_leaf_or_panic(&n("id"))),
             );
             let rhs_name = rhs
                 .it
-                .destructure(u_f.clone(), &Trivial)
+                .destructure(u_f.clone(), &raw_ast!(Trivial))
                 .map(|p| ast_to_name(p.get_leaf_or_panic(&n("id"))));
 
             match (lhs_name, rhs_name) {
                 // They are the same underdetermined type; nothing to do:
                 (Ok(l), Ok(r)) if l == r => {
-                    return None;
+                    None
                 }
                 // Make a determination (possibly just merging two underdetermined types):
                 (Ok(l), _) => {
                     unif.borrow_mut().insert(l, rhs.clone());
-                    return None;
+                    None
                 }
                 (_, Ok(r)) => {
                     unif.borrow_mut().insert(r, lhs.clone());
-                    return None;
+                    None
                 }
                 // They are (potentially) different.
                 _ => Some((lhs, rhs)),
@@ -722,6 +869,14 @@ fn subtype_dotdotdot() {
 
     assert_m!(must_subtype(&dddple, &dddple, Assoc::new()), Ok(_));
 
+    // Outside of a quotation, an unbound driver is just unbound:
+    let unbound_dddple = uty!({dotdotdot : [Unbound] Unbound});
+    assert_m!(crate::ty::synth_type(&unbound_dddple.0, Assoc::new()), Err(_));
+    assert_eq!(
+        must_subtype(&threeple, &unbound_dddple, Assoc::new()),
+        Err(TyErr::UnboundName(n("Unbound")))
+    );
+
     let expr_threeple = uty!({tuple : [{type_apply : (prim Expr) [{Int :}]};
                                        {type_apply : (prim Expr) [{Float :}]};
                                        {type_apply : (prim Expr) [{Nat :}]}]});
@@ -730,6 +885,24 @@ fn subtype_dotdotdot() {
     assert_m!(must_subtype(&expr_threeple, &dddple, Assoc::new()), Ok(_));
 
     assert_m!(must_subtype(&expr_threeple, &expr_dddple, Assoc::new()), Ok(_));
+
+    // As function parameters, `:::[ ]:::` types are contravariant, like any other parameter.
+    // (Since `[Int -> Int] <: forall X . [X -> X]`:)
+    let takes_id = uty!({fn : [{forall_type : [X] {fn : [X] X}}] {Int :}});
+    let takes_int_fn = uty!({fn : [{fn : [{Int :}] {Int :}}] {Int :}});
+    assert_m!(must_subtype(&takes_id, &takes_int_fn, Assoc::new()), Ok(_));
+    assert_m!(must_subtype(&takes_int_fn, &takes_id, Assoc::new()), Err(_));
+
+    let takes_ids = uty!({fn : [{forall_type : [X] {fn : [X] X}} ;
+                                {forall_type : [X] {fn : [X] X}}] {Int :}});
+    let takes_int_fns =
+        uty!({fn : [{fn : [{Int :}] {Int :}} ; {fn : [{Int :}] {Int :}}] {Int :}});
+    let takes_some_ids = uty!({forall_type : [T]
+        {fn : [{dotdotdot : [T] {forall_type : [X] {fn : [X] X}}}] {Int :}}});
+    let takes_some_int_fns = uty!({forall_type : [T]
+        {fn : [{dotdotdot : [T] {fn : [{Int :}] {Int :}}}] {Int :}}});
+    assert_m!(must_subtype(&takes_ids, &takes_some_int_fns, Assoc::new()), Ok(_));
+    assert_m!(must_subtype(&takes_int_fns, &takes_some_ids, Assoc::new()), Err(_));
 }
 
 #[test]
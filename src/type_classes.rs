@@ -0,0 +1,341 @@
+//! Type classes, elaborated into dictionary passing.
+//!
+//! ```text
+//! class Eq T { eq : [T T -> Bool] }
+//! instance Eq<Int> := *[ eq: .[a b . (equal? a b)]. ]*
+//! ```
+//!
+//! A class is a `struct` type of methods, parameterized by one type (so `Eq<Int>`,
+//!  the type of the instance above, is `*[eq: [Int Int -> Bool]]*`); its instances are values
+//!  of that type, called dictionaries.
+//! Each method gets a constrained type (here, `eq : forall T . { Eq<T> => [T T -> Bool] }`),
+//!  and at runtime it's a function that takes a dictionary and returns the method.
+//!
+//! Referring to anything of constrained type, say `(eq x y)`, instantiates it and asks for
+//!  the dictionaries it needs (here, an `Eq` instance for the type of `x`).
+//! Once the whole top-level item has been typechecked, we look for instances, and
+//!  `elaborate` rewrites the reference to pass them: roughly, `((eq Eq<Int>) x y)`.
+//! This happens after macro expansion (we typecheck the expanded code again to find out
+//!  what it wants), so references that macros generate get their instances, too.
+//! A top-level definition that needs an instance for a type that it could be generalized over
+//!  (e.g. `.[a b . (eq a b)].`) takes the dictionary as a parameter instead:
+//!  it gets the type `forall T . { Eq<T> => [T T -> Bool] }`.
+//!
+//! An instance can itself require other instances:
+//!
+//! ```text
+//! instance forall T . { Eq<T> => Eq<List<T>> } := ⋯
+//! ```
+//!
+//! Nothing here affects evaluation, other than the rewritten syntax.
+
+use crate::{
+    ast::{Ast, AstContents::*},
+    core_forms::{ast_to_name, find_core_form, strip_ee},
+    name::*,
+    ty::{synth_type, Ty, TyErr, TypeError},
+    ty_compare::{
+        canonicalize_partially, must_equal, must_subtype, underdetermined_form, unification, Subtype,
+    },
+    util::{assoc::Assoc, err::sp},
+    walk_mode::WalkMode,
+};
+use std::{cell::RefCell, collections::HashMap};
+
+/// A class name, and the type that needs to be an instance of it (`Eq<T>` is `(Eq, T)`).
+type Constraint = (Name, Ty);
+
+/// A reference to something of constrained type, needing the dictionaries for `constraints`.
+#[derive(Clone)]
+pub struct Wanted {
+    var: Ast,
+    constraints: Vec<Constraint>,
+}
+
+thread_local! {
+    /// Everything that typechecking has asked for since the last `take_wanted`.
+    static wanted: RefCell<Vec<Wanted>> = const { RefCell::new(vec![]) };
+}
+
+pub fn take_wanted() -> Vec<Wanted> { wanted.with(|w| w.replace(vec![])) }
+
+/// The constraint `Show<T>`.
+pub fn constraint(class: Name, arg: &Ty) -> Ast {
+    ast!({"Type" "type_apply" :
+        "type_rator" => (, raw_ast!(VariableReference(class))), "arg" => [(, arg.concrete())]})
+}
+
+/// The class and type of the constraint `Show<T>`, if that's what `c` is.
+pub fn constraint_parts(c: &Ast) -> Option<(Name, Ty)> {
+    let parts = c.destructure(find_core_form("Type", "type_apply"))?;
+    let args = parts.get_rep_leaf_or_panic(n("arg"));
+    match (parts.get_leaf_or_panic(&n("type_rator")).c(), args.len()) {
+        (VariableReference(class), 1) => Some((*class, Ty(args[0].clone()))),
+        _ => None,
+    }
+}
+
+/// If `ty` is `forall T ⋯ . { C<T> ⋯ => τ }`, the `T ⋯`, the `C<T> ⋯`, and `τ`.
+fn constrained_parts(ty: &Ast) -> Option<(Vec<Name>, Vec<Constraint>, Ast)> {
+    let forall_parts = ty.destructure(find_core_form("Type", "forall_type"))?;
+    let body = strip_ee(forall_parts.get_leaf_or_panic(&n("body")));
+    let (constraints, body) = constrained_body(body)?;
+    Some((
+        forall_parts.get_rep_leaf_or_panic(n("param")).into_iter().map(ast_to_name).collect(),
+        constraints,
+        body,
+    ))
+}
+
+/// If `ty` is `{ C<T> ⋯ => τ }`, the `C<T> ⋯` and `τ`.
+fn constrained_body(ty: &Ast) -> Option<(Vec<Constraint>, Ast)> {
+    let con_parts = ty.destructure(find_core_form("Type", "constrained_type"))?;
+    let constraints = con_parts
+        .get_rep_leaf_or_panic(n("constraint"))
+        .into_iter()
+        .map(constraint_parts)
+        .collect::<Option<Vec<_>>>()?;
+    Some((constraints, con_parts.get_leaf_or_panic(&n("body")).clone()))
+}
+
+/// Takes apart the head of an `instance` item:
+///  `forall T ⋯ . { C<T> ⋯ => Class<τ> }`, or just `Class<τ>`.
+/// Returns the `T ⋯`, the `C<T> ⋯`, `Class`, and `τ`.
+pub fn instance_head(head: &Ast) -> Option<(Vec<Name>, Vec<Constraint>, Name, Ty)> {
+    let (params, givens, body) = match head.destructure(find_core_form("Type", "forall_type")) {
+        Some(forall_parts) => {
+            let params =
+                forall_parts.get_rep_leaf_or_panic(n("param")).into_iter().map(ast_to_name);
+            let body = strip_ee(forall_parts.get_leaf_or_panic(&n("body")));
+            let (givens, body) = constrained_body(body).unwrap_or((vec![], body.clone()));
+            (params.collect(), givens, body)
+        }
+        None => (vec![], vec![], head.clone()),
+    };
+    let (class, arg) = constraint_parts(&body)?;
+    Some((params, givens, class, arg))
+}
+
+/// `var` has the type `ty`. If that's constrained, instantiate it, and ask for the instances.
+pub fn instantiate(ty: Ty, var: &Ast, env: &Assoc<Name, Ty>) -> Result<Ty, TypeError> {
+    let (params, constraints, body) = match constrained_parts(&ty.0) {
+        Some(parts) => parts,
+        None => return Ok(ty),
+    };
+    let mut inst_env = env.clone();
+    for param in params {
+        inst_env = inst_env.set(param, <Subtype as WalkMode>::underspecified(param));
+    }
+    let mut inst_constraints = vec![];
+    for (class, arg) in constraints {
+        inst_constraints.push((class, synth_type(&arg.0, inst_env.clone())?));
+    }
+    wanted.with(|w| {
+        w.borrow_mut().push(Wanted { var: var.clone(), constraints: inst_constraints })
+    });
+    synth_type(&body, inst_env)
+}
+
+/// `instance forall T ⋯ . { C<T> ⋯ => Class<τ> } := ⋯`.
+#[derive(Clone)]
+pub struct Instance {
+    pub params: Vec<Name>,
+    /// The dictionaries it takes to build this one (mentioning `params`).
+    pub givens: Vec<Constraint>,
+    pub class: Name,
+    pub arg: Ty,
+    /// Where the dictionary is bound.
+    /// It's a function (of no arguments) that produces the dictionary, or, if there are
+    ///  `givens`, a function of them that does.
+    /// (The indirection lets instances refer to themselves.)
+    pub dict: Name,
+}
+
+/// Syntax that evaluates to the dictionary of `inst`, given the dictionaries for its `givens`.
+fn instance_ref(inst: &Instance, givens: Vec<Ast>) -> Ast {
+    let dict_fn = ast!({"Expr" "apply" :
+        "rator" => (, raw_ast!(VariableReference(inst.dict))), "rand" => []});
+    if givens.is_empty() {
+        dict_fn
+    } else {
+        ast!({"Expr" "apply" : "rator" => (, dict_fn), "rand" => (,seq givens)})
+    }
+}
+
+/// Finds dictionaries for constraints.
+pub struct Resolver<'a> {
+    instances: &'a [Instance],
+    env: Assoc<Name, Ty>,
+    /// Dictionaries already available in variables: (class, type, variable).
+    givens: Vec<(Name, Ty, Name)>,
+    /// If we're generalizing, dictionaries for types that are still unknown
+    ///  become parameters: (class, id of the underdetermined type, parameter).
+    /// Otherwise, `None`, and they're errors.
+    deferred: Option<Vec<(Name, Name, Name)>>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(instances: &'a [Instance], env: Assoc<Name, Ty>) -> Resolver<'a> {
+        Resolver { instances, env, givens: vec![], deferred: None }
+    }
+
+    /// Make the dictionary for `class` at `ty` available as `param`.
+    pub fn given(mut self, class: Name, ty: Ty, param: Name) -> Resolver<'a> {
+        self.givens.push((class, ty, param));
+        self
+    }
+
+    pub fn generalizing(mut self) -> Resolver<'a> {
+        self.deferred = Some(vec![]);
+        self
+    }
+
+    /// The (class, underdetermined type id) pairs that became parameters, and the parameters.
+    pub fn deferred(&self) -> Vec<((Name, Name), Name)> {
+        self.deferred.iter().flatten().map(|&(class, id, param)| ((class, id), param)).collect()
+    }
+
+    /// Syntax for the dictionary for `class` at `ty`.
+    fn dictionary(&mut self, class: Name, ty: &Ty) -> Result<Ast, TyErr> {
+        let ty = canonicalize_partially(ty, self.env.clone())?;
+        if let Some((_, _, param)) = self.givens.iter().find(|(c, t, _)| *c == class && *t == ty) {
+            return Ok(raw_ast!(VariableReference(*param)));
+        }
+        let u_f = underdetermined_form.with(|u_f| u_f.clone());
+        if let Some(u_parts) = ty.0.destructure(u_f) {
+            let id = ast_to_name(u_parts.get_leaf_or_panic(&n("id")));
+            return match self.deferred {
+                Some(ref mut deferred) => {
+                    let param = match deferred.iter().find(|(c, i, _)| *c == class && *i == id) {
+                        Some(&(_, _, param)) => param,
+                        None => {
+                            let param = Name::gensym(&format!("{}_dict", class));
+                            deferred.push((class, id, param));
+                            param
+                        }
+                    };
+                    Ok(raw_ast!(VariableReference(param)))
+                }
+                None => Err(TyErr::AmbiguousInstance(class, ty)),
+            };
+        }
+
+        let instances = self.instances;
+        for inst in instances.iter().filter(|inst| inst.class == class) {
+            // Matching the instance shouldn't decide anything about `ty`, so we might need to undo:
+            let before = unification.with(|unif| unif.borrow().clone());
+            let mut inst_env = self.env.clone();
+            for param in &inst.params {
+                inst_env = inst_env.set(*param, <Subtype as WalkMode>::underspecified(*param));
+            }
+            let inst_arg = synth_type(&inst.arg.0, inst_env.clone()).map_err(|e| e.body)?;
+            // (`sup`'s underdetermined types are the ones that get determined when both sides
+            //  are underdetermined, so `ty` only changes if the instance doesn't match.
+            // Subtyping is looser than we want, so we check for equality, too.)
+            if must_subtype(&ty, &inst_arg, self.env.clone()).is_ok()
+                && canonicalize_partially(&ty, self.env.clone())? == ty
+                && must_equal(&ty, &inst_arg, self.env.clone()).is_ok()
+            {
+                let mut givens = vec![];
+                for (g_class, g_ty) in &inst.givens {
+                    let g_ty = synth_type(&g_ty.0, inst_env.clone()).map_err(|e| e.body)?;
+                    givens.push(self.dictionary(*g_class, &g_ty)?);
+                }
+                return Ok(instance_ref(inst, givens));
+            }
+            unification.with(|unif| *unif.borrow_mut() = before);
+        }
+        Err(TyErr::NoInstance(class, ty))
+    }
+
+    /// Find all of the dictionaries for `wanteds`, for `elaborate`.
+    pub fn resolve_all(&mut self, wanteds: Vec<Wanted>) -> Result<Dictionaries, TypeError> {
+        let mut res = HashMap::new();
+        let mut unidentified = vec![];
+        // The same reference might have been typechecked more than once; the last one counts.
+        for w in wanteds.into_iter().rev() {
+            let id = w.var.id();
+            if id.map(|id| res.contains_key(&id)).unwrap_or(false) {
+                continue;
+            }
+            let mut dicts = vec![];
+            for (class, ty) in &w.constraints {
+                dicts.push(self.dictionary(*class, ty).map_err(|e| sp(e, w.var.clone()))?);
+            }
+            match id {
+                Some(id) => {
+                    res.insert(id, dicts);
+                }
+                None => unidentified.push(w.var),
+            }
+        }
+        Ok(Dictionaries { at: res, unidentified })
+    }
+}
+
+/// Where to pass which dictionaries.
+pub struct Dictionaries {
+    /// Keyed by `Ast::id`, not location: a macro can put copies of one reference
+    ///  (with one location) in places that need different instances.
+    at: HashMap<usize, Vec<Ast>>,
+    /// References that need dictionaries, but that we can't find again to rewrite.
+    /// (Only syntax that didn't go through `identify` lacks ids.)
+    pub unidentified: Vec<Ast>,
+}
+
+/// Rebuild `a`, applying `f` to every `VariableReference` (including ones under quotation).
+pub(crate) fn map_vars(a: &Ast, f: &mut dyn FnMut(&Ast) -> Ast) -> Ast {
+    match *a.c() {
+        VariableReference(_) => f(a),
+        Node(ref form, ref parts, ref export) => {
+            a.with_c(Node(form.clone(), parts.map(&mut |part| map_vars(part, f)), export.clone()))
+        }
+        ExtendEnv(ref body, ref beta) => {
+            a.with_c(ExtendEnv(Box::new(map_vars(body, f)), beta.clone()))
+        }
+        ExtendEnvPhaseless(ref body, ref beta) => {
+            a.with_c(ExtendEnvPhaseless(Box::new(map_vars(body, f)), beta.clone()))
+        }
+        QuoteMore(ref body, pos) => a.with_c(QuoteMore(Box::new(map_vars(body, f)), pos)),
+        QuoteLess(ref body, depth) => a.with_c(QuoteLess(Box::new(map_vars(body, f)), depth)),
+        _ => a.clone(),
+    }
+}
+
+/// Give every reference in `a` its own id, so that `elaborate` can find it again.
+/// This needs to happen after expansion, which can duplicate references.
+pub fn identify(a: &Ast) -> Ast { map_vars(a, &mut |var| var.identified()) }
+
+/// Pass the dictionaries to the references that need them.
+pub fn elaborate(a: &Ast, dicts: &Dictionaries) -> Ast {
+    map_vars(a, &mut |var| match var.id().and_then(|id| dicts.at.get(&id)) {
+        Some(args) => {
+            ast!({"Expr" "apply" : "rator" => (, var.clone()), "rand" => (,seq args.clone())})
+        }
+        None => var.clone(),
+    })
+}
+
+/// `.[ param ⋯ . body ].` (if there are any parameters).
+pub fn abstract_over(params: &[Name], body: Ast) -> Ast {
+    if params.is_empty() {
+        return body;
+    }
+    let inferred = crate::core_type_forms::inferred_type.with(|it| it.clone());
+    ast!({"Expr" "lambda" :
+        "param" => (@"p" ,seq params.iter().map(|p| raw_ast!(Atom(*p))).collect::<Vec<_>>()),
+        "p_t" => (@"p" ,seq params.iter().map(|_| ast!({inferred.clone() ;})).collect::<Vec<_>>()),
+        "body" => (import [* ["param" : "p_t"]] (, body))})
+}
+
+/// The runtime value of the method `name`: a function from a dictionary to the method.
+pub fn method_value(name: Name) -> crate::runtime::eval::Value {
+    use crate::runtime::eval::{RuntimeErr, RuntimeError, Value, BIF};
+    Value::BuiltInFunction(BIF(std::rc::Rc::new(move |args: Vec<Value>| match args[..] {
+        [Value::Struct(ref methods)] => match methods.find(&name) {
+            Some(method) => Ok(method.clone()),
+            None => Err(RuntimeError::new(RuntimeErr::BifArgMismatch(args.clone()))),
+        },
+        _ => Err(RuntimeError::new(RuntimeErr::BifArgMismatch(args.clone()))),
+    })))
+}
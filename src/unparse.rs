@@ -1,17 +1,17 @@
 use crate::{
-    ast::Ast::{self, *},
+    ast::{Ast, AstContents::*},
     grammar::{
         FormPat::{self, *},
         SynEnv,
     },
     name::*,
-    util::mbe::EnvMBE,
+    util::{assoc::Assoc, mbe::EnvMBE},
 };
 
-fn node_names_mentioned(pat: &FormPat) -> Vec<Name> {
+pub fn node_names_mentioned(pat: &FormPat) -> Vec<Name> {
     match *pat {
         Named(n, ref body) => {
-            let mut res = node_names_mentioned(&*body);
+            let mut res = node_names_mentioned(body);
             res.push(n);
             res
         }
@@ -26,7 +26,8 @@ fn node_names_mentioned(pat: &FormPat) -> Vec<Name> {
         | QuoteDeepen(ref body, _)
         | QuoteEscape(ref body, _)
         | Common(ref body)
-        | Reserved(ref body, _) => node_names_mentioned(&*body),
+        | Prec(ref body, _, _)
+        | Reserved(ref body, _) => node_names_mentioned(body),
         Seq(ref sub_pats) | Alt(ref sub_pats) => {
             let mut res = vec![];
             for pat in sub_pats {
@@ -35,8 +36,8 @@ fn node_names_mentioned(pat: &FormPat) -> Vec<Name> {
             res
         }
         Biased(ref lhs, ref rhs) => {
-            let mut res = node_names_mentioned(&*lhs);
-            res.append(&mut node_names_mentioned(&*rhs));
+            let mut res = node_names_mentioned(lhs);
+            res.append(&mut node_names_mentioned(rhs));
             res
         }
         Anyways(_) | Impossible | Scan(_) | Call(_) | SynImport(_, _, _) => vec![],
@@ -46,7 +47,7 @@ fn node_names_mentioned(pat: &FormPat) -> Vec<Name> {
 pub fn unparse_mbe(pat: &FormPat, actl: &Ast, context: &EnvMBE<Ast>, s: &SynEnv) -> String {
     // HACK: handle underdetermined forms
     let undet = crate::ty_compare::underdetermined_form.with(|u| u.clone());
-    match *actl {
+    match *actl.c() {
         Node(ref form, ref body, _) if form == &undet => {
             return crate::ty_compare::unification.with(|unif| {
                 let var = crate::core_forms::ast_to_name(body.get_leaf_or_panic(&n("id")));
@@ -63,10 +64,24 @@ pub fn unparse_mbe(pat: &FormPat, actl: &Ast, context: &EnvMBE<Ast>, s: &SynEnv)
         _ => {}
     }
 
+    // HACK: `type_apply` scans its `<` and `>` itself, so they aren't in the `Ast` to print:
+    if let Node(ref form, ref body, _) = *actl.c() {
+        if std::ptr::eq(pat, &*form.grammar)
+            && form == &crate::core_forms::find_core_form("Type", "type_apply")
+        {
+            let unparse_type = |t: &Ast| unparse_mbe(&Call(n("Type")), t, body, s);
+            let args: Vec<String> =
+                body.get_rep_leaf_or_panic(n("arg")).into_iter().map(unparse_type).collect();
+            let rator = unparse_type(body.get_leaf_or_panic(&n("type_rator")));
+            return format!("{}<{}>", rator, args.join(" "));
+        }
+    }
+
     // TODO: this really ought to notice when `actl` is ill-formed for `pat`.
-    match (pat, actl) {
+    match (pat, actl.c()) {
         (&Named(name, ref body), _) => {
-            unparse_mbe(&*body, context.get_leaf(name).unwrap_or(&Atom(n("<->"))), context, s)
+            let placeholder = raw_ast!(Atom(n("<->")));
+            unparse_mbe(body, context.get_leaf(name).unwrap_or(&placeholder), context, s)
         }
         //=> unparse_mbe(&*body, context.get_leaf(name).unwrap_or(&Atom(n("<MISSING>"))), context, s),
         (&Call(sub_form), _) => unparse_mbe(s.find_or_panic(&sub_form), actl, context, s),
@@ -74,24 +89,24 @@ pub fn unparse_mbe(pat: &FormPat, actl: &Ast, context: &EnvMBE<Ast>, s: &SynEnv)
         (&Literal(_, n), _) => n.print(),
         (&Scan(_), &Atom(n)) => n.print(),
         (&Scan(_), _) => "".to_string(), // HACK for `Alt`
-        (&VarRef(ref sub_form), &VariableReference(n)) => {
-            unparse_mbe(&*sub_form, &Atom(n), context, s)
+        (VarRef(sub_form), &VariableReference(n)) => {
+            unparse_mbe(sub_form, &raw_ast!(Atom(n)), context, s)
         }
         (&VarRef(_), _) => "".to_string(), // HACK for `Alt`
-        (&Seq(ref sub_pats), _) => {
+        (Seq(sub_pats), _) => {
             let mut prev_empty = true;
             let mut res = String::new();
             for sub_pat in sub_pats {
-                let sub_res = unparse_mbe(&*sub_pat, actl, context, s);
-                if !prev_empty && sub_res != "" {
+                let sub_res = unparse_mbe(sub_pat, actl, context, s);
+                if !prev_empty && !sub_res.is_empty() {
                     res.push(' ');
                 }
-                prev_empty = sub_res == "";
+                prev_empty = sub_res.is_empty();
                 res.push_str(&sub_res);
             }
             res
         }
-        (&Alt(ref sub_pats), _) => {
+        (Alt(sub_pats), _) => {
             let mut any_scopes = false;
             for sub_pat in sub_pats {
                 if let Scope(_, _) = &**sub_pat {
@@ -99,66 +114,73 @@ pub fn unparse_mbe(pat: &FormPat, actl: &Ast, context: &EnvMBE<Ast>, s: &SynEnv)
                     continue;
                 }
 
-                let sub_res = unparse_mbe(&*sub_pat, actl, context, s);
-                if sub_res != "" {
+                let sub_res = unparse_mbe(sub_pat, actl, context, s);
+                if !sub_res.is_empty() {
                     return sub_res;
                 } // HACK: should use `Option`
             }
             // HACK: certain forms don't live in the syntax environment,
             //  but "belong" under an `Alt`, so just assume forms know their grammar:
             if any_scopes {
-                if let &Node(ref form_actual, ref body, _) = actl {
-                    return unparse_mbe(&*form_actual.grammar, actl, body, s);
+                if let Node(form_actual, body, _) = actl.c() {
+                    return unparse_mbe(&form_actual.grammar, actl, body, s);
                 }
             }
 
-            return "".to_string(); // Not sure if it's an error, or really just empty
+            "".to_string()// Not sure if it's an error, or really just empty
         }
-        (&Biased(ref lhs, ref rhs), _) => {
+        (Biased(lhs, rhs), _) => {
             format!("{}{}", unparse_mbe(lhs, actl, context, s), unparse_mbe(rhs, actl, context, s))
         }
         (&Star(ref sub_pat), _) | (&Plus(ref sub_pat), _) => {
             let mut first = true;
             let mut res = String::new();
-            for marched_ctxt in context.march_all(&node_names_mentioned(&*sub_pat)) {
+            for marched_ctxt in context.march_all(&node_names_mentioned(sub_pat)) {
                 if !first {
                     res.push(' ');
                 }
                 first = false;
-                res.push_str(&unparse_mbe(&*sub_pat, actl, &marched_ctxt, s));
+                res.push_str(&unparse_mbe(sub_pat, actl, &marched_ctxt, s));
             }
             res
         }
-        (&Scope(ref form, _), &Node(ref form_actual, ref body, _)) => {
+        (Scope(form, _), Node(form_actual, body, _)) => {
             if form == form_actual {
-                unparse_mbe(&*form.grammar, actl, body, s)
+                unparse_mbe(&form.grammar, actl, body, s)
             } else {
                 "".to_string() // HACK for `Alt`
             }
         }
         (&Scope(_, _), _) => "".to_string(), // Non-match
-        (&Pick(ref body, _), _) | (&Common(ref body), _) => unparse_mbe(&*body, actl, context, s),
-        (&NameImport(ref body, _), &ExtendEnv(ref actl_body, _)) => {
-            unparse_mbe(&*body, &*actl_body, context, s)
+        (&Pick(ref body, name), _) => {
+            // Only the picked part is in `actl`; anything else (e.g. whitespace) is gone:
+            let picked = EnvMBE::new_from_leaves(Assoc::new().set(name, actl.clone()));
+            unparse_mbe(body, &raw_ast!(Trivial), &picked, s)
+        }
+        (&Common(ref body), _) | (&Prec(ref body, _, _), _) => {
+            unparse_mbe(body, actl, context, s)
+        }
+        (NameImport(body, _), ExtendEnv(actl_body, _)) => {
+            unparse_mbe(body, actl_body, context, s)
         }
         (&NameImport(_, _), _) => format!("[Missing import]→{:#?}←", actl),
-        (&NameImportPhaseless(ref body, _), &ExtendEnvPhaseless(ref actl_body, _)) => {
-            unparse_mbe(&*body, &*actl_body, context, s)
+        (NameImportPhaseless(body, _), ExtendEnvPhaseless(actl_body, _)) => {
+            unparse_mbe(body, actl_body, context, s)
         }
         (&NameImportPhaseless(_, _), _) => format!("[Missing import]±→{:#?}←±", actl),
-        (&QuoteDeepen(ref body, _), &QuoteMore(ref actl_body, _)) => {
-            unparse_mbe(&*body, &*actl_body, context, s)
+        (QuoteDeepen(body, _), QuoteMore(actl_body, _)) => {
+            unparse_mbe(body, actl_body, context, s)
         }
         (&QuoteDeepen(_, _), _) => format!("[Missing qm]{:#?}", actl),
-        (&QuoteEscape(ref body, _), &QuoteLess(ref actl_body, _)) => {
-            unparse_mbe(&*body, &*actl_body, context, s)
+        (QuoteEscape(body, _), QuoteLess(actl_body, _)) => {
+            unparse_mbe(body, actl_body, context, s)
         }
         (&QuoteEscape(_, _), _) => format!("[Missing ql]{:#?}", actl),
-        (&SynImport(ref _lhs_grammar, ref _rhs, _), &Node(_, _, _)) => {
+        (SynImport(_lhs_grammar, _rhs, _), &Node(_, _, _)) => {
             // TODO: I think we need to store the LHS or the new SynEnv to make this pretty.
             format!("?synax import? {:#?} ?si?", actl)
         }
         (&SynImport(_, _, _), _) => "".to_string(),
-        (&Reserved(ref body, _), _) => unparse_mbe(body, actl, context, s),
+        (Reserved(body, _), _) => unparse_mbe(body, actl, context, s),
     }
 }
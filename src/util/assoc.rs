@@ -6,7 +6,7 @@ extern crate im_rc;
 use self::im_rc::HashMap;
 
 thread_local! {
-    static next_id: std::cell::RefCell<u32> = std::cell::RefCell::new(0);
+    static next_id: std::cell::RefCell<u32> = const { std::cell::RefCell::new(0) };
 }
 
 fn get_next_id() -> u32 {
@@ -61,7 +61,7 @@ impl<K: Eq + Hash + Clone + Reifiable, V: Clone + Reifiable> Reifiable for Assoc
 
         extract!((v) crate::runtime::eval::Value::Sequence = (ref parts) => {
             for part in parts {
-                let (k_part, v_part) = <(K,V)>::reflect(&**part);
+                let (k_part, v_part) = <(K,V)>::reflect(part);
                 res = res.set(k_part, v_part);
             }
         });
@@ -100,7 +100,7 @@ impl<K: Eq + Hash + Clone + fmt::Display, V: Clone + fmt::Display> fmt::Display
 }
 
 impl<K: Eq + Hash + Clone, V: Clone> Assoc<K, V> {
-    fn from_hamt(hamt: HashMap<K, V>) -> Self { Assoc { hamt: hamt, id: get_next_id() } }
+    fn from_hamt(hamt: HashMap<K, V>) -> Self { Assoc { hamt, id: get_next_id() } }
 
     pub fn new() -> Self { Self::from_hamt(HashMap::new()) }
 
@@ -116,11 +116,11 @@ impl<K: Eq + Hash + Clone, V: Clone> Assoc<K, V> {
 
     pub fn empty(&self) -> bool { self.hamt.is_empty() }
 
-    pub fn iter_pairs(&self) -> im_rc::hashmap::Iter<K, V> { self.hamt.iter() }
+    pub fn iter_pairs(&self) -> im_rc::hashmap::Iter<'_, K, V> { self.hamt.iter() }
 
-    pub fn iter_keys(&self) -> im_rc::hashmap::Keys<K, V> { self.hamt.keys() }
+    pub fn iter_keys(&self) -> im_rc::hashmap::Keys<'_, K, V> { self.hamt.keys() }
 
-    pub fn iter_values(&self) -> im_rc::hashmap::Values<K, V> { self.hamt.values() }
+    pub fn iter_values(&self) -> im_rc::hashmap::Values<'_, K, V> { self.hamt.values() }
 
     pub fn map<NewV: Clone, F>(&self, mut f: F) -> Assoc<K, NewV>
     where F: FnMut(&V) -> NewV {
@@ -162,18 +162,18 @@ impl<K: Eq + Hash + Clone, V: Clone> Assoc<K, V> {
         Assoc::<K, NewV>::from_hamt(
             self.hamt
                 .clone()
-                .intersection_with_key(other.hamt.clone(), |ref k, ref v_l, ref v_r| {
+                .intersection_with_key(other.hamt.clone(), |k, ref v_l, ref v_r| {
                     f(k, v_l, v_r)
                 }),
         )
     }
 
-    pub fn find_value<'assoc, 'f>(&'assoc self, target: &'f V) -> Option<&'assoc K>
+    pub fn find_value<'assoc>(&'assoc self, target: &V) -> Option<&'assoc K>
     where V: PartialEq {
         self.hamt.iter().find(|(_, v)| v == target).map(|(k, _)| k)
     }
 
-    pub fn find_or_panic<'assoc, 'f>(&'assoc self, target: &'f K) -> &'assoc V
+    pub fn find_or_panic<'assoc>(&'assoc self, target: &K) -> &'assoc V
     where K: fmt::Debug {
         match self.find(target) {
             None => panic!("{:#?} not found in {:#?}", target, self.map(|_| "…")),
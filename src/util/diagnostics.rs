@@ -0,0 +1,231 @@
+//! Multi-line error reports that quote the offending source, rustc-style:
+//!
+//! ```text
+//! error[Mismatch]: expected `Int`, got `Bool`
+//!  --> example.≉:2:14
+//!   |
+//! 2 |    (plus one (zero? one)))
+//!   |              ^^^^^^^^^^^ this has type `Bool`
+//! ```
+//!
+//! Color is used only when printing to a terminal (and `NO_COLOR` isn't set),
+//!  so CI logs get plain text.
+
+use crate::util::source::Span;
+use std::{cell::RefCell, fmt::Write, rc::Rc};
+
+/// Secondary information, possibly pointing at some other piece of source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    pub msg: String,
+    pub span: Option<Span>,
+}
+
+/// Errors stop the program; warnings are just advice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Short machine-friendly name for the kind of error (e.g. the `TyErr` variant).
+    pub code: Option<String>,
+    pub msg: String,
+    pub span: Option<Span>,
+    /// Printed next to the underline.
+    pub label: String,
+    pub notes: Vec<Note>,
+}
+
+impl Diagnostic {
+    pub fn new(msg: String, span: Option<Span>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            code: None,
+            msg,
+            span,
+            label: String::new(),
+            notes: vec![],
+        }
+    }
+
+    pub fn warning(mut self) -> Diagnostic {
+        self.severity = Severity::Warning;
+        self
+    }
+
+    pub fn code(mut self, code: &str) -> Diagnostic {
+        self.code = Some(code.to_owned());
+        self
+    }
+
+    pub fn label(mut self, label: String) -> Diagnostic {
+        self.label = label;
+        self
+    }
+
+    pub fn note(mut self, msg: String, span: Option<Span>) -> Diagnostic {
+        self.notes.push(Note { msg, span });
+        self
+    }
+
+    pub fn render(&self, color: bool) -> String {
+        let mut out = String::new();
+        let mut lines = self.msg.lines();
+        let code = match self.code {
+            Some(ref code) => format!("[{}]", code),
+            None => String::new(),
+        };
+        let (severity, ansi) = match self.severity {
+            Severity::Error => ("error", "1;31"),
+            Severity::Warning => ("warning", "1;33"),
+        };
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            paint(&format!("{}{}", severity, code), ansi, color),
+            paint(lines.next().unwrap_or(""), "1", color)
+        );
+        for line in lines {
+            let _ = writeln!(out, "       {}", line);
+        }
+        if let Some(ref span) = self.span {
+            render_snippet(&mut out, span, &self.label, ansi, color);
+        }
+        for note in &self.notes {
+            let _ = writeln!(out, "{}: {}", paint("note", "1;36", color), note.msg);
+            if let Some(ref span) = note.span {
+                render_snippet(&mut out, span, "", "1;36", color);
+            }
+        }
+        out.trim_end().to_owned()
+    }
+}
+
+/// Warnings waiting for someone to take them. (Each `Engine` has its own.)
+pub type Warnings = Rc<RefCell<Vec<Diagnostic>>>;
+
+thread_local! {
+    /// Where `warn` puts warnings: the queue of whichever `Engine` is doing the work (if any).
+    static warnings_to: RefCell<Option<Warnings>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, putting the warnings that it reports in `queue`.
+pub fn reporting_warnings_to<T>(queue: &Warnings, f: impl FnOnce() -> T) -> T {
+    let outer = warnings_to.with(|w| w.replace(Some(queue.clone())));
+    let res = f();
+    warnings_to.with(|w| *w.borrow_mut() = outer);
+    res
+}
+
+/// Run `f`, throwing away any warnings it reports.
+/// (For re-checking code whose warnings were already reported in terms of what the user wrote.)
+pub fn without_warnings<T>(f: impl FnOnce() -> T) -> T {
+    reporting_warnings_to(&Warnings::default(), f)
+}
+
+/// Report a problem that doesn't stop anything (e.g. an unreachable `match` arm).
+/// Code is sometimes parsed or typechecked more than once; don't repeat ourselves.
+pub fn warn(warning: Diagnostic) {
+    let warning = warning.warning();
+    warnings_to.with(|w| {
+        if let Some(ref queue) = *w.borrow() {
+            let mut queue = queue.borrow_mut();
+            if !queue.contains(&warning) {
+                queue.push(warning)
+            }
+        }
+    })
+}
+
+/// Things that can be reported to the user as a `Diagnostic`.
+pub trait Diagnose {
+    fn diagnostic(&self) -> Diagnostic;
+
+    /// Some things (e.g. a parse that recovered from errors) have more than one report.
+    fn diagnostics(&self) -> Vec<Diagnostic> { vec![self.diagnostic()] }
+
+    /// Render for the console, in color if appropriate.
+    fn render(&self) -> String {
+        let color = use_color();
+        self.diagnostics().iter().map(|d| d.render(color)).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// A `Diagnostic` is already a report.
+impl Diagnose for Diagnostic {
+    fn diagnostic(&self) -> Diagnostic { self.clone() }
+}
+
+/// Whether output should be colored: only on a terminal, and only if `NO_COLOR` isn't set.
+pub fn use_color() -> bool {
+    use std::io::IsTerminal;
+    std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+}
+
+pub fn paint(s: &str, ansi: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", ansi, s)
+    } else {
+        s.to_owned()
+    }
+}
+
+/// Quote the first line of `span`, underlining the part that `span` covers.
+fn render_snippet(out: &mut String, span: &Span, label: &str, ansi: &str, color: bool) {
+    let (line, col) = span.line_col();
+    let gutter = " ".repeat(line.to_string().len());
+    let width = span.snippet().lines().next().map(|l| l.trim_end().chars().count()).unwrap_or(0);
+    let underline = "^".repeat(std::cmp::max(width, 1));
+
+    let _ = writeln!(out, "{}{} {}", gutter, paint("-->", "1;34", color), span);
+    let _ = writeln!(out, "{} {}", gutter, paint("|", "1;34", color));
+    let _ = writeln!(
+        out,
+        "{} {} {}",
+        paint(&line.to_string(), "1;34", color),
+        paint("|", "1;34", color),
+        span.line_text()
+    );
+    let underline_line = format!(
+        "{} {} {}{} {}",
+        gutter,
+        paint("|", "1;34", color),
+        " ".repeat(col - 1),
+        paint(&underline, ansi, color),
+        paint(label, ansi, color)
+    );
+    let _ = writeln!(out, "{}", underline_line.trim_end());
+}
+
+#[test]
+fn render_plain_diagnostic() {
+    let file = crate::util::source::SourceFile::new("diag.≉", "(plus one\n   (zero? one))");
+    let primary = Some(Span { file: file.clone(), begin: 10, end: 24 });
+    let diag = Diagnostic::new("expected `Int`, got `Bool`".to_owned(), primary)
+        .code("Mismatch")
+        .label("this has type `Bool`".to_owned())
+        .note("`Int` was declared here".to_owned(), Some(Span { file, begin: 6, end: 9 }))
+        .note("no location for this one".to_owned(), None);
+
+    assert_eq!(
+        diag.render(false),
+        "error[Mismatch]: expected `Int`, got `Bool`
+ --> diag.≉:2:4
+  |
+2 |    (zero? one))
+  |    ^^^^^^^^^^^ this has type `Bool`
+note: `Int` was declared here
+ --> diag.≉:1:7
+  |
+1 | (plus one
+  |       ^^^
+note: no location for this one"
+    );
+
+    // Color is just decoration:
+    assert!(diag.render(true).contains("\x1b[1;31m^^^^^^^^^^^\x1b[0m"));
+}
@@ -3,20 +3,27 @@ use std::fmt::{Debug, Display, Formatter, Result};
 custom_derive! {
     #[derive(Reifiable, Clone, PartialEq)]
     pub struct Spanned<T> {
-        pub loc: crate::ast::Ast, // TODO: implement spans!
+        pub loc: crate::ast::Ast,
         pub body: T
     }
 }
 
 pub fn sp<T>(t: T, a: crate::ast::Ast) -> Spanned<T> { Spanned { loc: a, body: t } }
 
+impl<T> Spanned<T> {
+    /// Where in the source this happened (or something inside it did, for synthesized `Ast`s).
+    pub fn span(&self) -> Option<crate::util::source::Span> { self.loc.some_loc() }
+}
+
 impl<T: Display> Display for Spanned<T> {
-    fn fmt(&self, f: &mut Formatter) -> Result { write!(f, "{} at {}", self.body, self.loc) }
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "{} at {}", self.body, self.loc.describe_location())
+    }
 }
 
 // Force pretty version
 impl<T: Display> Debug for Spanned<T> {
-    fn fmt(&self, f: &mut Formatter) -> Result { write!(f, "{} at {}", self.body, self.loc) }
+    fn fmt(&self, f: &mut Formatter) -> Result { write!(f, "{}", self) }
 }
 
 // impl<T: From<()>> From<()> for Spanned<T> {
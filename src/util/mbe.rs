@@ -120,11 +120,11 @@ impl<T: Clone + crate::runtime::reify::Reifiable> crate::runtime::reify::Reifiab
     fn reflect(v: &crate::runtime::eval::Value) -> Self {
         extract!((v) crate::runtime::eval::Value::Sequence = (ref parts) => {
             EnvMBE {
-               leaves: <Assoc<Name, T>>::reflect(&*parts[0]),
-               repeats: <Vec<Rc<Vec<EnvMBE<T>>>>>::reflect(&*parts[1]),
-               ddd_rep_idxes: <Vec<Option<usize>>>::reflect(&*parts[2]),
-               leaf_locations: <Assoc<Name, Option<usize>>>::reflect(&*parts[3]),
-               named_repeats: <Assoc<Name, Option<usize>>>::reflect(&*parts[4])
+               leaves: <Assoc<Name, T>>::reflect(&parts[0]),
+               repeats: <Vec<Rc<Vec<EnvMBE<T>>>>>::reflect(&parts[1]),
+               ddd_rep_idxes: <Vec<Option<usize>>>::reflect(&parts[2]),
+               leaf_locations: <Assoc<Name, Option<usize>>>::reflect(&parts[3]),
+               named_repeats: <Assoc<Name, Option<usize>>>::reflect(&parts[4])
             }
         })
     }
@@ -139,7 +139,7 @@ impl<T: PartialEq + Clone> PartialEq for EnvMBE<T> {
         {
             for (k, v_maybe) in lhs.iter_pairs() {
                 if let Some(ref v) = *v_maybe {
-                    if let Some(&Some(ref other_v)) = rhs.find(k) {
+                    if let Some(Some(other_v)) = rhs.find(k) {
                         if !(v == other_v) {
                             return false;
                         }
@@ -151,7 +151,7 @@ impl<T: PartialEq + Clone> PartialEq for EnvMBE<T> {
 
             for (other_k, other_v_maybe) in rhs.iter_pairs() {
                 if let Some(ref other_v) = *other_v_maybe {
-                    if let Some(&Some(ref v)) = rhs.find(other_k) {
+                    if let Some(Some(v)) = rhs.find(other_k) {
                         if !(v == other_v) {
                             return false;
                         }
@@ -218,7 +218,7 @@ impl<'a, S: Clone> DddIter<'a, S> {
         DddIter {
             underlying: und,
             cur_idx: 0,
-            rep_idx: rep_idx,
+            rep_idx,
             repeated: None,
             extra_needed: extra,
         }
@@ -388,6 +388,40 @@ impl<T: Clone> EnvMBE<T> {
         result
     }
 
+    /// Describe the contents, using `f` for the leaves. Unlike the `Debug` output,
+    ///  this doesn't depend on the order that things were added in.
+    pub fn canonical_description(&self, f: &dyn Fn(&T) -> String) -> String {
+        let mut leaves: Vec<_> = self.leaves.iter_pairs().map(|(k, v)| (k.sp(), f(v))).collect();
+        leaves.sort();
+        // Repetitions are identified by the leaves in them:
+        let mut groups: Vec<(Vec<String>, usize)> = vec![];
+        for (name, loc) in self.leaf_locations.iter_pairs() {
+            if let Some(loc) = *loc {
+                match groups.iter_mut().find(|g| g.1 == loc) {
+                    Some(group) => group.0.push(name.sp()),
+                    None => groups.push((vec![name.sp()], loc)),
+                }
+            }
+        }
+        for group in &mut groups {
+            group.0.sort();
+        }
+        groups.sort();
+
+        let mut res = String::new();
+        for (name, leaf) in leaves {
+            res.push_str(&format!("{}: {}; ", name, leaf));
+        }
+        for (names, loc) in groups {
+            res.push_str(&format!("{:?}: [", names));
+            for rep in &*self.repeats[loc] {
+                res.push_str(&format!("({}) ", rep.canonical_description(f)));
+            }
+            res.push_str("]; ");
+        }
+        res
+    }
+
     /// Get a non-repeated thing in the enviornment
     pub fn get_leaf(&self, n: Name) -> Option<&T> { self.leaves.find(&n) }
 
@@ -628,6 +662,7 @@ impl<T: Clone> EnvMBE<T> {
     // The LHS must be the side with the DDD.
     // TODO: try just using `reduced` instead of `base.clone()`
     // TODO #15: `Result` instead of panicing
+    #[allow(clippy::too_many_arguments)] // it's `map_collapse_reduce_with`'s arguments, twice
     fn match_collapse_ddd<'a, NewT: Clone>(
         lhs: &'a Rc<Vec<EnvMBE<T>>>,
         lhs_ddd: &'a Option<usize>,
@@ -718,16 +753,16 @@ impl<T: Clone> EnvMBE<T> {
             .zip(self.ddd_rep_idxes.iter())
             .zip(o.repeats.iter().zip(o.ddd_rep_idxes.iter()))
         {
-            if subs_ddd != &None && o_subs_ddd != &None {
+            if subs_ddd.is_some() && o_subs_ddd.is_some() {
                 panic!("Ill-formed; can't walk two DDDs")
             }
-            if subs_ddd == &None && o_subs_ddd == &None && subs.len() != o_subs.len() {
+            if subs_ddd.is_none() && o_subs_ddd.is_none() && subs.len() != o_subs.len() {
                 return false;
             }
-            if subs_ddd != &None && o_subs.len() < subs.len() - 1 {
+            if subs_ddd.is_some() && o_subs.len() < subs.len() - 1 {
                 return false;
             }
-            if o_subs_ddd != &None && subs.len() < o_subs.len() - 1 {
+            if o_subs_ddd.is_some() && subs.len() < o_subs.len() - 1 {
                 return false;
             }
 
@@ -760,10 +795,7 @@ impl<T: Clone> EnvMBE<T> {
                 .iter()
                 .zip(self.ddd_rep_idxes.iter())
                 .zip(o.repeats.iter().zip(o.ddd_rep_idxes.iter()))
-                .map(&|((rc_vec_mbe, ddd_idx), (o_rc_vec_mbe, o_ddd_idx)): (
-                    (&Rc<Vec<EnvMBE<T>>>, &Option<usize>),
-                    (&Rc<Vec<EnvMBE<T>>>, &Option<usize>),
-                )| {
+                .map(|((rc_vec_mbe, ddd_idx), (o_rc_vec_mbe, o_ddd_idx))| {
                     let mapped: Vec<_> =
                         Self::resolve_ddd(rc_vec_mbe, ddd_idx, o_rc_vec_mbe, o_ddd_idx)
                             .iter()
@@ -890,6 +922,7 @@ impl<T: Clone> EnvMBE<T> {
     }
 
     // If `f` turns a leaf into a `Vec`, splice those results in
+    #[allow(clippy::type_complexity)] // (`f`'s type is the interface)
     pub fn heal_splices<E>(
         &mut self,
         f: &dyn Fn(&T) -> Result<Option<Vec<T>>, E>,
@@ -933,6 +966,7 @@ impl<T: Clone> EnvMBE<T> {
     }
 
     // TODO: this should return a usable error
+    #[allow(clippy::type_complexity)] // (`f`'s type is the interface)
     pub fn heal_splices__with<E>(
         &mut self,
         other: &EnvMBE<T>,
@@ -950,7 +984,7 @@ impl<T: Clone> EnvMBE<T> {
             let other__rep_loc = other.leaf_locations.find(names_needed[0]).unwrap().unwrap();
             // TODO: `Err` if we don't get the same result for all `names_needed[n]`
 
-            let other__cur_repeat: &Vec<EnvMBE<T>> = &*other.repeats[other__rep_loc];
+            let other__cur_repeat: &Vec<EnvMBE<T>> = &other.repeats[other__rep_loc];
             let mut cur_repeat: Vec<EnvMBE<T>> = (**repeat).clone();
 
             // If an item splices, how wide does the other side need to be
@@ -968,13 +1002,10 @@ impl<T: Clone> EnvMBE<T> {
                     let n_and_vals = cur_repeat[i].leaves.iter_pairs();
                     for (n, val) in n_and_vals {
                         let concrete_splice__thunk = || {
-                            let mut result = vec![];
-                            for spliced_i in i..i + splice_length {
-                                result.push(
-                                    other__cur_repeat[spliced_i].leaves.find_or_panic(n).clone(),
-                                )
-                            }
-                            result
+                            other__cur_repeat[i..i + splice_length]
+                                .iter()
+                                .map(|spliced| spliced.leaves.find_or_panic(n).clone())
+                                .collect()
                         };
 
                         if let Some(splice) = f(val, &concrete_splice__thunk)? {
@@ -1029,8 +1060,8 @@ impl<T: Clone, E: Clone> EnvMBE<Result<T, E>> {
         }
 
         Ok(EnvMBE {
-            leaves: leaves,
-            repeats: repeats,
+            leaves,
+            repeats,
             ddd_rep_idxes: self.ddd_rep_idxes.clone(),
             leaf_locations: self.leaf_locations.clone(),
             named_repeats: self.named_repeats.clone(),
@@ -1075,7 +1106,7 @@ impl<T: Clone + fmt::Debug> EnvMBE<T> {
 #[test]
 fn basic_mbe() {
     let mut mbe = EnvMBE::new();
-    mbe.add_leaf(n("eight"), 8 as i32);
+    mbe.add_leaf(n("eight"), 8_i32);
     mbe.add_leaf(n("nine"), 9);
 
     assert!(mbe != EnvMBE::new());
@@ -1096,14 +1127,14 @@ fn basic_mbe() {
 
     mbe.add_anon_repeat(big_mbe, None);
 
-    for (sub_mbe, teen) in mbe.march_all(&vec![n("t"), n("eight")]).iter().zip(vec![11, 12, 13]) {
+    for (sub_mbe, teen) in mbe.march_all(&[n("t"), n("eight")]).iter().zip(vec![11, 12, 13]) {
         assert_eq!(sub_mbe.get_leaf(n("eight")), Some(&8));
         assert_eq!(sub_mbe.get_leaf(n("nine")), Some(&9));
         assert_eq!(sub_mbe.get_leaf(n("t")), Some(&teen));
         assert_eq!(sub_mbe.get_leaf(n("y")), None);
 
         for (sub_sub_mbe, big) in
-            sub_mbe.march_all(&vec![n("y"), n("eight")]).iter().zip(vec![9001, 9002])
+            sub_mbe.march_all(&[n("y"), n("eight")]).iter().zip(vec![9001, 9002])
         {
             assert_eq!(sub_sub_mbe.get_leaf(n("eight")), Some(&8));
             assert_eq!(sub_sub_mbe.get_leaf(n("nine")), Some(&9));
@@ -1121,7 +1152,7 @@ fn basic_mbe() {
     mbe.add_named_repeat(n("low_two_digits"), neg_teens_mbe, None);
 
     for (sub_mbe, teen) in
-        mbe.march_all(&vec![n("t"), n("nt"), n("eight")]).iter().zip(vec![11, 12, 13])
+        mbe.march_all(&[n("t"), n("nt"), n("eight")]).iter().zip(vec![11, 12, 13])
     {
         assert_eq!(sub_mbe.get_leaf(n("eight")), Some(&8));
         assert_eq!(sub_mbe.get_leaf(n("nine")), Some(&9));
@@ -1129,7 +1160,7 @@ fn basic_mbe() {
         assert_eq!(sub_mbe.get_leaf(n("nt")), Some(&-teen));
 
         for (sub_sub_mbe, big) in
-            sub_mbe.march_all(&vec![n("y"), n("eight")]).iter().zip(vec![9001, 9002])
+            sub_mbe.march_all(&[n("y"), n("eight")]).iter().zip(vec![9001, 9002])
         {
             assert_eq!(sub_sub_mbe.get_leaf(n("eight")), Some(&8));
             assert_eq!(sub_sub_mbe.get_leaf(n("nine")), Some(&9));
@@ -1140,14 +1171,14 @@ fn basic_mbe() {
     }
 
     let all_zeroes = mbe.map_with(&mbe, &|a, b| a - b);
-    for sub_mbe in all_zeroes.march_all(&vec![n("t"), n("nt"), n("eight")]) {
+    for sub_mbe in all_zeroes.march_all(&[n("t"), n("nt"), n("eight")]) {
         assert_eq!(sub_mbe.get_leaf(n("eight")), Some(&0));
         assert_eq!(sub_mbe.get_leaf(n("nine")), Some(&0));
         assert_eq!(sub_mbe.get_leaf(n("t")), Some(&0));
         assert_eq!(sub_mbe.get_leaf(n("nt")), Some(&0));
 
         for (sub_sub_mbe, _) in
-            sub_mbe.march_all(&vec![n("y"), n("eight")]).iter().zip(vec![9001, 9002])
+            sub_mbe.march_all(&[n("y"), n("eight")]).iter().zip(vec![9001, 9002])
         {
             assert_eq!(sub_sub_mbe.get_leaf(n("eight")), Some(&0));
             assert_eq!(sub_sub_mbe.get_leaf(n("nine")), Some(&0));
@@ -1159,7 +1190,7 @@ fn basic_mbe() {
 
     assert_eq!(mbe, mbe);
     assert!(mbe != mbe.map(&mut |x| x - 1));
-    assert_eq!(mbe, mbe.map(&mut |x| x - 0));
+    assert_eq!(mbe, mbe.map(&mut |x| *x));
     assert!(mbe != EnvMBE::new());
     assert!(EnvMBE::new() != mbe);
 
@@ -1170,7 +1201,7 @@ fn basic_mbe() {
         mbe.map_reduce_with(
             &all_zeroes,
             &|a, b| if *a < *b { *a } else { *b },
-            &|a, b| (*a + *b),
+            &|a, b| *a + *b,
             0
         ),
         -11 + -12 + -13
@@ -1187,7 +1218,7 @@ fn basic_mbe() {
 
     let mapped_mbe = mbe.map(&mut |x: &i32| (*x, *x - 9000));
 
-    let first_sub_mbe = &mapped_mbe.march_all(&vec![n("y")])[0];
+    let first_sub_mbe = &mapped_mbe.march_all(&[n("y")])[0];
 
     assert_eq!(first_sub_mbe.get_leaf(n("y")), Some(&(9001, 1)));
     assert_eq!(first_sub_mbe.get_leaf(n("eight")), Some(&(8, (8 - 9000))));
@@ -1204,11 +1235,11 @@ fn basic_mbe() {
         EnvMBE::new_from_anon_repeat(vec![teens_with_outer, nothing_with_other]);
 
     let mut output = vec![];
-    for outer in teens_and_nothing.march_all(&vec![n("outer")]) {
-        for inner in outer.march_all(&vec![n("t")]) {
+    for outer in teens_and_nothing.march_all(&[n("outer")]) {
+        for inner in outer.march_all(&[n("t")]) {
             output.push((
-                inner.get_leaf(n("outer")).map(|x| x.clone()),
-                inner.get_leaf(n("t")).map(|x| x.clone()),
+                inner.get_leaf(n("outer")).copied(),
+                inner.get_leaf(n("t")).copied(),
             ));
         }
     }
@@ -1300,8 +1331,8 @@ fn mbe_ddd_map_with() {
     let rhs = mbe!( "a" => ["0" ...("1")..., "4"] );
 
     fn concat(l: &Ast, r: &Ast) -> Ast {
-        match (l, r) {
-            (&Atom(ln), &Atom(rn)) => Atom(n(format!("{}{}", ln, rn).as_str())),
+        match (l.c(), r.c()) {
+            (&Atom(ln), &Atom(rn)) => raw_ast!(Atom(n(format!("{}{}", ln, rn).as_str()))),
             _ => panic!(),
         }
     }
@@ -1334,7 +1365,7 @@ fn mbe_ddd_map_with() {
             lhs.map_collapse_reduce_with(
                 &rhs,
                 &concat,
-                &|v| if v.len() > 0 { v[0].clone() } else { ast!("") },
+                &|v| if !v.is_empty() { v[0].clone() } else { ast!("") },
                 &|l, r| concat(&l, &r),
                 ast!("")
             ),
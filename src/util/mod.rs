@@ -1,3 +1,5 @@
 pub mod assoc;
+pub mod diagnostics;
 pub mod err;
 pub mod mbe;
+pub mod source;
@@ -0,0 +1,107 @@
+//! Source text that `Ast`s can point back into.
+//! The parser records a `Span` (a byte range in some source file) on the `Ast`s it builds,
+//!  and errors use it to say `file:line:col` instead of pretty-printing the offending `Ast`.
+
+use std::{fmt, rc::Rc};
+
+/// Some source text, and where it came from.
+/// `Span`s keep it alive, so it goes away along with the last `Ast` that points into it.
+pub struct SourceFile {
+    pub name: String,
+    pub text: Rc<str>,
+}
+
+impl SourceFile {
+    pub fn new(name: &str, text: &str) -> Rc<SourceFile> {
+        Rc::new(SourceFile { name: name.to_owned(), text: Rc::from(text) })
+    }
+}
+
+/// A range of bytes in a source file.
+#[derive(Clone)]
+pub struct Span {
+    pub file: Rc<SourceFile>,
+    pub begin: usize,
+    pub end: usize,
+}
+
+// Two spans are the same if they point at the same text in the same file
+//  (not merely in a file with the same contents).
+impl PartialEq for Span {
+    fn eq(&self, other: &Span) -> bool {
+        Rc::ptr_eq(&self.file, &other.file) && self.begin == other.begin && self.end == other.end
+    }
+}
+
+impl Eq for Span {}
+
+impl std::hash::Hash for Span {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.file).hash(state);
+        self.begin.hash(state);
+        self.end.hash(state);
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{}..{}]", self.file.name, self.begin, self.end)
+    }
+}
+
+impl Span {
+    /// The span's start, skipping leading whitespace (the parser includes it in `Node`s).
+    fn first_interesting_byte(&self, text: &str) -> usize {
+        let chunk = &text[self.begin..self.end];
+        self.begin + (chunk.len() - chunk.trim_start().len())
+    }
+
+    /// 1-based line and column (in `char`s) of the start of the span.
+    pub fn line_col(&self) -> (usize, usize) {
+        let text = &self.file.text;
+        let begin = self.first_interesting_byte(text);
+        let line_begin = text[..begin].rfind('\n').map(|n| n + 1).unwrap_or(0);
+        (text[..begin].matches('\n').count() + 1, text[line_begin..begin].chars().count() + 1)
+    }
+
+    /// The full text of the line the span starts on.
+    pub fn line_text(&self) -> String {
+        let text = &self.file.text;
+        let begin = self.first_interesting_byte(text);
+        let line_begin = text[..begin].rfind('\n').map(|n| n + 1).unwrap_or(0);
+        let line_end = text[begin..].find('\n').map(|n| n + begin).unwrap_or(text.len());
+        text[line_begin..line_end].to_owned()
+    }
+
+    /// The source text covered by the span.
+    pub fn snippet(&self) -> String {
+        let text = &self.file.text;
+        text[self.first_interesting_byte(text)..self.end].to_owned()
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (line, col) = self.line_col();
+        write!(f, "{}:{}:{}", self.file.name, line, col)
+    }
+}
+
+#[test]
+fn span_line_col() {
+    let file = SourceFile::new("test.≉", "one\n  two three\nfour");
+    let span = Span { file: file.clone(), begin: 9, end: 15 };
+    assert_eq!(span.line_col(), (2, 7));
+    assert_eq!(span.line_text(), "  two three");
+    assert_eq!(span.snippet(), "three");
+    assert_eq!(format!("{}", span), "test.≉:2:7");
+
+    // Leading whitespace (which `Node` spans include) is skipped:
+    let span = Span { file: file.clone(), begin: 3, end: 7 };
+    assert_eq!(span.line_col(), (2, 3));
+
+    // Same place, but a different file:
+    let elsewhere = SourceFile::new("test.≉", "one\n  two three\nfour");
+    assert_ne!(span, Span { file: elsewhere, begin: 3, end: 7 });
+    assert_eq!(span, Span { file, begin: 3, end: 7 });
+}
@@ -1,6 +1,6 @@
 use crate::{
     alpha::{freshen, freshen_with},
-    ast::Ast::{self, *},
+    ast::{Ast, AstContents::*},
     ast_walk::{walk, Clo, LazyWalkReses, OutEnvHandle, WalkRule},
     form::Form,
     name::*,
@@ -167,12 +167,12 @@ impl<Mode: WalkMode<D = Self>> Dir for Positive<Mode> {
     }
 
     fn walk_quasi_literally(a: Ast, cnc: &LazyWalkReses<Self::Mode>) -> Res<Self::Mode> {
-        match a {
+        match a.c() {
             Node(f, parts, exports) => {
                 let mut walked: EnvMBE<Ast> = parts
                     .map_marched_against(
                         &mut |p: &Ast, cnc_m: &LazyWalkReses<Self::Mode>| {
-                            match *p {
+                            match *p.c() {
                                 // Yes, `walk`, not `w_q_l`;
                                 //  the mode is in charge of figuring things out.
                                 Node(_, _, _)
@@ -189,7 +189,7 @@ impl<Mode: WalkMode<D = Self>> Dir for Positive<Mode> {
 
                 // HACK: recognize `Shape` as the output of `core_qq_forms::dotdotdot`:
                 walked
-                    .heal_splices::<()>(&|a| match a {
+                    .heal_splices::<()>(&|a| match a.c() {
                         Shape(ref v) => Ok(Some(v.clone())),
                         _ => Ok(None),
                     })
@@ -198,42 +198,48 @@ impl<Mode: WalkMode<D = Self>> Dir for Positive<Mode> {
                 // TODO: it should be a type error (or at least an obvious runtime error)
                 // to put a splice (i.e. a `...[]...`) somewhere it can't be healed.
 
-                Ok(<Self::Mode as WalkMode>::Elt::from_ast(&Node(f, walked, exports)))
+                Ok(<Self::Mode as WalkMode>::Elt::from_ast(&a.with_c(Node(
+                    f.clone(),
+                    walked,
+                    exports.clone(),
+                ))))
             }
-            orig => {
+            _ => {
                 // All this mess is to push `Shape` down past a wrapper (i.e. `ExtendEnv`),
                 //  duplicating the wrapper around each element of `Shape`.
                 // This is all for splicing the result of `dotdotdot`
 
-                let body = match orig {
+                let body = match a.c() {
                     ExtendEnv(ref b, _)
                     | ExtendEnvPhaseless(ref b, _)
                     | QuoteMore(ref b, _)
                     | QuoteLess(ref b, _) => b,
                     _ => icp!(),
                 };
-                let sub_result = Mode::Elt::to_ast(&walk(&**body, cnc)?);
+                let sub_result = Mode::Elt::to_ast(&walk(body, cnc)?);
 
                 fn handle_wrapper<Mode: WalkMode>(orig: &Ast, a: Ast) -> Ast {
                     let boxed = Box::new(a);
-                    match orig {
+                    match orig.c() {
                         // Environment extension is handled at `walk`
-                        ExtendEnv(_, beta) => ExtendEnv(boxed, beta.clone()),
-                        ExtendEnvPhaseless(_, beta) => ExtendEnvPhaseless(boxed, beta.clone()),
-                        QuoteMore(_, pos) => QuoteMore(boxed, *pos),
-                        QuoteLess(_, depth) => QuoteLess(boxed, *depth),
+                        ExtendEnv(_, beta) => orig.with_c(ExtendEnv(boxed, beta.clone())),
+                        ExtendEnvPhaseless(_, beta) => {
+                            orig.with_c(ExtendEnvPhaseless(boxed, beta.clone()))
+                        }
+                        QuoteMore(_, pos) => orig.with_c(QuoteMore(boxed, *pos)),
+                        QuoteLess(_, depth) => orig.with_c(QuoteLess(boxed, *depth)),
                         _ => icp!(),
                     }
                 }
 
-                let res: Ast = match sub_result {
-                    Shape(sub_results) => Shape(
+                let res: Ast = match sub_result.c() {
+                    Shape(sub_results) => raw_ast!(Shape(
                         sub_results
-                            .into_iter()
-                            .map(|sub| handle_wrapper::<Self::Mode>(&orig, sub))
+                            .iter()
+                            .map(|sub| handle_wrapper::<Self::Mode>(&a, sub.clone()))
                             .collect(),
-                    ),
-                    sub_result => handle_wrapper::<Self::Mode>(&orig, sub_result),
+                    )),
+                    _ => handle_wrapper::<Self::Mode>(&a, sub_result.clone()),
                 };
 
                 Ok(Mode::Elt::from_ast(&res))
@@ -284,19 +290,23 @@ impl<Mode: WalkMode<D = Self> + NegativeWalkMode> Dir for Negative<Mode> {
             }
             // HACK: force walking to automatically succeed, avoiding return type muckery
             None => (
-                Atom(negative_ret_val()),
-                cnc.with_context(<Self::Mode as WalkMode>::Elt::from_ast(&Trivial)),
+                raw_ast!(Atom(negative_ret_val())),
+                cnc.with_context(<Self::Mode as WalkMode>::Elt::from_ast(&raw_ast!(Trivial))),
             ),
         }
     }
 
     fn walk_quasi_literally(expected: Ast, cnc: &LazyWalkReses<Self::Mode>) -> Res<Self::Mode> {
+        // `walk` has already removed the corresponding wrapper from the context element:
+        if let ExtendEnv(ref body, _) | ExtendEnvPhaseless(ref body, _) = expected.c() {
+            return walk(body, cnc);
+        }
         let got = <Mode::Elt as WalkElt>::to_ast(&cnc.context_elt().clone());
 
         let parts_actual = Mode::context_match(&expected, &got, cnc.env.clone())?;
 
         let its_a_trivial_ast = EnvMBE::new(); // No more walking to do
-        let expd_parts = match expected {
+        let expd_parts = match expected.c() {
             Node(_, ref p, _) => p,
             _ => &its_a_trivial_ast,
         };
@@ -306,7 +316,7 @@ impl<Mode: WalkMode<D = Self> + NegativeWalkMode> Dir for Negative<Mode> {
         //  so that matching DDDed syntax won't go horribly wrong
         expd_parts.map_collapse_reduce_with(
             &parts_actual,
-            &|model: &Ast, actual: &Ast| match *model {
+            &|model: &Ast, actual: &Ast| match *model.c() {
                 Node(_, _, _)
                 | VariableReference(_)
                 | ExtendEnv(_, _)
@@ -357,6 +367,7 @@ pub trait NegativeWalkMode: WalkMode {
 
     /// Before matching, possibly adjust the two `Elt`s to match better. (`None` is auto-match.)
     /// By default, a no-op.
+    #[allow(clippy::type_complexity)]
     fn pre_match(
         expected: Self::Elt,
         got: Self::Elt,
@@ -377,9 +388,9 @@ pub trait NegativeWalkMode: WalkMode {
     ) -> Result<EnvMBE<Ast>, <Self as WalkMode>::Err>
     {
         // break apart the node, and walk it element-wise
-        match (expected, got) {
+        match (expected.c(), got.c()) {
             // `pre_walk` has already freshened for us
-            (&Node(ref f, _, _), &Node(ref f_actual, ref parts_actual, _)) if *f == *f_actual => {
+            (Node(f, _, _), Node(f_actual, parts_actual, _)) if *f == *f_actual => {
                 Ok(parts_actual.clone())
             }
             _ => {
@@ -398,7 +409,7 @@ pub trait NegativeWalkMode: WalkMode {
 
 /// `var_to_out`, for positive walks where `Out` == `Elt`
 pub fn var_lookup<Elt: Debug + Clone>(n: Name, env: &Assoc<Name, Elt>) -> Result<Elt, ()> {
-    Ok((*env.find(&n).unwrap_or_else(|| panic!(format!("Name {:#?} unbound in {:#?}", n, env))))
+    Ok((*env.find(&n).unwrap_or_else(|| panic!("Name {:#?} unbound in {:#?}", n, env)))
         .clone())
 }
 